@@ -0,0 +1,125 @@
+use bevy::{
+    ecs::schedule::ExecutorKind,
+    prelude::{in_state, IntoSystemConfigs, Schedule, State, States, World},
+};
+use bevy_state_matching_prototype::{
+    apply_state_transition, NextMatchableState, StateMatchingSystems,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+enum AppState {
+    #[default]
+    Menu,
+    InGame {
+        paused: bool,
+    },
+}
+
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+struct Numbered<const N: usize>(bool);
+
+const SYSTEM_COUNTS: [usize; 3] = [10, 100, 1000];
+
+fn gated_schedule(count: usize, gate: impl Fn(&mut Schedule)) -> (World, Schedule) {
+    let mut world = World::new();
+    world.insert_resource(State::new(AppState::InGame { paused: false }));
+    let mut schedule = Schedule::default();
+    schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    for _ in 0..count {
+        gate(&mut schedule);
+    }
+    schedule.run(&mut world);
+    (world, schedule)
+}
+
+fn run_in_evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("run_in_evaluation");
+    for count in SYSTEM_COUNTS {
+        group.bench_with_input(BenchmarkId::new("in_state", count), &count, |b, &count| {
+            let (mut world, mut schedule) = gated_schedule(count, |schedule| {
+                schedule.add_systems(
+                    (|| {
+                        black_box(());
+                    })
+                    .run_if(in_state(AppState::InGame { paused: false })),
+                );
+            });
+            b.iter(|| schedule.run(&mut world));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("run_in_value", count),
+            &count,
+            |b, &count| {
+                let (mut world, mut schedule) = gated_schedule(count, |schedule| {
+                    schedule.add_systems(
+                        (|| {
+                            black_box(());
+                        })
+                        .run_in(AppState::InGame { paused: false }),
+                    );
+                });
+                b.iter(|| schedule.run(&mut world));
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("run_in_fn", count), &count, |b, &count| {
+            let (mut world, mut schedule) = gated_schedule(count, |schedule| {
+                schedule.add_systems(
+                    (|| {
+                        black_box(());
+                    })
+                    .run_in(|state: &AppState| matches!(state, AppState::InGame { .. })),
+                );
+            });
+            b.iter(|| schedule.run(&mut world));
+        });
+    }
+    group.finish();
+}
+
+fn add_numbered<const N: usize>(world: &mut World, schedule: &mut Schedule) {
+    world.init_resource::<State<Numbered<N>>>();
+    world.init_resource::<NextMatchableState<Numbered<N>>>();
+    schedule.add_systems(apply_state_transition::<Numbered<N>>);
+}
+
+fn toggle_numbered<const N: usize>(world: &mut World) {
+    world
+        .resource_mut::<NextMatchableState<Numbered<N>>>()
+        .setter(|Numbered(value)| Numbered(!value));
+}
+
+fn state_transitions(c: &mut Criterion) {
+    let mut world = World::new();
+    let mut schedule = Schedule::default();
+    schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    add_numbered::<0>(&mut world, &mut schedule);
+    add_numbered::<1>(&mut world, &mut schedule);
+    add_numbered::<2>(&mut world, &mut schedule);
+    add_numbered::<3>(&mut world, &mut schedule);
+    add_numbered::<4>(&mut world, &mut schedule);
+    add_numbered::<5>(&mut world, &mut schedule);
+    add_numbered::<6>(&mut world, &mut schedule);
+    add_numbered::<7>(&mut world, &mut schedule);
+    schedule.run(&mut world);
+
+    let mut group = c.benchmark_group("apply_state_transition");
+    group.bench_function("8_states_idle", |b| b.iter(|| schedule.run(&mut world)));
+    group.bench_function("8_states_transitioning", |b| {
+        b.iter(|| {
+            toggle_numbered::<0>(&mut world);
+            toggle_numbered::<1>(&mut world);
+            toggle_numbered::<2>(&mut world);
+            toggle_numbered::<3>(&mut world);
+            toggle_numbered::<4>(&mut world);
+            toggle_numbered::<5>(&mut world);
+            toggle_numbered::<6>(&mut world);
+            toggle_numbered::<7>(&mut world);
+            schedule.run(&mut world);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, run_in_evaluation, state_transitions);
+criterion_main!(benches);
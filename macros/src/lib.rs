@@ -1,14 +1,24 @@
 extern crate proc_macro;
 
 mod state_matchers;
+mod state_tags;
+mod state_transitions;
 use proc_macro::TokenStream;
-use state_matchers::state_matches_macro;
+use state_matchers::{define_matcher_macro, state_matches_macro};
+use syn::{parse_macro_input, DeriveInput};
 
 /// Run a system only if the current state matches the provided expressions.
 ///
 /// This can be done by:
 /// - using matching pattern, like so `state_matches!(AppState, InGame { .. })`. Note that when matching
 /// enums, you do  not need to repeat the type within the pattern.
+/// - using a matching pattern followed by a closure taking its bindings, like so
+/// `state_matches!(AppState, Playing { level, .. } => |level| *level > 3)`. The closure's parameters
+/// have to be named after the bindings they take.
+/// - using a transition between two patterns, like so `state_matches!(AppState, MainMenu => InGame { .. })`.
+/// This returns `TransitionMatches` when the main state matches the second pattern and the other side of
+/// the transition matches the first, and `MainMatches` when only the main state matches. When `Entering`, the main
+/// state is the entered one - use `invert_transition` to read it the same way when `Exiting`.
 /// - using a closure with a type that automatically implements `StateMatcher<S>`, like so `state_matches!(AppState, |state| { /// some logic here - return a bool})`
 /// - using an expression preceded by a `=`, like so `state_matches!(=AppState::Menu)`
 ///
@@ -19,3 +29,40 @@ pub fn state_matches(input: TokenStream) -> TokenStream {
         state_matchers::define_match_macro(input).expect("Couldn't parse `state_matches!`");
     state_matches_macro(result)
 }
+
+/// Define a named, `Copy` state matcher type, so a matcher can be stored in constants and reused
+/// across modules rather than expanding `state_matches!` at every call site.
+///
+/// Takes the name of the type (optionally preceded by attributes and a visibility),
+/// followed by the same input as `state_matches!` - like so
+/// `define_matcher!(pub PausedMatcher, AppState, InGame { paused: true, .. })`.
+#[proc_macro]
+pub fn define_matcher(input: TokenStream) -> TokenStream {
+    define_matcher_macro(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implement `TaggedState`, using the tags listed in `#[state_tags(...)]` attributes.
+///
+/// Tags on the type apply to every variant, and tags on a variant only apply to that variant.
+#[proc_macro_derive(StateTags, attributes(state_tags))]
+pub fn derive_state_tags(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    state_tags::derive_state_tags(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implement `DeclaredTransitions`, using the edges listed in `#[states(transitions(...))]` attributes.
+///
+/// Each edge is written as `From -> To`, naming the variants without the type - followed by their
+/// fields if they have any, like `Menu -> InGame { paused: false }`. Use `*` as the source to allow
+/// transitioning from any state.
+#[proc_macro_derive(StateTransitions, attributes(states))]
+pub fn derive_state_transitions(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    state_transitions::derive_state_transitions(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
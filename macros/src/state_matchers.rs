@@ -3,7 +3,9 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::Error;
 use syn::ExprClosure;
-use syn::{parse::Parse, Expr, ExprPath, Ident, Pat, PatTupleStruct, Path, Token};
+use syn::{
+    parse::Parse, Attribute, Expr, ExprPath, Ident, Pat, PatTupleStruct, Path, Token, Visibility,
+};
 
 fn bevy_ecs_path() -> Path {
     format_ident!("bevy_state_matching_prototype").into()
@@ -13,6 +15,17 @@ fn bevy_ecs_path() -> Path {
 
 struct MatcherPattern {
     pattern: Pat,
+    suffix: PatternSuffix,
+}
+
+#[derive(Clone)]
+enum PatternSuffix {
+    /// Just the pattern
+    None,
+    /// `pattern => |bindings| expression`
+    Bindings(ExprClosure),
+    /// `from => to`, where `to` is matched against the main state
+    Transition(Pat),
 }
 
 #[derive(Clone)]
@@ -140,7 +153,29 @@ impl MatcherPattern {
 
         let pattern = inject_state_type(pattern, state_type);
 
-        Ok(Self { pattern })
+        let suffix = if !input.peek(Token![=>]) {
+            PatternSuffix::None
+        } else {
+            input.parse::<Token![=>]>()?;
+            if input.peek(Token![|]) || input.peek(Token![move]) {
+                PatternSuffix::Bindings(ExprClosure::parse(input).map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        format!("Expected a closure taking the pattern's bindings: {e:?}"),
+                    )
+                })?)
+            } else {
+                let to = Pat::parse_multi_with_leading_vert(input).map_err(|e| {
+                    syn::Error::new(
+                        e.span(),
+                        format!("Couldn't parse the target pattern: {e:?}"),
+                    )
+                })?;
+                PatternSuffix::Transition(inject_state_type(to, state_type))
+            }
+        };
+
+        Ok(Self { pattern, suffix })
     }
 }
 
@@ -249,6 +284,7 @@ pub enum MatchTypes {
     Expression(TokenStream),
     Pattern(TokenStream),
     Closure(TokenStream),
+    Transition(TokenStream, TokenStream),
 }
 
 impl MatchTypes {
@@ -262,10 +298,39 @@ impl MatchTypes {
                         #exp
                     )),
                 ),
-                (every, MatcherType::Pattern(MatcherPattern { pattern })) => (
+                (
+                    every,
+                    MatcherType::Pattern(MatcherPattern {
+                        pattern,
+                        suffix: PatternSuffix::None,
+                    }),
+                ) => (
                     *every,
                     MatchTypes::Pattern(quote!(matches!(state, #pattern))),
                 ),
+                (
+                    every,
+                    MatcherType::Pattern(MatcherPattern {
+                        pattern,
+                        suffix: PatternSuffix::Bindings(closure),
+                    }),
+                ) => (
+                    *every,
+                    MatchTypes::Pattern(pattern_with_bindings(pattern, closure)),
+                ),
+                (
+                    every,
+                    MatcherType::Pattern(MatcherPattern {
+                        pattern,
+                        suffix: PatternSuffix::Transition(to),
+                    }),
+                ) => (
+                    *every,
+                    MatchTypes::Transition(
+                        quote!(matches!(state, #pattern)),
+                        quote!(matches!(state, #to)),
+                    ),
+                ),
                 (every, MatcherType::Closure(MatcherClosure { closure: pattern })) => {
                     (*every, MatchTypes::Closure(quote!(#pattern)))
                 }
@@ -274,6 +339,37 @@ impl MatchTypes {
     }
 }
 
+/// Expand `pattern => |bindings| body` into a match that runs `body` with the pattern's bindings in scope.
+///
+/// The closure's parameters have to be named after the bindings they take, and are re-bound
+/// with `let` so any type annotations on them still apply.
+fn pattern_with_bindings(pattern: &Pat, closure: &ExprClosure) -> TokenStream {
+    let inputs = closure.inputs.iter();
+    let names = closure.inputs.iter().map(|input| {
+        let pat = match input {
+            Pat::Type(typed) => typed.pat.as_ref(),
+            pat => pat,
+        };
+        match pat {
+            Pat::Ident(ident) => {
+                let ident = &ident.ident;
+                quote!(#ident)
+            }
+            pat => quote!(#pat),
+        }
+    });
+    let body = &closure.body;
+    quote!(
+        match state {
+            #pattern => {
+                #(let #inputs = #names;)*
+                #body
+            }
+            _ => false,
+        }
+    )
+}
+
 pub fn define_match_macro(input: proc_macro::TokenStream) -> syn::Result<MatchMacroResult> {
     let matcher = syn::parse::<Matcher>(input)
         .map_err(|e| Error::new(e.span(), format!("Attempting to parse matcher: {e:?}")))?;
@@ -292,6 +388,63 @@ pub fn define_match_macro(input: proc_macro::TokenStream) -> syn::Result<MatchMa
     })
 }
 
+/// The input of `define_matcher!` - a type name, followed by the same input as `state_matches!`
+struct DefinedMatcher {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    matcher: Matcher,
+}
+
+impl Parse for DefinedMatcher {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let matcher = input.parse()?;
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            matcher,
+        })
+    }
+}
+
+pub fn define_matcher_macro(input: proc_macro::TokenStream) -> syn::Result<TokenStream> {
+    let DefinedMatcher {
+        attrs,
+        vis,
+        name,
+        matcher,
+    } = syn::parse::<DefinedMatcher>(input)?;
+    let Some(state_type) = matcher.state_type else {
+        return Err(Error::new(Span::call_site(), "No State Type"));
+    };
+    if matcher.matchers.is_empty() {
+        return Err(Error::new(Span::call_site(), "No matcher statements found"));
+    }
+    let matchers = MatchTypes::from_matcher_type_vec(matcher.matchers);
+    let match_function = generate_match_function(&state_type, &matchers);
+    let module_path = bevy_ecs_path();
+
+    Ok(quote!(
+        #(#attrs)*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        #vis struct #name;
+
+        impl #module_path::macro_support::DefinedMatcher<#state_type> for #name {
+            fn matches(
+                main: Option<&#state_type>,
+                secondary: Option<&#state_type>,
+            ) -> #module_path::MatchesStateTransition {
+                (#match_function)(main, secondary)
+            }
+        }
+    ))
+}
+
 pub fn state_matches_macro(match_result: MatchMacroResult) -> proc_macro::TokenStream {
     let MatchMacroResult {
         state_type,
@@ -314,87 +467,52 @@ pub fn state_matches_macro(match_result: MatchMacroResult) -> proc_macro::TokenS
 fn generate_match_function(state_type: &Path, matchers: &[(bool, MatchTypes)]) -> TokenStream {
     let module_path = bevy_ecs_path();
 
-    let tokens = matchers
-        .iter()
-        .map(|(every, matcher)| match matcher {
-            MatchTypes::Expression(e) => {
-                if *every {
-                    quote!(if main.matches(#e) { return #module_path::MatchesStateTransition::TransitionMatches; }
-                    )
-                } else {
-                    quote!(match #state_type::matches_transition(#e,Some(main), secondary) {
-                            #module_path::MatchesStateTransition::TransitionMatches => { return #module_path::MatchesStateTransition::TransitionMatches; },
-                            #module_path::MatchesStateTransition::MainMatches => { return #module_path::MatchesStateTransition::MainMatches; },
-                            _ => {}
-                        }
-                    )
+    // Each matcher expands into a single call to a shared helper, so the matching logic
+    // itself is only compiled once per state type rather than once per invocation.
+    let steps = matchers.iter().map(|(every, matcher)| match matcher {
+        MatchTypes::Expression(tokens) | MatchTypes::Closure(tokens) => {
+            quote!(#module_path::macro_support::matcher(main, secondary, #every, #tokens))
+        }
+        MatchTypes::Pattern(tokens) => {
+            quote!(#module_path::macro_support::pattern(main, secondary, #every, {
+                #[allow(unreachable_patterns)]
+                fn matches(state: &#state_type) -> bool {
+                    #tokens
                 }
-            }
-            MatchTypes::Pattern(tokens) => {
-                if *every {
-                    quote!({
-                            fn matches(state: &#state_type) -> bool {
-                                #tokens
-                            }
-
-                            if matches(main) { return #module_path::MatchesStateTransition::TransitionMatches; }
-                        }
-                    )
-                } else {
-                    quote!({
-                            fn matches(state: &#state_type) -> bool {
-                                #tokens
-                            }
-
-                            if matches(main) {  if let Some(secondary) = secondary {
-                                if matches(secondary) {
-                                    return #module_path::MatchesStateTransition::MainMatches;
-                                } else {
-                                    return #module_path::MatchesStateTransition::TransitionMatches;
-                                }
-                            } else {
-                                return #module_path::MatchesStateTransition::TransitionMatches;
-                            } }
-                        }
-                    )
+                matches
+            }))
+        }
+        MatchTypes::Transition(from, to) => {
+            quote!(#module_path::macro_support::transition(main, secondary, #every, {
+                #[allow(unreachable_patterns)]
+                fn matches(state: &#state_type) -> bool {
+                    #from
                 }
-            }
-            MatchTypes::Closure(tokens) => {
-                if *every {
-                    quote!({
-                            let matches = #tokens;
-
-                            if main.matches(matches) { return #module_path::MatchesStateTransition::TransitionMatches; }
-                        }
-                    )
-                } else {
-                    quote!({
-                            let matches = #tokens;
-
-
-                            match #state_type::matches_transition(matches, Some(main), secondary) {
-                                #module_path::MatchesStateTransition::TransitionMatches => { return #module_path::MatchesStateTransition::TransitionMatches; },
-                                #module_path::MatchesStateTransition::MainMatches => { return #module_path::MatchesStateTransition::MainMatches; },
-                                _ => {}
-                            }
-                        }
-                    )
+                matches
+            }, {
+                #[allow(unreachable_patterns)]
+                fn matches(state: &#state_type) -> bool {
+                    #to
                 }
-            }
-        })
-        .collect::<Vec<_>>();
-
-    let tokens = TokenStream::from_iter(tokens);
+                matches
+            }))
+        }
+    });
 
     quote!(
         |main: Option<&#state_type>, secondary: Option<&#state_type>| {
             let Some(main) = main else {
-                return  #module_path::MatchesStateTransition::NoMatch;
+                return #module_path::MatchesStateTransition::NoMatch;
             };
 
-            #tokens
+            #(
+                let result = #steps;
+                if result != #module_path::MatchesStateTransition::NoMatch {
+                    return result;
+                }
+            )*
 
-            return  #module_path::MatchesStateTransition::NoMatch;
+            #module_path::MatchesStateTransition::NoMatch
         }
     )
 }
@@ -0,0 +1,57 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, Attribute, Data, DeriveInput, Error, Ident, Token};
+
+fn bevy_ecs_path() -> syn::Path {
+    quote::format_ident!("bevy_state_matching_prototype").into()
+}
+
+fn parse_tags(attrs: &[Attribute]) -> syn::Result<Vec<String>> {
+    let mut tags = vec![];
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("state_tags"))
+    {
+        let idents = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        tags.extend(idents.iter().map(|ident| ident.to_string()));
+    }
+    Ok(tags)
+}
+
+pub fn derive_state_tags(input: DeriveInput) -> syn::Result<TokenStream> {
+    let module_path = bevy_ecs_path();
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let shared = parse_tags(&input.attrs)?;
+
+    let body = match &input.data {
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let mut tags = shared.clone();
+                    tags.extend(parse_tags(&variant.attrs)?);
+                    Ok(quote!(Self::#ident { .. } => &[#(#tags),*],))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote!(match self { #(#arms)* })
+        }
+        Data::Struct(_) => quote!(&[#(#shared),*]),
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                name,
+                "`StateTags` can only be derived for enums and structs",
+            ))
+        }
+    };
+
+    Ok(quote!(
+        impl #impl_generics #module_path::TaggedState for #name #type_generics #where_clause {
+            fn tags(&self) -> &'static [&'static str] {
+                #body
+            }
+        }
+    ))
+}
@@ -0,0 +1,108 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Data, DeriveInput, Error, Ident, Token,
+};
+
+fn bevy_ecs_path() -> syn::Path {
+    quote::format_ident!("bevy_state_matching_prototype").into()
+}
+
+/// A variant of the state, optionally followed by its fields - like `InGame { paused: false }`
+struct Variant {
+    ident: Ident,
+    fields: Option<TokenTree>,
+}
+
+impl Parse for Variant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let fields = if input.peek(token::Brace) || input.peek(token::Paren) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { ident, fields })
+    }
+}
+
+impl Variant {
+    fn to_tokens(&self) -> TokenStream {
+        let Self { ident, fields } = self;
+        quote!(Self::#ident #fields)
+    }
+}
+
+/// A single `from -> to` edge, where `from` can be `*` to allow any state
+struct Edge {
+    from: Option<Variant>,
+    to: Variant,
+}
+
+impl Parse for Edge {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let from = if input.peek(Token![*]) {
+            input.parse::<Token![*]>()?;
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        input.parse::<Token![->]>()?;
+        let to = input.parse()?;
+        Ok(Self { from, to })
+    }
+}
+
+fn parse_edges(input: &DeriveInput) -> syn::Result<Vec<Edge>> {
+    let mut edges = vec![];
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("states"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("transitions") {
+                return Err(meta.error("expected `transitions(...)`"));
+            }
+            let content;
+            syn::parenthesized!(content in meta.input);
+            edges.extend(Punctuated::<Edge, Token![,]>::parse_terminated(&content)?);
+            Ok(())
+        })?;
+    }
+    Ok(edges)
+}
+
+pub fn derive_state_transitions(input: DeriveInput) -> syn::Result<TokenStream> {
+    let module_path = bevy_ecs_path();
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    if !matches!(input.data, Data::Enum(_)) {
+        return Err(Error::new_spanned(
+            name,
+            "`StateTransitions` can only be derived for enums",
+        ));
+    }
+
+    let edges = parse_edges(&input)?.into_iter().map(|Edge { from, to }| {
+        let to = to.to_tokens();
+        match from {
+            Some(from) => {
+                let from = from.to_tokens();
+                quote!((Some(#from), #to))
+            }
+            None => quote!((None, #to)),
+        }
+    });
+
+    Ok(quote!(
+        impl #impl_generics #module_path::DeclaredTransitions for #name #type_generics #where_clause {
+            fn declared_transitions() -> Vec<(Option<Self>, Self)> {
+                vec![#(#edges),*]
+            }
+        }
+    ))
+}
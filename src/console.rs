@@ -0,0 +1,233 @@
+use std::fmt::Display;
+
+use bevy::{
+    prelude::{App, Resource, State, World},
+    reflect::{DynamicEnum, DynamicVariant, FromReflect, TypeInfo, TypePath, Typed, VariantInfo},
+};
+
+use crate::{
+    registration::{NamedState, NamedStates},
+    MatchableState, NextMatchableState, RegisterableState,
+};
+
+struct ConsoleEntry {
+    get: fn(&World) -> Option<String>,
+    set: fn(&mut World, &str) -> Result<(), StateConsoleError>,
+    values: fn() -> Vec<&'static str>,
+}
+
+/// The state types available to [`run_state_command`], registered with
+/// [`StateMatchingConsoleApp::register_state_console`]
+#[derive(Resource, Default)]
+pub struct StateConsole(NamedStates<ConsoleEntry>);
+
+/// An error returned by [`run_state_command`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateConsoleError {
+    /// The command isn't one of `state set`, `state get` or `state list`
+    UnknownCommand(String),
+    /// No state with this name was registered
+    UnknownState(String),
+    /// The state has no unit variant with this name
+    UnknownValue(String, String),
+    /// The state is registered, but has no [`NextMatchableState`] in the world
+    MissingState(String),
+}
+
+impl Display for StateConsoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(command) => write!(
+                f,
+                "unknown command {command:?} - expected `state set <State> <Value>`, `state get <State>` or `state list`"
+            ),
+            Self::UnknownState(state) => write!(f, "unknown state {state}"),
+            Self::UnknownValue(state, value) => write!(f, "{state} has no value {value}"),
+            Self::MissingState(state) => write!(f, "state {state} doesn't exist in the world"),
+        }
+    }
+}
+
+impl std::error::Error for StateConsoleError {}
+
+impl StateConsole {
+    fn state(&self, name: &str) -> Result<&NamedState<ConsoleEntry>, StateConsoleError> {
+        self.0
+            .find(name)
+            .ok_or_else(|| StateConsoleError::UnknownState(name.to_string()))
+    }
+
+    fn describe(&self, state: &NamedState<ConsoleEntry>, world: &World) -> String {
+        let current = (state.entry.get)(world).unwrap_or_else(|| "<missing>".to_string());
+        format!("{} = {current}", state.name)
+    }
+}
+
+/// Run a dev-console command against the states registered with
+/// [`StateMatchingConsoleApp::register_state_console`], returning the text to print.
+///
+/// Supported commands are:
+/// - `state set <State> <Value>` - queue a transition into the unit variant `Value`
+/// - `state get <State>` - print the current value
+/// - `state list` - print every registered state, its current value and the values it can be set to
+///
+/// States can be referred to by their short or full type path. Transitions are queued in
+/// [`NextMatchableState`], so all the exit and enter schedules run as usual.
+/// Hook this into a console crate's command handler, or call it directly from a debug UI.
+pub fn run_state_command(world: &mut World, command: &str) -> Result<String, StateConsoleError> {
+    let console = world.remove_resource::<StateConsole>().unwrap_or_default();
+    let result = run_with_console(&console, world, command);
+    world.insert_resource(console);
+    result
+}
+
+fn run_with_console(
+    console: &StateConsole,
+    world: &mut World,
+    command: &str,
+) -> Result<String, StateConsoleError> {
+    match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["state", "set", state, value] => {
+            let state = console.state(state)?;
+            (state.entry.set)(world, value)?;
+            Ok(format!("{} -> {value}", state.name))
+        }
+        ["state", "get", state] => Ok(console.describe(console.state(state)?, world)),
+        ["state", "list"] => Ok(console
+            .0
+            .iter()
+            .map(|state| {
+                format!(
+                    "{} [{}]",
+                    console.describe(state, world),
+                    (state.entry.values)().join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+        _ => Err(StateConsoleError::UnknownCommand(command.to_string())),
+    }
+}
+
+fn unit_variants<S: Typed>() -> Vec<&'static str> {
+    let TypeInfo::Enum(info) = S::type_info() else {
+        return vec![];
+    };
+    info.iter()
+        .filter_map(|variant| match variant {
+            VariantInfo::Unit(unit) => Some(unit.name()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn get_state<S: MatchableState>(world: &World) -> Option<String> {
+    world
+        .get_resource::<State<S>>()
+        .map(|state| format!("{:?}", state.get()))
+}
+
+/// Build the unit variant of `S` named `name`, if there is one
+pub(crate) fn unit_variant<S: RegisterableState + Typed>(name: &str) -> Option<S> {
+    unit_variants::<S>()
+        .into_iter()
+        .find(|variant| *variant == name)
+        .and_then(|variant| S::from_reflect(&DynamicEnum::new(variant, DynamicVariant::Unit)))
+}
+
+fn set_state<S: MatchableState + RegisterableState + Typed>(
+    world: &mut World,
+    value: &str,
+) -> Result<(), StateConsoleError> {
+    let name = S::short_type_path();
+    let Some(value) = unit_variant::<S>(value) else {
+        return Err(StateConsoleError::UnknownValue(
+            name.to_string(),
+            value.to_string(),
+        ));
+    };
+    let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() else {
+        return Err(StateConsoleError::MissingState(name.to_string()));
+    };
+    next_state.set(value);
+    Ok(())
+}
+
+/// A trait adding dev-console commands for states to a bevy `App`
+pub trait StateMatchingConsoleApp {
+    /// Make `S` available to [`run_state_command`], under its short type path
+    fn register_state_console<S: MatchableState + RegisterableState + Typed>(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl StateMatchingConsoleApp for App {
+    fn register_state_console<S: MatchableState + RegisterableState + Typed>(
+        &mut self,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(StateConsole::default)
+            .0
+            .register::<S>(|| ConsoleEntry {
+                get: get_state::<S>,
+                set: set_state::<S>,
+                values: unit_variants::<S>,
+            });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{run_state_command, StateConsoleError, StateMatchingApp, StateMatchingConsoleApp};
+    use bevy::prelude::{App, Reflect, State, States};
+
+    #[derive(States, Reflect, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum AppState {
+        #[default]
+        MainMenu,
+        InGame,
+    }
+
+    #[test]
+    fn commands_set_and_get_states() {
+        let mut app = App::new();
+        app.add_matchable_state::<AppState>()
+            .register_state_console::<AppState>();
+        app.update();
+
+        assert_eq!(
+            run_state_command(&mut app.world, "state list"),
+            Ok("AppState = MainMenu [MainMenu, InGame]".to_string())
+        );
+        assert_eq!(
+            run_state_command(&mut app.world, "state set AppState Paused"),
+            Err(StateConsoleError::UnknownValue(
+                "AppState".to_string(),
+                "Paused".to_string()
+            ))
+        );
+        assert!(run_state_command(&mut app.world, "state set AppState InGame").is_ok());
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::InGame
+        );
+        assert_eq!(
+            run_state_command(&mut app.world, "state get AppState"),
+            Ok("AppState = InGame".to_string())
+        );
+    }
+
+    #[test]
+    fn setting_missing_states_fails() {
+        let mut app = App::new();
+        app.register_state_console::<AppState>();
+
+        assert_eq!(
+            run_state_command(&mut app.world, "state set AppState InGame"),
+            Err(StateConsoleError::MissingState("AppState".to_string()))
+        );
+    }
+}
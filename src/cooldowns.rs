@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use bevy::prelude::{Resource, Time, World};
+
+use crate::{MatchableState, TransitionRejection};
+
+struct Cooldown<S: MatchableState> {
+    from: Box<dyn Fn(&S) -> bool + Send + Sync>,
+    to: Box<dyn Fn(&S) -> bool + Send + Sync>,
+    duration: Duration,
+    started: Option<Duration>,
+}
+
+impl<S: MatchableState> Cooldown<S> {
+    fn covers(&self, from: &S, to: &S) -> bool {
+        (self.from)(from) && (self.to)(to)
+    }
+}
+
+/// Rate limits on transitions of `S`, added with
+/// [`StateMatchingApp::transition_cooldown`](crate::StateMatchingApp::transition_cooldown).
+///
+/// Once a transition covered by a cooldown is applied, [`apply_state_transition`](crate::apply_state_transition)
+/// rejects any other transition it covers until the cooldown runs out, sending a
+/// [`TransitionRejected<S>`](crate::TransitionRejected) event with [`TransitionRejection::Cooldown`].
+/// Cooldowns are measured with the `Time` resource, and don't apply without it.
+#[derive(Resource)]
+pub struct TransitionCooldowns<S: MatchableState>(Vec<Cooldown<S>>);
+
+impl<S: MatchableState> Default for TransitionCooldowns<S> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<S: MatchableState> TransitionCooldowns<S> {
+    /// Rate limit transitions from states matching `from` into states matching `to`
+    pub fn add(
+        &mut self,
+        from: impl Fn(&S) -> bool + Send + Sync + 'static,
+        to: impl Fn(&S) -> bool + Send + Sync + 'static,
+        duration: Duration,
+    ) {
+        self.0.push(Cooldown {
+            from: Box::new(from),
+            to: Box::new(to),
+            duration,
+            started: None,
+        });
+    }
+
+    /// Whether a transition from `from` to `to` would currently be rejected
+    pub fn is_cooling_down(&self, now: Duration, from: &S, to: &S) -> bool {
+        self.0.iter().any(|cooldown| {
+            cooldown.covers(from, to)
+                && cooldown
+                    .started
+                    .is_some_and(|started| now.saturating_sub(started) < cooldown.duration)
+        })
+    }
+
+    /// Reset every cooldown, allowing all transitions again
+    pub fn clear(&mut self) {
+        for cooldown in self.0.iter_mut() {
+            cooldown.started = None;
+        }
+    }
+}
+
+/// Reject the transition from `from` to `to` if one of the [`TransitionCooldowns<S>`] covering it is running
+pub(crate) fn check_cooldowns<S: MatchableState>(
+    world: &World,
+    from: &S,
+    to: &S,
+) -> Result<(), TransitionRejection> {
+    let (Some(cooldowns), Some(time)) = (
+        world.get_resource::<TransitionCooldowns<S>>(),
+        world.get_resource::<Time>(),
+    ) else {
+        return Ok(());
+    };
+    if cooldowns.is_cooling_down(time.elapsed(), from, to) {
+        Err(TransitionRejection::Cooldown)
+    } else {
+        Ok(())
+    }
+}
+
+/// Start every [`TransitionCooldowns<S>`] covering the transition from `from` to `to`
+pub(crate) fn start_cooldowns<S: MatchableState>(world: &mut World, from: &S, to: &S) {
+    let Some(now) = world.get_resource::<Time>().map(|time| time.elapsed()) else {
+        return;
+    };
+    let Some(mut cooldowns) = world.get_resource_mut::<TransitionCooldowns<S>>() else {
+        return;
+    };
+    for cooldown in cooldowns.0.iter_mut() {
+        if cooldown.covers(from, to) {
+            cooldown.started = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{NextMatchableState, StateMatchingApp, TransitionRejected, TransitionRejection};
+    use bevy::prelude::{App, Events, State, States, Time};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        Playing,
+        Paused,
+    }
+
+    fn toggle(app: &mut App, state: TestState) -> TestState {
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(state);
+        app.update();
+        app.world.resource::<State<TestState>>().get().clone()
+    }
+
+    #[test]
+    fn covered_transitions_are_rate_limited() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_matchable_state::<TestState>()
+            .transition_cooldown(
+                TestState::Playing,
+                TestState::Paused,
+                Duration::from_secs(5),
+            );
+        app.update();
+
+        assert_eq!(toggle(&mut app, TestState::Paused), TestState::Paused);
+        assert_eq!(toggle(&mut app, TestState::Playing), TestState::Playing);
+        assert_eq!(toggle(&mut app, TestState::Paused), TestState::Playing);
+        let rejected = app
+            .world
+            .resource_mut::<Events<TransitionRejected<TestState>>>()
+            .drain()
+            .map(|rejected| rejected.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec![TransitionRejection::Cooldown]);
+
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(5));
+        assert_eq!(toggle(&mut app, TestState::Paused), TestState::Paused);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{MatchableState, StateMatcher, TransitionTable};
+
+/// Checks that a set of matchers covers every state in a known list of states,
+/// helping find states where no systems would run at all.
+///
+/// Since `States` doesn't expose its variants, the list of states has to be provided -
+/// either directly, or from a [`TransitionTable<S>`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+///   Paused,
+/// }
+///
+/// let coverage = MatcherCoverage::new([AppState::Menu, AppState::InGame, AppState::Paused])
+///     .with(AppState::Menu)
+///     .with(|state: &AppState| *state == AppState::InGame);
+/// assert_eq!(coverage.uncovered(), vec![AppState::Paused]);
+/// ```
+pub struct MatcherCoverage<S: MatchableState> {
+    states: Vec<S>,
+    matchers: Vec<Box<dyn Fn(&S) -> bool>>,
+}
+
+impl<S: MatchableState> MatcherCoverage<S> {
+    /// Check coverage over the given states
+    pub fn new(states: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            states: states.into_iter().collect(),
+            matchers: vec![],
+        }
+    }
+
+    /// Check coverage over the states declared in `table`
+    pub fn from_table(table: &TransitionTable<S>) -> Self {
+        Self::new(table.states().iter().cloned())
+    }
+
+    /// Add a matcher to the set
+    pub fn with<M: 'static>(mut self, matcher: impl StateMatcher<S, M>) -> Self {
+        self.matchers
+            .push(Box::new(move |state: &S| matcher.match_state(state)));
+        self
+    }
+
+    /// The states not matched by any of the matchers
+    pub fn uncovered(&self) -> Vec<S> {
+        self.states
+            .iter()
+            .filter(|state| !self.matchers.iter().any(|matcher| matcher(state)))
+            .cloned()
+            .collect()
+    }
+
+    /// Panic if any state isn't matched by one of the matchers.
+    ///
+    /// Only checked in debug builds.
+    pub fn assert_covered(&self) {
+        if cfg!(debug_assertions) {
+            let uncovered = self.uncovered();
+            assert!(
+                uncovered.is_empty(),
+                "States of {} not covered by any matcher: {uncovered:?}",
+                std::any::type_name::<S>()
+            );
+        }
+    }
+}
+
+/// Panic if any of `states` isn't matched by one of `matchers`. Only checked in debug builds.
+///
+/// A shorthand for [`MatcherCoverage`] when all the matchers share a type.
+pub fn assert_matchers_cover<S: MatchableState, M: 'static>(
+    states: impl IntoIterator<Item = S>,
+    matchers: impl IntoIterator<Item = impl StateMatcher<S, M>>,
+) {
+    matchers
+        .into_iter()
+        .fold(MatcherCoverage::new(states), MatcherCoverage::with)
+        .assert_covered();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assert_matchers_cover, MatcherCoverage, StateMachineBuilder};
+    use bevy::prelude::States;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[test]
+    fn reports_states_no_matcher_covers() {
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .transition(TestState::B, TestState::C)
+            .build();
+        let coverage = MatcherCoverage::from_table(&table)
+            .with(TestState::A)
+            .with(|state: &TestState| matches!(state, TestState::A | TestState::C));
+        assert_eq!(coverage.uncovered(), vec![TestState::B]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn assert_panics_when_not_covered() {
+        assert_matchers_cover(
+            [TestState::A, TestState::B, TestState::C],
+            [TestState::A, TestState::B],
+        );
+    }
+}
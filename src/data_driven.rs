@@ -0,0 +1,457 @@
+use std::{fmt::Display, marker::PhantomData};
+
+use bevy::{
+    asset::{
+        io::Reader, Asset, AssetApp, AssetEvent, AssetLoader, AssetPath, AssetServer, Assets,
+        AsyncReadExt, Handle, LoadContext,
+    },
+    log::error,
+    prelude::{
+        App, Commands, Event, EventReader, EventWriter, Local, Res, ResMut, Resource, State,
+        TypePath, Update,
+    },
+    time::Time,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    MatchableState, NextMatchableState, StateMachineBuilder, TransitionRejected, TransitionTable,
+};
+
+/// A transition declared in a [`StateMachineConfig`]
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransitionConfig {
+    /// The state the transition starts from, or `None` for any state
+    #[serde(default)]
+    pub from: Option<String>,
+    /// The state the transition leads to
+    pub to: String,
+}
+
+/// A transition that is queued automatically once a state has been active for `seconds`
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimedTransitionConfig {
+    /// The state to wait in
+    pub from: String,
+    /// The state to transition to
+    pub to: String,
+    /// How long to wait, in seconds
+    pub seconds: f32,
+}
+
+/// A state machine defined in data, loaded from `.sm.ron` or `.sm.json` files.
+///
+/// States are written the way `S` would be written in RON - so `"InGame"` for a unit variant,
+/// or `"Level(3)"` for a tuple variant - and converted using `S`'s `Deserialize` implementation.
+/// This is done with serde rather than reflection (`ReflectDeserializer`), so `S` doesn't need to be
+/// registered in the `AppTypeRegistry`, and [`build`](Self::build) works without a `World` -
+/// the same way the scripting and snapshot support parse states.
+///
+/// ```ron
+/// (
+///     transitions: [
+///         (from: Some("Menu"), to: "InGame"),
+///         (from: Some("InGame"), to: "Menu"),
+///         (to: "Error"),
+///     ],
+///     matchers: {
+///         "Playing": ["InGame"],
+///     },
+///     timed_transitions: [
+///         (from: "Splash", to: "Menu", seconds: 2.0),
+///     ],
+/// )
+/// ```
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct StateMachineConfig {
+    /// Additional states to declare, beyond those used in transitions
+    #[serde(default)]
+    pub states: Vec<String>,
+    /// The allowed transitions
+    #[serde(default)]
+    pub transitions: Vec<TransitionConfig>,
+    /// Named sets of states, usable with [`in_named_matcher`]
+    #[serde(default)]
+    pub matchers: HashMap<String, Vec<String>>,
+    /// Transitions queued automatically after some time in a state
+    #[serde(default)]
+    pub timed_transitions: Vec<TimedTransitionConfig>,
+}
+
+/// An error encountered while loading or applying a [`StateMachineConfig`]
+#[derive(Debug)]
+pub enum StateMachineConfigError {
+    /// The file couldn't be read
+    Io(std::io::Error),
+    /// The file couldn't be parsed
+    Parse(String),
+    /// A state name couldn't be converted into a state
+    UnknownState(String),
+}
+
+impl Display for StateMachineConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read state machine config: {e}"),
+            Self::Parse(e) => write!(f, "couldn't parse state machine config: {e}"),
+            Self::UnknownState(name) => write!(f, "unknown state in state machine config: {name}"),
+        }
+    }
+}
+
+impl std::error::Error for StateMachineConfigError {}
+
+impl From<std::io::Error> for StateMachineConfigError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Named sets of states loaded from a [`StateMachineConfig`]
+#[derive(Resource, Debug)]
+pub struct NamedMatchers<S: MatchableState>(HashMap<String, Vec<S>>);
+
+impl<S: MatchableState> NamedMatchers<S> {
+    /// Whether `state` is part of the set called `name`
+    pub fn matches(&self, name: &str, state: &S) -> bool {
+        self.0
+            .get(name)
+            .is_some_and(|states| states.contains(state))
+    }
+}
+
+/// The timed transitions loaded from a [`StateMachineConfig`].
+///
+/// Each one is queued at most once per entry into its state - if it is rejected, it isn't retried
+/// until the state is entered again.
+#[derive(Resource, Debug)]
+pub struct TimedTransitions<S: MatchableState>(Vec<(S, S, f32)>);
+
+/// The results of converting a [`StateMachineConfig`] for states of type `S`
+pub struct LoadedStateMachine<S: MatchableState> {
+    /// The allowed transitions
+    pub table: TransitionTable<S>,
+    /// The named matchers
+    pub matchers: NamedMatchers<S>,
+    /// The timed transitions
+    pub timed_transitions: TimedTransitions<S>,
+}
+
+impl StateMachineConfig {
+    /// Convert the config into a state machine for `S`, parsing state names with `S`'s `Deserialize`
+    /// implementation
+    pub fn build<S: MatchableState + DeserializeOwned>(
+        &self,
+    ) -> Result<LoadedStateMachine<S>, StateMachineConfigError> {
+        let parse = |name: &String| {
+            ron::from_str::<S>(name)
+                .map_err(|_| StateMachineConfigError::UnknownState(name.clone()))
+        };
+
+        let mut builder = StateMachineBuilder::new();
+        for state in self.states.iter() {
+            builder = builder.state(parse(state)?);
+        }
+        for transition in self.transitions.iter() {
+            let to = parse(&transition.to)?;
+            builder = match &transition.from {
+                Some(from) => builder.transition(parse(from)?, to),
+                None => builder.transition_from_any(to),
+            };
+        }
+
+        let matchers = self
+            .matchers
+            .iter()
+            .map(|(name, states)| {
+                let states = states.iter().map(parse).collect::<Result<Vec<_>, _>>()?;
+                Ok((name.clone(), states))
+            })
+            .collect::<Result<HashMap<_, _>, StateMachineConfigError>>()?;
+
+        let timed_transitions = self
+            .timed_transitions
+            .iter()
+            .map(|timed| Ok((parse(&timed.from)?, parse(&timed.to)?, timed.seconds)))
+            .collect::<Result<Vec<_>, StateMachineConfigError>>()?;
+
+        Ok(LoadedStateMachine {
+            table: builder.build(),
+            matchers: NamedMatchers(matchers),
+            timed_transitions: TimedTransitions(timed_transitions),
+        })
+    }
+}
+
+/// Loads [`StateMachineConfig`] assets from `.sm.ron` and `.sm.json` files
+#[derive(Default)]
+pub struct StateMachineConfigLoader;
+
+impl AssetLoader for StateMachineConfigLoader {
+    type Asset = StateMachineConfig;
+    type Settings = ();
+    type Error = StateMachineConfigError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<StateMachineConfig, StateMachineConfigError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            if load_context
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                serde_json::from_slice(&bytes)
+                    .map_err(|e| StateMachineConfigError::Parse(e.to_string()))
+            } else {
+                ron::de::from_bytes(&bytes)
+                    .map_err(|e| StateMachineConfigError::Parse(e.to_string()))
+            }
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sm.ron", "sm.json"]
+    }
+}
+
+#[derive(Resource)]
+struct StateMachineConfigHandle<S: MatchableState>(
+    Handle<StateMachineConfig>,
+    PhantomData<fn() -> S>,
+);
+
+/// Sent whenever the [`StateMachineConfig`] for `S` is modified on disk and successfully re-applied.
+///
+/// Hot reloading requires bevy's `file_watcher` feature. If the modified config is invalid,
+/// the error is logged and the previous state machine is kept.
+#[derive(Event, Debug)]
+pub struct StateMachineReloaded<S: MatchableState>(PhantomData<fn() -> S>);
+
+impl<S: MatchableState> Default for StateMachineReloaded<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+fn apply_state_machine_config<S: MatchableState + DeserializeOwned>(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<StateMachineConfig>>,
+    mut reloaded: EventWriter<StateMachineReloaded<S>>,
+    handle: Res<StateMachineConfigHandle<S>>,
+    configs: Res<Assets<StateMachineConfig>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        let Some(config) = configs.get(*id) else {
+            continue;
+        };
+        match config.build::<S>() {
+            Ok(loaded) => {
+                commands.insert_resource(loaded.table);
+                commands.insert_resource(loaded.matchers);
+                commands.insert_resource(loaded.timed_transitions);
+                if matches!(event, AssetEvent::Modified { .. }) {
+                    reloaded.send(StateMachineReloaded::default());
+                }
+            }
+            Err(e) => error!("{e}"),
+        }
+    }
+}
+
+/// Queues the timed transition out of the current state once its delay has passed.
+///
+/// Each timed transition is queued at most once per entry into its state, so a rejected one
+/// isn't queued again every frame.
+fn apply_timed_transitions<S: MatchableState>(
+    state: Option<Res<State<S>>>,
+    timed: Option<Res<TimedTransitions<S>>>,
+    time: Option<Res<Time>>,
+    next_state: Option<ResMut<NextMatchableState<S>>>,
+    mut elapsed: Local<f32>,
+    mut fired: Local<bool>,
+) {
+    let (Some(state), Some(time), Some(mut next_state)) = (state, time, next_state) else {
+        return;
+    };
+    if state.is_changed() {
+        *elapsed = 0.;
+        *fired = false;
+    }
+    *elapsed += time.delta_seconds();
+    let Some(timed) = timed.filter(|_| !*fired) else {
+        return;
+    };
+    if let Some((_, to, _)) = timed
+        .0
+        .iter()
+        .find(|(from, _, seconds)| from == state.get() && *elapsed >= *seconds)
+    {
+        next_state.set(to.clone());
+        *fired = true;
+    }
+}
+
+/// A run condition that is true while the current state is part of the set called `name`
+/// in the loaded [`NamedMatchers<S>`]
+pub fn in_named_matcher<S: MatchableState>(
+    name: impl Into<String>,
+) -> impl FnMut(Option<Res<State<S>>>, Option<Res<NamedMatchers<S>>>) -> bool + Clone {
+    let name = name.into();
+    move |state: Option<Res<State<S>>>, matchers: Option<Res<NamedMatchers<S>>>| match (
+        state, matchers,
+    ) {
+        (Some(state), Some(matchers)) => matchers.matches(&name, state.get()),
+        _ => false,
+    }
+}
+
+/// A trait adding data-driven state machines to a bevy `App`
+pub trait StateMatchingDataApp {
+    /// Load the state machine for `S` from a [`StateMachineConfig`] asset at `path`.
+    ///
+    /// Once loaded, the [`TransitionTable<S>`], [`NamedMatchers<S>`] and [`TimedTransitions<S>`]
+    /// resources are inserted. Requires the `AssetPlugin` to be added first.
+    ///
+    /// When the asset changes, they are rebuilt live and a [`StateMachineReloaded<S>`] event is sent.
+    fn load_state_machine<S: MatchableState + DeserializeOwned>(
+        &mut self,
+        path: impl Into<AssetPath<'static>>,
+    ) -> &mut Self;
+}
+
+impl StateMatchingDataApp for App {
+    fn load_state_machine<S: MatchableState + DeserializeOwned>(
+        &mut self,
+        path: impl Into<AssetPath<'static>>,
+    ) -> &mut Self {
+        if !self.world.contains_resource::<Assets<StateMachineConfig>>() {
+            self.init_asset::<StateMachineConfig>()
+                .init_asset_loader::<StateMachineConfigLoader>();
+        }
+        let handle = self.world.resource::<AssetServer>().load(path);
+        self.insert_resource(StateMachineConfigHandle::<S>(handle, PhantomData))
+            .add_event::<TransitionRejected<S>>()
+            .add_event::<StateMachineReloaded<S>>()
+            .add_systems(
+                Update,
+                (
+                    apply_state_machine_config::<S>,
+                    apply_timed_transitions::<S>,
+                ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::apply_timed_transitions;
+    use crate::{
+        StateMachineBuilder, StateMachineConfig, StateMatchingApp, TimedTransitions,
+        TransitionRejected, TransitionRejection,
+    };
+    use bevy::prelude::{App, EventReader, ResMut, Resource, State, States, Time, Update, World};
+    use serde::Deserialize;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Deserialize)]
+    enum TestState {
+        #[default]
+        Menu,
+        InGame,
+        Level(u32),
+    }
+
+    #[test]
+    fn builds_a_state_machine_from_ron() {
+        let config: StateMachineConfig = ron::from_str(
+            r#"(
+                transitions: [
+                    (from: Some("Menu"), to: "InGame"),
+                    (from: Some("InGame"), to: "Level(1)"),
+                ],
+                matchers: { "Playing": ["InGame", "Level(1)"] },
+            )"#,
+        )
+        .unwrap();
+        let loaded = config.build::<TestState>().unwrap();
+        let world = World::new();
+
+        assert_eq!(
+            loaded
+                .table
+                .check(&world, &TestState::Menu, &TestState::InGame),
+            Ok(())
+        );
+        assert_eq!(
+            loaded
+                .table
+                .check(&world, &TestState::Menu, &TestState::Level(1)),
+            Err(TransitionRejection::NotAllowed)
+        );
+        assert!(loaded.matchers.matches("Playing", &TestState::Level(1)));
+        assert!(!loaded.matchers.matches("Playing", &TestState::Menu));
+    }
+
+    #[test]
+    fn rejected_timed_transitions_are_queued_once_per_entry() {
+        #[derive(Resource, Default)]
+        struct Rejections(usize);
+
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Rejections>()
+            .add_matchable_state::<TestState>()
+            .add_state_machine(
+                StateMachineBuilder::new().transition(TestState::InGame, TestState::Menu),
+            )
+            .insert_resource(TimedTransitions(vec![(
+                TestState::Menu,
+                TestState::InGame,
+                0.5,
+            )]))
+            .add_systems(
+                Update,
+                (
+                    apply_timed_transitions::<TestState>,
+                    |mut rejected: EventReader<TransitionRejected<TestState>>,
+                     mut rejections: ResMut<Rejections>| {
+                        rejections.0 += rejected.read().count();
+                    },
+                ),
+            );
+        for _ in 0..5 {
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            app.update();
+        }
+
+        assert_eq!(app.world.resource::<Rejections>().0, 1);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::Menu
+        );
+    }
+
+    #[test]
+    fn unknown_states_are_an_error() {
+        let config: StateMachineConfig =
+            ron::from_str(r#"(transitions: [(to: "Paused")])"#).unwrap();
+        assert!(config.build::<TestState>().is_err());
+    }
+}
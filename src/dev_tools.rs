@@ -0,0 +1,138 @@
+use bevy::{
+    log::{info, warn},
+    prelude::App,
+    reflect::{TypePath, Typed},
+};
+
+use crate::{console::unit_variant, state::InitialStateFn, MatchableState, RegisterableState};
+
+/// The prefix of the environment variables and command line arguments read by
+/// [`StateMatchingDevToolsApp::override_initial_state`]
+pub const STATE_OVERRIDE_PREFIX: &str = "BEVY_STATE_";
+
+/// Find the override for the state called `name`, looking at `KEY=Value` command line arguments
+/// before the `KEY` environment variable
+fn find_override(
+    name: &str,
+    mut args: impl Iterator<Item = String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let key = format!("{STATE_OVERRIDE_PREFIX}{name}");
+    args.find_map(|arg| {
+        arg.strip_prefix(&key)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(str::to_string)
+    })
+    .or_else(|| env(&key))
+}
+
+/// A trait adding development overrides for states to a bevy `App`
+pub trait StateMatchingDevToolsApp {
+    /// Start `S` in the unit variant named by the `BEVY_STATE_<State>` command line argument
+    /// or environment variable, where `<State>` is the short type path of `S` - so running with
+    /// `BEVY_STATE_AppState=InGame` skips straight into `AppState::InGame`.
+    ///
+    /// Command line arguments take precedence over the environment. Call this after registering
+    /// `S`, since it replaces any closure passed to
+    /// [`init_matchable_state_with`](crate::StateMatchingApp::init_matchable_state_with).
+    /// Unknown values are ignored with a warning.
+    fn override_initial_state<S: MatchableState + RegisterableState + Typed>(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl StateMatchingDevToolsApp for App {
+    fn override_initial_state<S: MatchableState + RegisterableState + Typed>(
+        &mut self,
+    ) -> &mut Self {
+        override_initial_state_from::<S>(self, std::env::args(), |key| std::env::var(key).ok())
+    }
+}
+
+/// [`StateMatchingDevToolsApp::override_initial_state`], reading the overrides from `args` and `env`
+fn override_initial_state_from<S: MatchableState + RegisterableState + Typed>(
+    app: &mut App,
+    args: impl Iterator<Item = String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> &mut App {
+    let Some(value) = find_override(S::short_type_path(), args, env) else {
+        return app;
+    };
+    let Some(state) = unit_variant::<S>(&value) else {
+        warn!(
+            "{} has no value {value} - ignoring the override",
+            S::type_path()
+        );
+        return app;
+    };
+    info!(
+        "Overriding the initial state of {} with {state:?}",
+        S::type_path()
+    );
+    app.insert_resource(InitialStateFn::<S>(Box::new(move |_| state)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_override, override_initial_state_from};
+    use crate::StateMatchingApp;
+    use bevy::prelude::{App, Reflect, State, States};
+
+    #[derive(States, Reflect, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum AppState {
+        #[default]
+        MainMenu,
+        InGame,
+    }
+
+    #[test]
+    fn overrides_prefer_arguments_over_the_environment() {
+        let args = || {
+            [
+                "game",
+                "BEVY_STATE_AppStateX=Other",
+                "BEVY_STATE_AppState=InGame",
+            ]
+            .into_iter()
+            .map(str::to_string)
+        };
+        let env = |key: &str| (key == "BEVY_STATE_AppState").then(|| "MainMenu".to_string());
+
+        assert_eq!(
+            find_override("AppState", args(), env),
+            Some("InGame".to_string())
+        );
+        assert_eq!(
+            find_override("AppState", std::iter::empty(), env),
+            Some("MainMenu".to_string())
+        );
+        assert_eq!(find_override("Other", args(), env), None);
+    }
+
+    fn start_with_override(value: &str) -> App {
+        let mut app = App::new();
+        app.add_matchable_state::<AppState>();
+        let args = [format!("BEVY_STATE_AppState={value}")].into_iter();
+        override_initial_state_from::<AppState>(&mut app, args, |_| None);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn overridden_states_are_entered_first() {
+        let app = start_with_override("InGame");
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::InGame
+        );
+    }
+
+    #[test]
+    fn unknown_overrides_are_ignored() {
+        let app = start_with_override("Credits");
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::MainMenu
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, RegisterDiagnostic},
+    prelude::{App, DetectChanges, IntoSystemConfigs, Local, Res, State, StateTransition},
+    time::Time,
+};
+
+use crate::{apply_state_transition, MatchableState, TransitionBatchStats};
+
+/// The diagnostics registered for each state type by
+/// [`StateMatchingDiagnosticsApp::add_state_diagnostics`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StateDiagnostic {
+    /// The number of transitions per second
+    TransitionsPerSecond,
+    /// The total number of transitions since the app started
+    TotalTransitions,
+    /// The time since the last transition, in seconds
+    SecondsSinceLastTransition,
+    /// The number of queued transitions deferred to the next frame by a
+    /// [`TransitionBudget`](crate::TransitionBudget) - only measured while one is set
+    DeferredTransitions,
+}
+
+impl StateDiagnostic {
+    /// The id of this diagnostic for states of type `S`
+    pub fn id<S: MatchableState>(self) -> DiagnosticId {
+        let mut hasher = DefaultHasher::new();
+        std::any::type_name::<S>().hash(&mut hasher);
+        self.hash(&mut hasher);
+        let low = hasher.finish();
+        low.hash(&mut hasher);
+        DiagnosticId::from_u128(((hasher.finish() as u128) << 64) | low as u128)
+    }
+
+    fn name<S: MatchableState>(self) -> String {
+        let suffix = match self {
+            StateDiagnostic::TransitionsPerSecond => "transitions_per_second",
+            StateDiagnostic::TotalTransitions => "total_transitions",
+            StateDiagnostic::SecondsSinceLastTransition => "seconds_since_last_transition",
+            StateDiagnostic::DeferredTransitions => "deferred_transitions",
+        };
+        format!("{}/{suffix}", std::any::type_name::<S>())
+    }
+}
+
+fn measure_state_diagnostics<S: MatchableState>(
+    mut diagnostics: Diagnostics,
+    state: Res<State<S>>,
+    time: Option<Res<Time>>,
+    batch: Option<Res<TransitionBatchStats<S>>>,
+    mut measurements: Local<Option<(u64, f64)>>,
+) {
+    let delta = time
+        .map(|time| time.delta_seconds_f64())
+        .unwrap_or_default();
+    // The initial state is inserted before the first run, so it isn't counted as a transition
+    let transitioned = measurements.is_some() && state.is_changed();
+    let (total, since_last) = measurements.get_or_insert((0, 0.));
+    if transitioned {
+        *total += 1;
+        *since_last = 0.;
+    } else {
+        *since_last += delta;
+    }
+    let (total, since_last) = (*total as f64, *since_last);
+
+    diagnostics.add_measurement(StateDiagnostic::TotalTransitions.id::<S>(), || total);
+    diagnostics.add_measurement(
+        StateDiagnostic::SecondsSinceLastTransition.id::<S>(),
+        || since_last,
+    );
+    if let Some(batch) = batch {
+        let deferred = batch.deferred as f64;
+        diagnostics.add_measurement(StateDiagnostic::DeferredTransitions.id::<S>(), || deferred);
+    }
+    if delta > 0. {
+        let rate = if transitioned { 1. / delta } else { 0. };
+        diagnostics.add_measurement(StateDiagnostic::TransitionsPerSecond.id::<S>(), || rate);
+    }
+}
+
+/// A trait adding state diagnostics to a bevy `App`
+pub trait StateMatchingDiagnosticsApp {
+    /// Register the [`StateDiagnostic`]s for `S`, so tools like the `LogDiagnosticsPlugin`
+    /// surface how often it transitions.
+    fn add_state_diagnostics<S: MatchableState>(&mut self) -> &mut Self;
+}
+
+impl StateMatchingDiagnosticsApp for App {
+    fn add_state_diagnostics<S: MatchableState>(&mut self) -> &mut Self {
+        for diagnostic in [
+            StateDiagnostic::TransitionsPerSecond,
+            StateDiagnostic::TotalTransitions,
+            StateDiagnostic::SecondsSinceLastTransition,
+            StateDiagnostic::DeferredTransitions,
+        ] {
+            self.register_diagnostic(Diagnostic::new(
+                diagnostic.id::<S>(),
+                diagnostic.name::<S>(),
+                Diagnostic::DEFAULT_MAX_HISTORY_LENGTH,
+            ));
+        }
+        self.add_systems(
+            StateTransition,
+            measure_state_diagnostics::<S>.after(apply_state_transition::<S>),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        NextMatchableState, StateDiagnostic, StateMatchingApp, StateMatchingDiagnosticsApp,
+    };
+    use bevy::{
+        diagnostic::DiagnosticsStore,
+        prelude::{App, States},
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn counts_transitions() {
+        let mut app = App::new();
+        app.init_resource::<DiagnosticsStore>()
+            .add_matchable_state::<TestState>()
+            .add_state_diagnostics::<TestState>();
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+
+        let store = app.world.resource::<DiagnosticsStore>();
+        let total = store
+            .get(StateDiagnostic::TotalTransitions.id::<TestState>())
+            .and_then(|diagnostic| diagnostic.value());
+        assert_eq!(total, Some(1.));
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::{
+        App, DetectChanges, IntoSystemConfigs, Res, ResMut, Resource, State, StateTransition,
+    },
+    time::{Time, Timer, TimerMode},
+    utils::HashMap,
+};
+
+use crate::{apply_state_transition, MatchableState};
+
+/// The time spent in a single state value, aggregated by [`DwellTimes<S>`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DwellTime {
+    /// The total time spent in the state
+    pub total: Duration,
+    /// The number of times the state was entered
+    pub entries: u32,
+}
+
+/// The time spent in, and the number of entries into, each value of `S` -
+/// tracked once added with [`StateMatchingAnalyticsApp::add_dwell_times`]
+#[derive(Resource, Debug)]
+pub struct DwellTimes<S: MatchableState> {
+    states: HashMap<S, DwellTime>,
+    current: Option<S>,
+}
+
+impl<S: MatchableState> Default for DwellTimes<S> {
+    fn default() -> Self {
+        Self {
+            states: HashMap::default(),
+            current: None,
+        }
+    }
+}
+
+impl<S: MatchableState> DwellTimes<S> {
+    /// The time spent in `state` since the last flush
+    pub fn get(&self, state: &S) -> Option<&DwellTime> {
+        self.states.get(state)
+    }
+
+    /// The time spent in every state entered since the last flush
+    pub fn iter(&self) -> impl Iterator<Item = (&S, &DwellTime)> {
+        self.states.iter()
+    }
+
+    /// Take the aggregated times, resetting them - the current state keeps being tracked
+    pub fn flush(&mut self) -> HashMap<S, DwellTime> {
+        std::mem::take(&mut self.states)
+    }
+}
+
+fn track_dwell_times<S: MatchableState>(
+    mut dwell_times: ResMut<DwellTimes<S>>,
+    state: Res<State<S>>,
+    time: Option<Res<Time>>,
+) {
+    let delta = time.map(|time| time.delta()).unwrap_or_default();
+    let dwell_times = &mut *dwell_times;
+    // The time since the last run was spent in the state that was current back then
+    if let Some(current) = &dwell_times.current {
+        dwell_times.states.entry(current.clone()).or_default().total += delta;
+    }
+    if dwell_times.current.is_none() || state.is_changed() {
+        let entered = state.get().clone();
+        dwell_times
+            .states
+            .entry(entered.clone())
+            .or_default()
+            .entries += 1;
+        dwell_times.current = Some(entered);
+    }
+}
+
+#[derive(Resource)]
+struct DwellTimeFlush<S: MatchableState> {
+    timer: Timer,
+    callback: Box<dyn Fn(HashMap<S, DwellTime>) + Send + Sync>,
+}
+
+fn flush_dwell_times<S: MatchableState>(
+    mut flush: ResMut<DwellTimeFlush<S>>,
+    mut dwell_times: ResMut<DwellTimes<S>>,
+    time: Option<Res<Time>>,
+) {
+    let delta = time.map(|time| time.delta()).unwrap_or_default();
+    if flush.timer.tick(delta).just_finished() {
+        (flush.callback)(dwell_times.flush());
+    }
+}
+
+/// A trait adding state analytics to a bevy `App`
+pub trait StateMatchingAnalyticsApp {
+    /// Track how long is spent in, and how often each value of `S` is entered, in a [`DwellTimes<S>`] resource
+    fn add_dwell_times<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Pass the aggregated [`DwellTimes<S>`] to `callback` every `interval`, resetting them -
+    /// to send them to an analytics backend, for example. Also adds the dwell times if needed.
+    fn flush_dwell_times<S: MatchableState>(
+        &mut self,
+        interval: Duration,
+        callback: impl Fn(HashMap<S, DwellTime>) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl StateMatchingAnalyticsApp for App {
+    fn add_dwell_times<S: MatchableState>(&mut self) -> &mut Self {
+        if self.world.contains_resource::<DwellTimes<S>>() {
+            return self;
+        }
+        self.init_resource::<DwellTimes<S>>().add_systems(
+            StateTransition,
+            track_dwell_times::<S>.after(apply_state_transition::<S>),
+        )
+    }
+
+    fn flush_dwell_times<S: MatchableState>(
+        &mut self,
+        interval: Duration,
+        callback: impl Fn(HashMap<S, DwellTime>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_dwell_times::<S>()
+            .insert_resource(DwellTimeFlush::<S> {
+                timer: Timer::new(interval, TimerMode::Repeating),
+                callback: Box::new(callback),
+            })
+            .add_systems(
+                StateTransition,
+                flush_dwell_times::<S>.after(track_dwell_times::<S>),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{DwellTimes, NextMatchableState, StateMatchingAnalyticsApp, StateMatchingApp};
+    use bevy::prelude::{App, States, Time};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn advance(app: &mut App, seconds: u64) {
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(seconds));
+        app.update();
+    }
+
+    #[test]
+    fn dwell_times_are_aggregated_and_flushed() {
+        let flushed = Arc::new(Mutex::new(vec![]));
+        let sink = flushed.clone();
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_matchable_state::<TestState>()
+            .flush_dwell_times::<TestState>(Duration::from_secs(10), move |times| {
+                sink.lock().unwrap().push(times)
+            });
+        app.update();
+        advance(&mut app, 2);
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        advance(&mut app, 3);
+        advance(&mut app, 4);
+
+        let dwell_times = app.world.resource::<DwellTimes<TestState>>();
+        let a = dwell_times.get(&TestState::A).unwrap();
+        assert_eq!((a.total, a.entries), (Duration::from_secs(5), 1));
+        let b = dwell_times.get(&TestState::B).unwrap();
+        assert_eq!((b.total, b.entries), (Duration::from_secs(4), 1));
+        assert!(flushed.lock().unwrap().is_empty());
+
+        advance(&mut app, 1);
+        let flushed = flushed.lock().unwrap();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(
+            flushed[0].get(&TestState::B).unwrap().total,
+            Duration::from_secs(5)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::{
+    prelude::{Res, State},
+    reflect::{Enum, Reflect, ReflectRef},
+};
+
+use crate::{
+    state_matching::{sealed, SingleStateMatcher},
+    MatchableState,
+};
+
+/// A state matcher built at runtime from an enum variant name, and optionally some field values,
+/// compared using reflection - for scripts and config files that can't use `state_matches!`.
+///
+/// Fields are looked up by name, or by index for tuple variants (`"0"`, `"1"`...).
+pub struct DynamicMatcher {
+    variant: String,
+    fields: Vec<(String, Box<dyn Reflect>)>,
+}
+
+impl Clone for DynamicMatcher {
+    fn clone(&self) -> Self {
+        Self {
+            variant: self.variant.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone_value()))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for DynamicMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicMatcher")
+            .field("variant", &self.variant)
+            .field(
+                "fields",
+                &self
+                    .fields
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl DynamicMatcher {
+    /// Match any state of the enum variant called `name`
+    pub fn variant(name: impl Into<String>) -> Self {
+        Self {
+            variant: name.into(),
+            fields: vec![],
+        }
+    }
+
+    /// Only match if the field `name` of the variant is equal to `value`
+    pub fn with_field(mut self, name: impl Into<String>, value: impl Reflect) -> Self {
+        self.fields.push((name.into(), Box::new(value)));
+        self
+    }
+
+    /// Whether `state` matches
+    pub fn matches(&self, state: &dyn Reflect) -> bool {
+        let ReflectRef::Enum(state) = state.reflect_ref() else {
+            return false;
+        };
+        state.variant_name() == self.variant
+            && self.fields.iter().all(|(name, value)| {
+                field(state, name)
+                    .and_then(|field| field.reflect_partial_eq(value.as_ref()))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+fn field<'a>(state: &'a dyn Enum, name: &str) -> Option<&'a dyn Reflect> {
+    state
+        .field(name)
+        .or_else(|| name.parse().ok().and_then(|index| state.field_at(index)))
+}
+
+impl<S: MatchableState + Reflect> SingleStateMatcher<S, sealed::IsDynamicMatcher>
+    for DynamicMatcher
+{
+    fn match_single_state(&self, state: &S) -> bool {
+        self.matches(state)
+    }
+}
+
+/// A run condition that is true while the current state is the enum variant called `variant`
+///
+/// Use [`DynamicMatcher`] to match field values as well.
+pub fn matches_path<S: MatchableState + Reflect>(
+    variant: impl Into<String>,
+) -> impl FnMut(Option<Res<State<S>>>) -> bool + Clone {
+    let matcher = DynamicMatcher::variant(variant);
+    move |state: Option<Res<State<S>>>| {
+        state
+            .map(|state| matcher.matches(state.get()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{state_matching::sealed::InternalStateMatcher, DynamicMatcher};
+    use bevy::prelude::{Reflect, States};
+
+    #[derive(States, Reflect, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        MainMenu,
+        InGame {
+            paused: bool,
+        },
+        Level(u32),
+    }
+
+    #[test]
+    fn matches_variants_and_fields() {
+        let in_game = DynamicMatcher::variant("InGame");
+        assert!(in_game.match_state(&TestState::InGame { paused: true }));
+        assert!(!in_game.match_state(&TestState::MainMenu));
+
+        let paused = DynamicMatcher::variant("InGame").with_field("paused", true);
+        assert!(paused.match_state(&TestState::InGame { paused: true }));
+        assert!(!paused.match_state(&TestState::InGame { paused: false }));
+
+        let level = DynamicMatcher::variant("Level").with_field("0", 2u32);
+        assert!(level.match_state(&TestState::Level(2)));
+        assert!(!level.match_state(&TestState::Level(3)));
+    }
+}
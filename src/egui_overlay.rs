@@ -0,0 +1,142 @@
+use bevy::{
+    prelude::{App, DetectChanges, IntoSystemConfigs, Res, ResMut, Resource, State, Update},
+    time::Time,
+};
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crate::{MatchableState, NextMatchableState, TransitionStepping};
+
+#[derive(Debug)]
+struct StateDebugEntry {
+    name: &'static str,
+    current: String,
+    queued: String,
+    seconds_in_state: f32,
+    options: Vec<String>,
+    requested: Option<usize>,
+    held: Option<String>,
+    step_requested: bool,
+}
+
+/// The debug window listing the states registered with [`StateMatchingEguiApp::add_state_debug`]
+#[derive(Resource, Debug)]
+pub struct StateDebugOverlay {
+    /// Whether the window is shown
+    pub visible: bool,
+    entries: Vec<StateDebugEntry>,
+}
+
+impl Default for StateDebugOverlay {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            entries: vec![],
+        }
+    }
+}
+
+fn draw_state_debug_overlay(mut contexts: EguiContexts, mut overlay: ResMut<StateDebugOverlay>) {
+    if !overlay.visible {
+        return;
+    }
+    egui::Window::new("States").show(contexts.ctx_mut(), |ui| {
+        for entry in overlay.entries.iter_mut() {
+            ui.collapsing(entry.name, |ui| {
+                ui.label(format!("Current: {}", entry.current));
+                ui.label(format!("Queued: {}", entry.queued));
+                ui.label(format!("Time in state: {:.2}s", entry.seconds_in_state));
+                if let Some(held) = &entry.held {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Held: {held}"));
+                        if ui.button("Step").clicked() {
+                            entry.step_requested = true;
+                        }
+                    });
+                }
+                egui::ComboBox::from_id_source(entry.name)
+                    .selected_text("Force transition")
+                    .show_ui(ui, |ui| {
+                        for (i, option) in entry.options.iter().enumerate() {
+                            if ui.selectable_label(false, option).clicked() {
+                                entry.requested = Some(i);
+                            }
+                        }
+                    });
+            });
+        }
+    });
+}
+
+/// A trait adding an egui debug window for states to a bevy `App`
+pub trait StateMatchingEguiApp {
+    /// Show `S` in the [`StateDebugOverlay`], with its current value, the queued transition and the time spent
+    /// in the current state - along with a dropdown forcing a transition into any of `options`.
+    /// Transitions held by a [`TransitionStepping<S>`] are shown with a button stepping them.
+    ///
+    /// Adds the `EguiPlugin` if it wasn't added yet.
+    fn add_state_debug<S: MatchableState>(
+        &mut self,
+        options: impl IntoIterator<Item = S>,
+    ) -> &mut Self;
+}
+
+impl StateMatchingEguiApp for App {
+    fn add_state_debug<S: MatchableState>(
+        &mut self,
+        options: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        if !self.is_plugin_added::<EguiPlugin>() {
+            self.add_plugins(EguiPlugin);
+        }
+        if !self.world.contains_resource::<StateDebugOverlay>() {
+            self.init_resource::<StateDebugOverlay>()
+                .add_systems(Update, draw_state_debug_overlay);
+        }
+
+        let options = options.into_iter().collect::<Vec<_>>();
+        let mut overlay = self.world.resource_mut::<StateDebugOverlay>();
+        let index = overlay.entries.len();
+        overlay.entries.push(StateDebugEntry {
+            name: std::any::type_name::<S>(),
+            current: String::new(),
+            queued: String::new(),
+            seconds_in_state: 0.,
+            options: options.iter().map(|option| format!("{option:?}")).collect(),
+            requested: None,
+            held: None,
+            step_requested: false,
+        });
+
+        self.add_systems(
+            Update,
+            (move |state: Res<State<S>>,
+                   next_state: Option<ResMut<NextMatchableState<S>>>,
+                   stepping: Option<ResMut<TransitionStepping<S>>>,
+                   time: Res<Time>,
+                   mut overlay: ResMut<StateDebugOverlay>| {
+                let entry = &mut overlay.entries[index];
+                if state.is_changed() {
+                    entry.seconds_in_state = 0.;
+                }
+                entry.seconds_in_state += time.delta_seconds();
+                entry.current = format!("{:?}", state.get());
+                if let Some(mut stepping) = stepping {
+                    if std::mem::take(&mut entry.step_requested) {
+                        stepping.step();
+                    }
+                    entry.held = stepping
+                        .pending()
+                        .map(|(from, to)| format!("{from:?} -> {to:?}"));
+                }
+                let Some(mut next_state) = next_state else {
+                    return;
+                };
+                if let Some(requested) = entry.requested.take() {
+                    next_state.set(options[requested].clone());
+                }
+                entry.queued = format!("{:?}", *next_state);
+            })
+            .after(draw_state_debug_overlay),
+        )
+    }
+}
@@ -0,0 +1,633 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use bevy::{
+    ecs::{
+        archetype::{Archetype, ArchetypeComponentId},
+        component::{ComponentId, Tick},
+        query::{Access, FilteredAccess, ReadOnlyWorldQuery, WorldQuery},
+        storage::{Table, TableRow},
+        system::SystemId,
+        world::unsafe_world_cell::UnsafeWorldCell,
+    },
+    prelude::{Added, Commands, Component, Entity, Query, Res, Resource, World},
+};
+
+use crate::{
+    macro_support, MatchableState, MatchesStateTransition, NextMatchableState, StateMatcher,
+    StateSetters,
+};
+
+/// The current state of a per-entity state machine.
+///
+/// Requires [`StateMatchingApp::add_entity_state`](crate::StateMatchingApp::add_entity_state)
+/// to be called for `S`. Transitions are queued with [`NextEntityState<S>`], which is inserted
+/// alongside this component when using [`EntityState::bundle`].
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct EntityState<S: MatchableState>(pub(crate) S);
+
+impl<S: MatchableState> EntityState<S> {
+    /// Create a new entity state
+    pub fn new(state: S) -> Self {
+        Self(state)
+    }
+
+    /// Create the components needed for a per-entity state machine starting at `state`
+    pub fn bundle(state: S) -> (Self, NextEntityState<S>) {
+        (Self(state), NextEntityState::default())
+    }
+
+    /// Get the current state
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+
+    /// Check whether the current state matches `matcher`
+    pub fn matches<M>(&self, matcher: impl StateMatcher<S, M>) -> bool {
+        matcher.match_state(&self.0)
+    }
+}
+
+/// The next state of an [`EntityState<S>`], dereferencing to a [`NextMatchableState<S>`]
+#[derive(Component, Debug)]
+pub struct NextEntityState<S: MatchableState>(NextMatchableState<S>);
+
+impl<S: MatchableState> Default for NextEntityState<S> {
+    fn default() -> Self {
+        Self(NextMatchableState::Keep)
+    }
+}
+
+impl<S: MatchableState> Deref for NextEntityState<S> {
+    type Target = NextMatchableState<S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S: MatchableState> DerefMut for NextEntityState<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// The entity transition currently being processed.
+///
+/// Available to the one-shot systems registered with
+/// [`StateMatchingApp::on_entity_enter`](crate::StateMatchingApp::on_entity_enter) and
+/// [`StateMatchingApp::on_entity_exit`](crate::StateMatchingApp::on_entity_exit).
+#[derive(Resource, Debug, Clone)]
+pub struct EntityTransition<S: MatchableState> {
+    /// The entity whose state is changing
+    pub entity: Entity,
+    /// The state being exited, or `None` if the state machine was just added
+    pub exited: Option<S>,
+    /// The state being entered, or `None` if the state machine is being removed - like a sub-state
+    /// machine whose parent stopped matching
+    pub entered: Option<S>,
+}
+
+type TransitionMatcherFn<S> =
+    Box<dyn Fn(Option<&S>, Option<&S>) -> MatchesStateTransition + Send + Sync>;
+
+/// The enter and exit hooks registered for entity states of type `S`
+#[derive(Resource)]
+pub struct EntityStateHooks<S: MatchableState> {
+    on_enter: Vec<(TransitionMatcherFn<S>, SystemId)>,
+    on_exit: Vec<(TransitionMatcherFn<S>, SystemId)>,
+}
+
+impl<S: MatchableState> Default for EntityStateHooks<S> {
+    fn default() -> Self {
+        Self {
+            on_enter: vec![],
+            on_exit: vec![],
+        }
+    }
+}
+
+impl<S: MatchableState> EntityStateHooks<S> {
+    pub(crate) fn add_on_enter<M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    ) {
+        self.on_enter.push((
+            Box::new(move |main: Option<&S>, secondary: Option<&S>| {
+                matcher.match_state_transition(main, secondary)
+            }),
+            system,
+        ));
+    }
+
+    pub(crate) fn add_on_exit<M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    ) {
+        self.on_exit.push((
+            Box::new(move |main: Option<&S>, secondary: Option<&S>| {
+                matcher.match_state_transition(main, secondary)
+            }),
+            system,
+        ));
+    }
+
+    fn matching(
+        hooks: &[(TransitionMatcherFn<S>, SystemId)],
+        main: Option<&S>,
+        secondary: Option<&S>,
+    ) -> Vec<SystemId> {
+        hooks
+            .iter()
+            .filter(|(matcher, _)| {
+                matcher(main, secondary) == MatchesStateTransition::TransitionMatches
+            })
+            .map(|(_, system)| *system)
+            .collect()
+    }
+}
+
+fn run_hooks<S: MatchableState>(world: &mut World, transition: EntityTransition<S>, enter: bool) {
+    let Some(hooks) = world.get_resource::<EntityStateHooks<S>>() else {
+        return;
+    };
+    let systems = if enter {
+        EntityStateHooks::matching(
+            &hooks.on_enter,
+            transition.entered.as_ref(),
+            transition.exited.as_ref(),
+        )
+    } else {
+        EntityStateHooks::matching(
+            &hooks.on_exit,
+            transition.exited.as_ref(),
+            transition.entered.as_ref(),
+        )
+    };
+    if systems.is_empty() {
+        return;
+    }
+    world.insert_resource(transition);
+    for system in systems {
+        world.run_system(system).ok();
+    }
+    world.remove_resource::<EntityTransition<S>>();
+}
+
+/// Applies the transitions queued in every [`NextEntityState<S>`],
+/// running the matching exit and enter hooks for each entity.
+///
+/// Newly added [`EntityState<S>`]s run their enter hooks, with no exited state.
+pub fn apply_entity_state_transitions<S: MatchableState>(world: &mut World) {
+    let added = world
+        .query_filtered::<(Entity, &EntityState<S>), Added<EntityState<S>>>()
+        .iter(world)
+        .map(|(entity, state)| (entity, state.0.clone()))
+        .collect::<Vec<_>>();
+    for (entity, entered) in added {
+        run_hooks(
+            world,
+            EntityTransition {
+                entity,
+                exited: None,
+                entered: Some(entered),
+            },
+            true,
+        );
+    }
+
+    let setters = world.remove_resource::<StateSetters<S>>();
+    let transitions = world
+        .query::<(Entity, &EntityState<S>, &mut NextEntityState<S>)>()
+        .iter_mut(world)
+        .filter_map(|(entity, state, mut next)| {
+            if matches!(**next, NextMatchableState::Keep) {
+                return None;
+            }
+            let entered = next.resolve(&state.0, setters.as_ref());
+            **next = NextMatchableState::Keep;
+            let entered = entered?;
+            (entered != state.0).then(|| EntityTransition {
+                entity,
+                exited: Some(state.0.clone()),
+                entered: Some(entered),
+            })
+        })
+        .collect::<Vec<_>>();
+    if let Some(setters) = setters {
+        world.insert_resource(setters);
+    }
+
+    for transition in transitions {
+        let entity = transition.entity;
+        run_hooks(world, transition.clone(), false);
+        let (Some(mut state), Some(entered)) = (
+            world.get_mut::<EntityState<S>>(entity),
+            transition.entered.clone(),
+        ) else {
+            continue;
+        };
+        state.0 = entered;
+        run_hooks(world, transition, true);
+    }
+}
+
+/// The state an entity's [`EntityState<C>`] sub-state machine was in when its parent last exited.
+///
+/// Recorded by [`StateMatchingApp::add_entity_sub_state_with_history`](crate::StateMatchingApp::add_entity_sub_state_with_history),
+/// and used to restore the sub-state once the parent matches again.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct EntitySubStateHistory<C: MatchableState>(pub(crate) C);
+
+impl<C: MatchableState> EntitySubStateHistory<C> {
+    /// Get the remembered state
+    pub fn get(&self) -> &C {
+        &self.0
+    }
+}
+
+/// Inserts an [`EntityState<C>`] machine on the entity in the current [`EntityTransition<P>`],
+/// starting from its [`EntitySubStateHistory<C>`] if it has one, or `C::default()` otherwise.
+///
+/// Used by [`StateMatchingApp::add_entity_sub_state`](crate::StateMatchingApp::add_entity_sub_state).
+pub fn insert_entity_sub_state<P: MatchableState, C: MatchableState>(
+    mut commands: Commands,
+    transition: Res<EntityTransition<P>>,
+    history: Query<&EntitySubStateHistory<C>>,
+) {
+    let state = history
+        .get(transition.entity)
+        .map_or_else(|_| C::default(), |history| history.0.clone());
+    if let Some(mut entity) = commands.get_entity(transition.entity) {
+        entity.insert(EntityState::bundle(state));
+    }
+}
+
+/// Records the current [`EntityState<C>`] of the entity in the current [`EntityTransition<P>`]
+/// as its [`EntitySubStateHistory<C>`], before the sub-state machine is removed.
+///
+/// Used by [`StateMatchingApp::add_entity_sub_state_with_history`](crate::StateMatchingApp::add_entity_sub_state_with_history).
+pub fn record_entity_sub_state_history<P: MatchableState, C: MatchableState>(
+    mut commands: Commands,
+    transition: Res<EntityTransition<P>>,
+    states: Query<&EntityState<C>>,
+) {
+    let Ok(state) = states.get(transition.entity) else {
+        return;
+    };
+    if let Some(mut entity) = commands.get_entity(transition.entity) {
+        entity.insert(EntitySubStateHistory(state.0.clone()));
+    }
+}
+
+/// Removes the [`EntityState<C>`] machine from the entity in the current [`EntityTransition<P>`],
+/// running the exit hooks of its current state first - with no entered state.
+///
+/// Used by [`StateMatchingApp::add_entity_sub_state`](crate::StateMatchingApp::add_entity_sub_state).
+pub fn remove_entity_sub_state<P: MatchableState, C: MatchableState>(world: &mut World) {
+    let Some(entity) = world
+        .get_resource::<EntityTransition<P>>()
+        .map(|transition| transition.entity)
+    else {
+        return;
+    };
+    let Some(exited) = world
+        .get::<EntityState<C>>(entity)
+        .map(|state| state.0.clone())
+    else {
+        return;
+    };
+    run_hooks(
+        world,
+        EntityTransition {
+            entity,
+            exited: Some(exited),
+            entered: None,
+        },
+        false,
+    );
+    if let Some(mut entity) = world.get_entity_mut(entity) {
+        entity.remove::<(EntityState<C>, NextEntityState<C>)>();
+    }
+}
+
+/// A type-level matcher for [`EntityState<S>`], used by the [`InEntityState`] query filter.
+///
+/// Every matcher type generated by [`define_matcher!`](crate::define_matcher) implements it, so the
+/// same patterns as [`state_matches!`](crate::state_matches) can be used as a filter.
+/// It can also be implemented by hand:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum EnemyState {
+///   #[default]
+///   Idle,
+///   Chasing { target: Entity },
+///   Fleeing,
+/// }
+///
+/// struct Hunting;
+///
+/// impl EntityStateFilter<EnemyState> for Hunting {
+///     fn matches(state: &EnemyState) -> bool {
+///         matches!(state, EnemyState::Chasing { .. })
+///     }
+/// }
+///
+/// define_matcher!(Calm, EnemyState, Idle | Fleeing);
+///
+/// fn move_hunters(mut hunters: Query<&mut Transform, InEntityState<EnemyState, Hunting>>) {
+///     for mut transform in hunters.iter_mut() {
+///         transform.translation.x += 1.;
+///     }
+/// }
+///
+/// fn rest(calm: Query<Entity, InEntityState<EnemyState, Calm>>) {}
+/// ```
+pub trait EntityStateFilter<S: MatchableState>: Send + Sync + 'static {
+    /// Check whether the state matches
+    fn matches(state: &S) -> bool;
+}
+
+impl<S: MatchableState, T: macro_support::DefinedMatcher<S>> EntityStateFilter<S> for T {
+    fn matches(state: &S) -> bool {
+        <T as macro_support::DefinedMatcher<S>>::matches(Some(state), None)
+            != MatchesStateTransition::NoMatch
+    }
+}
+
+/// A query filter that only includes entities whose [`EntityState<S>`] matches `F`
+pub struct InEntityState<S: MatchableState, F: EntityStateFilter<S>>(PhantomData<(S, F)>);
+
+type EntityStateRef<S> = &'static EntityState<S>;
+
+/// # SAFETY: All access is delegated to the `&EntityState<S>` implementation
+unsafe impl<S: MatchableState, F: EntityStateFilter<S>> WorldQuery for InEntityState<S, F> {
+    type Item<'w> = ();
+    type Fetch<'w> = <EntityStateRef<S> as WorldQuery>::Fetch<'w>;
+    type ReadOnly = Self;
+    type State = <EntityStateRef<S> as WorldQuery>::State;
+
+    fn shrink<'wlong: 'wshort, 'wshort>(item: Self::Item<'wlong>) -> Self::Item<'wshort> {
+        item
+    }
+
+    unsafe fn init_fetch<'w>(
+        world: UnsafeWorldCell<'w>,
+        state: &Self::State,
+        last_run: Tick,
+        this_run: Tick,
+    ) -> Self::Fetch<'w> {
+        <EntityStateRef<S> as WorldQuery>::init_fetch(world, state, last_run, this_run)
+    }
+
+    const IS_DENSE: bool = <EntityStateRef<S> as WorldQuery>::IS_DENSE;
+
+    const IS_ARCHETYPAL: bool = false;
+
+    unsafe fn set_archetype<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        state: &Self::State,
+        archetype: &'w Archetype,
+        table: &'w Table,
+    ) {
+        <EntityStateRef<S> as WorldQuery>::set_archetype(fetch, state, archetype, table);
+    }
+
+    unsafe fn set_table<'w>(fetch: &mut Self::Fetch<'w>, state: &Self::State, table: &'w Table) {
+        <EntityStateRef<S> as WorldQuery>::set_table(fetch, state, table);
+    }
+
+    unsafe fn fetch<'w>(
+        _fetch: &mut Self::Fetch<'w>,
+        _entity: Entity,
+        _table_row: TableRow,
+    ) -> Self::Item<'w> {
+    }
+
+    unsafe fn filter_fetch(
+        fetch: &mut Self::Fetch<'_>,
+        entity: Entity,
+        table_row: TableRow,
+    ) -> bool {
+        F::matches(&<EntityStateRef<S> as WorldQuery>::fetch(fetch, entity, table_row).0)
+    }
+
+    fn update_component_access(state: &Self::State, access: &mut FilteredAccess<ComponentId>) {
+        <EntityStateRef<S> as WorldQuery>::update_component_access(state, access);
+    }
+
+    fn update_archetype_component_access(
+        state: &Self::State,
+        archetype: &Archetype,
+        access: &mut Access<ArchetypeComponentId>,
+    ) {
+        <EntityStateRef<S> as WorldQuery>::update_archetype_component_access(
+            state, archetype, access,
+        );
+    }
+
+    fn init_state(world: &mut World) -> Self::State {
+        <EntityStateRef<S> as WorldQuery>::init_state(world)
+    }
+
+    fn matches_component_set(
+        state: &Self::State,
+        set_contains_id: &impl Fn(ComponentId) -> bool,
+    ) -> bool {
+        <EntityStateRef<S> as WorldQuery>::matches_component_set(state, set_contains_id)
+    }
+}
+
+/// # SAFETY: `InEntityState` only reads the `EntityState<S>` component
+unsafe impl<S: MatchableState, F: EntityStateFilter<S>> ReadOnlyWorldQuery for InEntityState<S, F> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        define_matcher, EntityState, EntitySubStateHistory, EntityTransition, InEntityState,
+        NextEntityState, StateMatchingApp,
+    };
+    use bevy::prelude::{App, Entity, Res, ResMut, Resource, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum Parent {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum Child {
+        #[default]
+        X,
+        Y,
+    }
+
+    #[derive(Resource, Default)]
+    struct Entered(Vec<Entity>);
+
+    #[test]
+    fn entity_states_run_enter_hooks_for_matching_transitions() {
+        let mut app = App::new();
+        app.init_resource::<Entered>()
+            .add_entity_state::<Parent>()
+            .on_entity_enter(
+                Parent::B,
+                |transition: Res<EntityTransition<Parent>>, mut entered: ResMut<Entered>| {
+                    entered.0.push(transition.entity);
+                },
+            );
+        let entity = app.world.spawn(EntityState::bundle(Parent::A)).id();
+
+        app.update();
+        assert!(app.world.resource::<Entered>().0.is_empty());
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::B);
+        app.update();
+        assert_eq!(app.world.resource::<Entered>().0, vec![entity]);
+        assert_eq!(
+            app.world.get::<EntityState<Parent>>(entity).unwrap().get(),
+            &Parent::B
+        );
+    }
+
+    define_matcher!(IsB, Parent, B);
+
+    #[test]
+    fn defined_matchers_filter_entity_states() {
+        let mut app = App::new();
+        app.add_entity_state::<Parent>();
+        app.world.spawn(EntityState::bundle(Parent::A));
+        let b = app.world.spawn(EntityState::bundle(Parent::B)).id();
+
+        let matching = app
+            .world
+            .query_filtered::<Entity, InEntityState<Parent, IsB>>()
+            .iter(&app.world)
+            .collect::<Vec<_>>();
+        assert_eq!(matching, vec![b]);
+    }
+
+    #[test]
+    fn entity_sub_states_only_exist_while_the_parent_matches() {
+        let mut app = App::new();
+        app.add_entity_state::<Parent>()
+            .add_entity_sub_state::<Parent, Child, _>(Parent::B);
+        let entity = app.world.spawn(EntityState::bundle(Parent::A)).id();
+
+        app.update();
+        assert!(app.world.get::<EntityState<Child>>(entity).is_none());
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::B);
+        app.update();
+        assert_eq!(
+            app.world.get::<EntityState<Child>>(entity).unwrap().get(),
+            &Child::X
+        );
+
+        app.world
+            .get_mut::<NextEntityState<Child>>(entity)
+            .unwrap()
+            .set(Child::Y);
+        app.update();
+        assert_eq!(
+            app.world.get::<EntityState<Child>>(entity).unwrap().get(),
+            &Child::Y
+        );
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::A);
+        app.update();
+        assert!(app.world.get::<EntityState<Child>>(entity).is_none());
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::B);
+        app.update();
+        assert_eq!(
+            app.world.get::<EntityState<Child>>(entity).unwrap().get(),
+            &Child::X
+        );
+    }
+
+    #[test]
+    fn removed_entity_sub_states_run_their_exit_hooks() {
+        let mut app = App::new();
+        app.init_resource::<Entered>()
+            .add_entity_state::<Parent>()
+            .add_entity_sub_state::<Parent, Child, _>(Parent::B)
+            .on_entity_exit(
+                Child::X,
+                |transition: Res<EntityTransition<Child>>, mut exited: ResMut<Entered>| {
+                    assert!(transition.entered.is_none());
+                    exited.0.push(transition.entity);
+                },
+            );
+        let entity = app.world.spawn(EntityState::bundle(Parent::B)).id();
+        app.update();
+        assert!(app.world.resource::<Entered>().0.is_empty());
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::A);
+        app.update();
+        assert_eq!(app.world.resource::<Entered>().0, vec![entity]);
+        assert!(app.world.get::<EntityState<Child>>(entity).is_none());
+    }
+
+    #[test]
+    fn entity_sub_states_with_history_are_restored() {
+        let mut app = App::new();
+        app.add_entity_state::<Parent>()
+            .add_entity_sub_state_with_history::<Parent, Child, _>(Parent::B);
+        let entity = app.world.spawn(EntityState::bundle(Parent::B)).id();
+        app.update();
+
+        app.world
+            .get_mut::<NextEntityState<Child>>(entity)
+            .unwrap()
+            .set(Child::Y);
+        app.update();
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::A);
+        app.update();
+        assert!(app.world.get::<EntityState<Child>>(entity).is_none());
+        assert_eq!(
+            app.world
+                .get::<EntitySubStateHistory<Child>>(entity)
+                .unwrap()
+                .get(),
+            &Child::Y
+        );
+
+        app.world
+            .get_mut::<NextEntityState<Parent>>(entity)
+            .unwrap()
+            .set(Parent::B);
+        app.update();
+        assert_eq!(
+            app.world.get::<EntityState<Child>>(entity).unwrap().get(),
+            &Child::Y
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    app::{MainScheduleOrder, RunFixedUpdateLoop},
+    ecs::schedule::ScheduleLabel,
+    prelude::{App, IntoSystemConfigs, Res, ResMut, Resource, State, StateTransition, World},
+};
+
+use crate::{apply_state_transition, MatchableState};
+
+/// Tracks whether the fixed timestep loop is running, so transitions of the states added with
+/// [`StateMatchingApp::defer_transitions_in_fixed_update`](crate::StateMatchingApp::defer_transitions_in_fixed_update)
+/// can be held back until it is done.
+#[derive(Resource, Default, Debug)]
+pub struct FixedLoopGuard {
+    running: bool,
+}
+
+impl FixedLoopGuard {
+    /// Whether the fixed timestep loop is currently running
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// Whether a transition of `S` was held back during the current fixed timestep loop
+#[derive(Resource)]
+struct DeferredInFixedLoop<S: MatchableState> {
+    held: bool,
+    marker: PhantomData<fn() -> S>,
+}
+
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+struct FixedLoopStarting;
+
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+struct FixedLoopFinished;
+
+fn set_fixed_loop_running<const RUNNING: bool>(mut guard: ResMut<FixedLoopGuard>) {
+    guard.running = RUNNING;
+}
+
+/// Whether transitions of `S` should be held back, because the fixed timestep loop is running.
+///
+/// Held transitions are flushed by [`flush_deferred_transitions`] once the loop is done.
+pub(crate) fn transitions_deferred<S: MatchableState>(world: &mut World) -> bool {
+    let running = world
+        .get_resource::<FixedLoopGuard>()
+        .is_some_and(|guard| guard.running);
+    match world.get_resource_mut::<DeferredInFixedLoop<S>>() {
+        Some(mut deferred) if running => {
+            deferred.held = true;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Whether transitions of `S` would be held back right now, without recording them as held
+pub(crate) fn transitions_would_defer<S: MatchableState>(world: &World) -> bool {
+    world.contains_resource::<DeferredInFixedLoop<S>>()
+        && world
+            .get_resource::<FixedLoopGuard>()
+            .is_some_and(|guard| guard.running)
+}
+
+/// Apply the transitions of `S` held back during the fixed timestep loop that just finished
+fn flush_deferred_transitions<S: MatchableState>(world: &mut World) {
+    let held = world
+        .get_resource_mut::<DeferredInFixedLoop<S>>()
+        .is_some_and(|mut deferred| std::mem::replace(&mut deferred.held, false));
+    if held {
+        apply_state_transition::<S>(world);
+    }
+}
+
+/// The state of `S` when the current fixed timestep loop started - the same for every substep of a frame.
+///
+/// Used to gate the systems added with [`StateMatchingApp::add_fixed_systems_in`](crate::StateMatchingApp::add_fixed_systems_in).
+#[derive(Resource, Debug)]
+pub struct FixedStateSnapshot<S: MatchableState>(Option<S>);
+
+impl<S: MatchableState> Default for FixedStateSnapshot<S> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<S: MatchableState> FixedStateSnapshot<S> {
+    /// The state when the fixed timestep loop started, if it existed
+    pub fn get(&self) -> Option<&S> {
+        self.0.as_ref()
+    }
+}
+
+fn take_fixed_state_snapshot<S: MatchableState>(
+    state: Option<Res<State<S>>>,
+    mut snapshot: ResMut<FixedStateSnapshot<S>>,
+) {
+    snapshot.0 = state.map(|state| state.get().clone());
+}
+
+/// Hold back transitions of `S` while the fixed timestep loop runs, flushing them once it's done
+pub(crate) fn defer_transitions_in_fixed_update<S: MatchableState>(app: &mut App) {
+    if app.world.contains_resource::<DeferredInFixedLoop<S>>() {
+        return;
+    }
+    add_fixed_loop_schedules(app);
+    app.insert_resource(DeferredInFixedLoop::<S> {
+        held: false,
+        marker: PhantomData,
+    })
+    .add_systems(
+        FixedLoopFinished,
+        flush_deferred_transitions::<S>.after(set_fixed_loop_running::<false>),
+    );
+}
+
+/// Keep a [`FixedStateSnapshot<S>`], taken right before each fixed timestep loop
+pub(crate) fn snapshot_state_for_fixed_update<S: MatchableState>(app: &mut App) {
+    if app.world.contains_resource::<FixedStateSnapshot<S>>() {
+        return;
+    }
+    add_fixed_loop_schedules(app);
+    app.init_resource::<FixedStateSnapshot<S>>()
+        .add_systems(FixedLoopStarting, take_fixed_state_snapshot::<S>);
+}
+
+/// Wrap the fixed timestep loop with a schedule on each side, tracking it in the [`FixedLoopGuard`]
+fn add_fixed_loop_schedules(app: &mut App) {
+    if app.world.contains_resource::<FixedLoopGuard>() {
+        return;
+    }
+    app.init_resource::<FixedLoopGuard>()
+        .add_systems(FixedLoopStarting, set_fixed_loop_running::<true>)
+        .add_systems(FixedLoopFinished, set_fixed_loop_running::<false>);
+    if let Some(mut order) = app.world.get_resource_mut::<MainScheduleOrder>() {
+        order.insert_after(StateTransition, FixedLoopStarting);
+        order.insert_after(RunFixedUpdateLoop, FixedLoopFinished);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        apply_state_transition, FixedLoopGuard, MatchableStatePlugin, NextMatchableState,
+        StateMatchingApp,
+    };
+    use bevy::{
+        prelude::{App, FixedUpdate, IntoSystemConfigs, Res, ResMut, Resource, State, States},
+        time::{TimePlugin, TimeUpdateStrategy},
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Seen(Vec<TestState>);
+
+    #[test]
+    fn transitions_queued_in_the_fixed_loop_wait_for_it_to_finish() {
+        let mut app = App::new();
+        app.add_plugins((
+            TimePlugin,
+            MatchableStatePlugin::<TestState>::default().in_schedule(FixedUpdate),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            50,
+        )))
+        .init_resource::<Seen>()
+        .defer_transitions_in_fixed_update::<TestState>()
+        .add_systems(
+            FixedUpdate,
+            (
+                (|mut next_state: ResMut<NextMatchableState<TestState>>| {
+                    next_state.set(TestState::B)
+                })
+                .before(apply_state_transition::<TestState>),
+                (|state: Res<State<TestState>>, mut seen: ResMut<Seen>| {
+                    seen.0.push(state.get().clone())
+                })
+                .after(apply_state_transition::<TestState>),
+            ),
+        );
+        // The first update only starts the clock, so it might not run the fixed loop yet
+        for _ in 0..2 {
+            if app.world.resource::<Seen>().0.is_empty() {
+                app.update();
+            }
+        }
+
+        let seen = &app.world.resource::<Seen>().0;
+        assert!(seen.len() > 1);
+        assert!(seen.iter().all(|state| state == &TestState::A));
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert!(!app.world.resource::<FixedLoopGuard>().is_running());
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    #[test]
+    fn fixed_systems_use_the_state_from_the_start_of_the_loop() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .add_fixed_systems_in(TestState::A, |mut runs: ResMut<Runs>| runs.0 += 1);
+        app.update();
+
+        app.world.run_schedule(FixedUpdate);
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        apply_state_transition::<TestState>(&mut app.world);
+        app.world.run_schedule(FixedUpdate);
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+
+        app.update();
+        app.world.run_schedule(FixedUpdate);
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{
+    log::debug,
+    prelude::{Resource, World},
+    utils::Instant,
+};
+
+use crate::{state::apply_next_transition, MatchableState, TransitionQueue};
+
+/// Limits how much work [`apply_state_transition`](crate::apply_state_transition) does per run,
+/// when draining a [`TransitionQueue<S>`] - set with
+/// [`MatchableStatePlugin::frame_budget`](crate::MatchableStatePlugin::frame_budget).
+///
+/// Transitions are applied one after the other until the queue is empty or a limit is reached,
+/// and the remainder is deferred to the next run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransitionBudget {
+    /// The maximum number of transitions applied per run
+    pub max_transitions: Option<usize>,
+    /// The maximum time spent in transition schedules per run - checked after each transition,
+    /// so a single slow transition can still go over it
+    pub max_time: Option<Duration>,
+}
+
+impl TransitionBudget {
+    /// Apply at most `max_transitions` transitions per run
+    pub fn transitions(max_transitions: usize) -> Self {
+        Self {
+            max_transitions: Some(max_transitions.max(1)),
+            max_time: None,
+        }
+    }
+
+    /// Stop applying transitions once `max_time` was spent in a run
+    pub fn time(max_time: Duration) -> Self {
+        Self {
+            max_transitions: None,
+            max_time: Some(max_time),
+        }
+    }
+
+    fn exceeded(&self, applied: usize, elapsed: Duration) -> bool {
+        self.max_transitions.is_some_and(|max| applied >= max)
+            || self.max_time.is_some_and(|max| elapsed >= max)
+    }
+}
+
+/// Statistics about the last run of [`apply_state_transition`](crate::apply_state_transition) for `S`,
+/// kept while a [`TransitionBudget`] is set
+#[derive(Resource, Debug)]
+pub struct TransitionBatchStats<S: MatchableState> {
+    /// The number of transitions that ran in the last run - idle, rejected and held runs apply none
+    pub applied: usize,
+    /// The time spent applying them
+    pub elapsed: Duration,
+    /// The number of queued transitions deferred to the next run
+    pub deferred: usize,
+    /// The number of runs that deferred transitions since the app started
+    pub runs_over_budget: u64,
+    marker: PhantomData<fn() -> S>,
+}
+
+impl<S: MatchableState> Default for TransitionBatchStats<S> {
+    fn default() -> Self {
+        Self {
+            applied: 0,
+            elapsed: Duration::ZERO,
+            deferred: 0,
+            runs_over_budget: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+fn queued_transitions<S: MatchableState>(world: &World) -> usize {
+    world
+        .get_resource::<TransitionQueue<S>>()
+        .map_or(0, |queue| queue.len())
+}
+
+/// Apply queued transitions of `S` until the queue is empty, progress stops (because of a
+/// transition phase, for example) or `budget` runs out
+pub(crate) fn apply_within_budget<S: MatchableState>(world: &mut World, budget: TransitionBudget) {
+    let start = Instant::now();
+    let mut applied = 0;
+    loop {
+        let queued = queued_transitions::<S>(world);
+        if apply_next_transition::<S>(world) {
+            applied += 1;
+        }
+        let remaining = queued_transitions::<S>(world);
+        if remaining == 0 || remaining >= queued {
+            break;
+        }
+        if budget.exceeded(applied, start.elapsed()) {
+            debug!(
+                "{} went over its transition budget - deferring {remaining} transitions",
+                std::any::type_name::<S>()
+            );
+            break;
+        }
+    }
+    let deferred = queued_transitions::<S>(world);
+    let mut stats = world.get_resource_or_insert_with(TransitionBatchStats::<S>::default);
+    stats.applied = applied;
+    stats.elapsed = start.elapsed();
+    stats.deferred = deferred;
+    if deferred > 0 {
+        stats.runs_over_budget += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatchableStatePlugin, StateMatchingSwitchWorld, TransitionBatchStats, TransitionBudget,
+        TransitionQueue,
+    };
+    use bevy::prelude::{App, OnEnter, ResMut, Resource, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+        D,
+    }
+
+    #[derive(Resource, Default)]
+    struct Entered(Vec<TestState>);
+
+    #[test]
+    fn queued_transitions_are_drained_within_the_budget() {
+        let mut app = App::new();
+        app.init_resource::<Entered>().add_plugins(
+            MatchableStatePlugin::<TestState>::default()
+                .frame_budget(TransitionBudget::transitions(2)),
+        );
+        for state in [TestState::B, TestState::C, TestState::D] {
+            app.add_systems(
+                OnEnter(state.clone()),
+                move |mut entered: ResMut<Entered>| entered.0.push(state.clone()),
+            );
+        }
+        app.update();
+
+        let mut queue = app.world.resource_mut::<TransitionQueue<TestState>>();
+        queue.push(TestState::B);
+        queue.push(TestState::C);
+        queue.push(TestState::D);
+        app.update();
+        assert_eq!(
+            app.world.resource::<Entered>().0,
+            vec![TestState::B, TestState::C]
+        );
+        let stats = app.world.resource::<TransitionBatchStats<TestState>>();
+        assert_eq!((stats.applied, stats.deferred), (2, 1));
+
+        app.update();
+        assert_eq!(
+            app.world.resource::<Entered>().0,
+            vec![TestState::B, TestState::C, TestState::D]
+        );
+        assert_eq!(
+            app.world
+                .resource::<TransitionBatchStats<TestState>>()
+                .runs_over_budget,
+            1
+        );
+    }
+
+    #[test]
+    fn runs_that_apply_nothing_count_no_transitions() {
+        let mut app = App::new();
+        app.add_plugins(
+            MatchableStatePlugin::<TestState>::default()
+                .frame_budget(TransitionBudget::transitions(2)),
+        );
+        app.update();
+        app.update();
+        let stats = app.world.resource::<TransitionBatchStats<TestState>>();
+        assert_eq!((stats.applied, stats.deferred), (0, 0));
+
+        app.world.set_state_machine_enabled::<TestState>(false);
+        app.world
+            .resource_mut::<TransitionQueue<TestState>>()
+            .push(TestState::B);
+        app.update();
+        let stats = app.world.resource::<TransitionBatchStats<TestState>>();
+        assert_eq!((stats.applied, stats.deferred), (0, 1));
+    }
+}
@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::{DetectChanges, Res, ResMut, Resource, State};
+
+use crate::MatchableState;
+
+/// The number of frames and fixed update ticks spent in the current state of `S` - tracked once added with
+/// [`StateMatchingApp::add_frames_in_state`](crate::StateMatchingApp::add_frames_in_state).
+///
+/// Both counts are reset when a transition of `S` is applied, and grow at the end of each frame and
+/// `FixedUpdate` tick - so they are 0 during the frame the state was entered in, and 2 on the third frame:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum GameState {
+/// #   #[default]
+/// #   Menu,
+/// #   Playing,
+/// # }
+/// fn spawn_enemies(frames: Res<FramesInState<GameState>>) {
+///     if frames.frames() == 2 {
+///         // ...
+///     }
+/// }
+///
+/// App::new()
+///     .add_matchable_state::<GameState>()
+///     .add_frames_in_state::<GameState>()
+///     .add_systems(Update, spawn_enemies.run_in(GameState::Playing));
+/// ```
+#[derive(Resource, Debug)]
+pub struct FramesInState<S: MatchableState> {
+    frames: u64,
+    fixed_ticks: u64,
+    marker: PhantomData<fn() -> S>,
+}
+
+impl<S: MatchableState> Default for FramesInState<S> {
+    fn default() -> Self {
+        Self {
+            frames: 0,
+            fixed_ticks: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<S: MatchableState> FramesInState<S> {
+    /// The number of frames that ended since the current state was entered
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The number of `FixedUpdate` ticks that ran since the current state was entered
+    pub fn fixed_ticks(&self) -> u64 {
+        self.fixed_ticks
+    }
+}
+
+/// Reset the [`FramesInState<S>`] whenever a transition of `S` was applied
+pub(crate) fn reset_frames_in_state<S: MatchableState>(
+    state: Res<State<S>>,
+    mut frames: ResMut<FramesInState<S>>,
+) {
+    if state.is_changed() {
+        *frames = FramesInState::default();
+    }
+}
+
+pub(crate) fn count_frames_in_state<S: MatchableState>(mut frames: ResMut<FramesInState<S>>) {
+    frames.frames += 1;
+}
+
+pub(crate) fn count_fixed_ticks_in_state<S: MatchableState>(mut frames: ResMut<FramesInState<S>>) {
+    frames.fixed_ticks += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FramesInState, NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{App, FixedUpdate, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn counts(app: &App) -> (u64, u64) {
+        let frames = app.world.resource::<FramesInState<TestState>>();
+        (frames.frames(), frames.fixed_ticks())
+    }
+
+    #[test]
+    fn frames_and_ticks_reset_on_transition() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_frames_in_state::<TestState>();
+        app.update();
+        app.update();
+        app.world.run_schedule(FixedUpdate);
+        assert_eq!(counts(&app), (2, 1));
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(counts(&app), (1, 0));
+        app.update();
+        assert_eq!(counts(&app), (2, 0));
+    }
+}
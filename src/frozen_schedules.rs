@@ -0,0 +1,194 @@
+use bevy::{
+    ecs::schedule::{ScheduleLabel, Schedules},
+    prelude::{Local, Mut, Resource, Schedule, State, World},
+    utils::HashMap,
+};
+
+use crate::{MatchableState, StateMatcher};
+
+/// Holds the schedules that are currently frozen by
+/// [`StateMatchingApp::pause_schedule_in`](crate::StateMatchingApp::pause_schedule_in).
+///
+/// A frozen schedule stays in [`Schedules`] between frames, so systems added to it keep their
+/// sets and ordering. Only while the `Main` schedule runs is it set aside for an empty one with the same label,
+/// so anything running it keeps working but none of its systems execute.
+#[derive(Resource)]
+pub struct FrozenSchedules {
+    schedules: Schedules,
+    holders: HashMap<Box<dyn ScheduleLabel>, usize>,
+}
+
+impl Default for FrozenSchedules {
+    fn default() -> Self {
+        Self {
+            schedules: Schedules::new(),
+            holders: HashMap::default(),
+        }
+    }
+}
+
+impl FrozenSchedules {
+    /// Whether the schedule with the given label is currently frozen
+    pub fn is_frozen(&self, label: &dyn ScheduleLabel) -> bool {
+        self.holders.contains_key(label)
+    }
+
+    fn freeze(&mut self, world: &mut World, label: impl ScheduleLabel + Clone) {
+        let holders = self
+            .holders
+            .entry(Box::new(label.clone()) as Box<dyn ScheduleLabel>)
+            .or_insert(0);
+        *holders += 1;
+        self.set_aside(world, &label);
+    }
+
+    fn thaw(&mut self, world: &mut World, label: impl ScheduleLabel + Clone) {
+        let Some(holders) = self.holders.get_mut(&label as &dyn ScheduleLabel) else {
+            return;
+        };
+        *holders = holders.saturating_sub(1);
+        if *holders > 0 {
+            return;
+        }
+        self.holders.remove(&label as &dyn ScheduleLabel);
+        self.put_back(world, &label);
+    }
+
+    fn set_aside(&mut self, world: &mut World, label: &dyn ScheduleLabel) {
+        if self.schedules.contains(label) {
+            return;
+        }
+        let mut schedules = world.resource_mut::<Schedules>();
+        if let Some(schedule) = schedules.remove(label) {
+            schedules.insert(Schedule::new(label.dyn_clone()));
+            self.schedules.insert(schedule);
+        }
+    }
+
+    fn put_back(&mut self, world: &mut World, label: &dyn ScheduleLabel) {
+        if let Some(schedule) = self.schedules.remove(label) {
+            world.resource_mut::<Schedules>().insert(schedule);
+        }
+    }
+}
+
+/// Sets the frozen schedules aside before the `Main` schedule runs
+pub(crate) fn set_aside_frozen_schedules(world: &mut World) {
+    world.resource_scope(|world, mut frozen: Mut<FrozenSchedules>| {
+        let labels: Vec<_> = frozen
+            .holders
+            .keys()
+            .map(|label| label.dyn_clone())
+            .collect();
+        for label in labels {
+            frozen.set_aside(world, label.as_ref());
+        }
+    });
+}
+
+/// Puts the frozen schedules back once the `Main` schedule is done,
+/// so they are in place whenever systems get added to them
+pub(crate) fn put_back_frozen_schedules(world: &mut World) {
+    world.resource_scope(|world, mut frozen: Mut<FrozenSchedules>| {
+        let labels: Vec<_> = frozen
+            .schedules
+            .iter()
+            .map(|(label, _)| label.dyn_clone())
+            .collect();
+        for label in labels {
+            frozen.put_back(world, label.as_ref());
+        }
+    });
+}
+
+/// Creates an exclusive system that freezes the schedule `label` whenever the current state matches `matcher`,
+/// and thaws it once it no longer does.
+///
+/// The schedule can't be frozen while it is running, so this system should not be added to `label` itself.
+pub fn freeze_schedule_in<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+    label: impl ScheduleLabel + Clone,
+) -> impl FnMut(&mut World, Local<bool>) {
+    move |world: &mut World, mut holding: Local<bool>| {
+        let should_freeze = world
+            .get_resource::<State<S>>()
+            .is_some_and(|state| matcher.match_state(state.get()));
+        if should_freeze == *holding {
+            return;
+        }
+        *holding = should_freeze;
+        world.resource_scope(|world, mut frozen: Mut<FrozenSchedules>| {
+            if should_freeze {
+                frozen.freeze(world, label.clone());
+            } else {
+                frozen.thaw(world, label.clone());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{App, IntoSystemConfigs, ResMut, Resource, States, Update};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        Playing,
+        Paused,
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs(Vec<&'static str>);
+
+    fn set(app: &mut App, state: TestState) {
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(state);
+        app.update();
+    }
+
+    #[test]
+    fn frozen_schedules_resume_once_thawed() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .pause_schedule_in(TestState::Paused, Update)
+            .add_systems(Update, |mut runs: ResMut<Runs>| runs.0.push("original"));
+        app.update();
+
+        set(&mut app, TestState::Paused);
+        assert_eq!(app.world.resource::<Runs>().0, vec!["original"]);
+
+        set(&mut app, TestState::Playing);
+        assert_eq!(app.world.resource::<Runs>().0, vec!["original", "original"]);
+    }
+
+    #[test]
+    fn systems_added_while_frozen_keep_their_ordering() {
+        fn original(mut runs: ResMut<Runs>) {
+            runs.0.push("original");
+        }
+
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .pause_schedule_in(TestState::Paused, Update)
+            .add_systems(Update, original);
+        app.update();
+        set(&mut app, TestState::Paused);
+
+        app.add_systems(
+            Update,
+            (|mut runs: ResMut<Runs>| runs.0.push("before")).before(original),
+        );
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, vec!["original"]);
+
+        set(&mut app, TestState::Playing);
+        app.world.resource_mut::<Runs>().0.clear();
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, vec!["before", "original"]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    core::FrameCount,
+    prelude::{App, IntoSystemConfigs, Res, ResMut, Resource, State, StateTransition, World},
+};
+
+use crate::{apply_state_transition, state::AppliedState, MatchableState, NextMatchableState};
+
+/// A fixed-size ring buffer of past values of `State<S>`, keyed by frame - for rollback networking.
+#[derive(Resource, Debug)]
+pub struct StateHistory<S: MatchableState> {
+    capacity: usize,
+    entries: VecDeque<(u32, S)>,
+}
+
+impl<S: MatchableState> StateHistory<S> {
+    /// Create an empty history keeping up to `capacity` frames
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record `state` as the value at `frame`, replacing any later frames
+    pub fn save(&mut self, frame: u32, state: S) {
+        while self
+            .entries
+            .back()
+            .is_some_and(|(saved, _)| *saved >= frame)
+        {
+            self.entries.pop_back();
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((frame, state));
+    }
+
+    /// The value recorded at `frame`, if it is still in the buffer
+    pub fn get(&self, frame: u32) -> Option<&S> {
+        self.entries
+            .iter()
+            .find(|(saved, _)| *saved == frame)
+            .map(|(_, state)| state)
+    }
+
+    /// The oldest frame still in the buffer
+    pub fn oldest_frame(&self) -> Option<u32> {
+        self.entries.front().map(|(frame, _)| *frame)
+    }
+}
+
+/// Record the current value of `State<S>` at `frame` in the [`StateHistory<S>`]
+pub fn save_state_for_rollback<S: MatchableState>(world: &mut World, frame: u32) {
+    let Some(state) = world.get_resource::<State<S>>().map(|s| s.get().clone()) else {
+        return;
+    };
+    if let Some(mut history) = world.get_resource_mut::<StateHistory<S>>() {
+        history.save(frame, state);
+    }
+}
+
+/// Restore `State<S>` to its value at `frame`, returning whether that frame was in the [`StateHistory<S>`].
+///
+/// The state is overwritten directly, without running any exit or enter schedules - the
+/// rolled back frames are resimulated from there. Any queued transition is discarded.
+pub fn restore_state_for_rollback<S: MatchableState>(world: &mut World, frame: u32) -> bool {
+    let Some(state) = world
+        .get_resource::<StateHistory<S>>()
+        .and_then(|history| history.get(frame).cloned())
+    else {
+        return false;
+    };
+    world.insert_resource(State::new(state.clone()));
+    world.insert_resource(AppliedState(state));
+    world.insert_resource(NextMatchableState::<S>::Keep);
+    true
+}
+
+fn record_state_history<S: MatchableState>(
+    state: Res<State<S>>,
+    frame: Res<FrameCount>,
+    mut history: ResMut<StateHistory<S>>,
+) {
+    history.save(frame.0, state.get().clone());
+}
+
+/// A trait adding state history to a bevy `App`
+pub trait StateMatchingHistoryApp {
+    /// Keep the last `capacity` frames of `S` in a [`StateHistory<S>`], recorded every frame
+    /// once transitions are applied, keyed by bevy's `FrameCount`.
+    ///
+    /// Rollback libraries with their own frame numbers can use [`save_state_for_rollback`] and
+    /// [`restore_state_for_rollback`] directly instead, after inserting a [`StateHistory<S>`].
+    fn add_state_history<S: MatchableState>(&mut self, capacity: usize) -> &mut Self;
+}
+
+impl StateMatchingHistoryApp for App {
+    fn add_state_history<S: MatchableState>(&mut self, capacity: usize) -> &mut Self {
+        self.insert_resource(StateHistory::<S>::new(capacity))
+            .init_resource::<FrameCount>()
+            .add_systems(
+                StateTransition,
+                record_state_history::<S>.after(apply_state_transition::<S>),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply_state_transition, restore_state_for_rollback, save_state_for_rollback, Exiting,
+        NextMatchableState, StateHistory,
+    };
+    use bevy::prelude::{ResMut, Resource, Schedule, State, States, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Exits(u32);
+
+    #[test]
+    fn history_drops_the_oldest_frames() {
+        let mut history = StateHistory::new(2);
+        history.save(1, TestState::A);
+        history.save(2, TestState::B);
+        history.save(3, TestState::A);
+        assert_eq!(history.oldest_frame(), Some(2));
+        assert_eq!(history.get(1), None);
+        assert_eq!(history.get(2), Some(&TestState::B));
+    }
+
+    #[test]
+    fn restoring_does_not_run_schedules() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.init_resource::<NextMatchableState<TestState>>();
+        world.init_resource::<Exits>();
+        world.insert_resource(StateHistory::<TestState>::new(8));
+        let mut exiting = Schedule::new(Exiting);
+        exiting.add_systems(|mut exits: ResMut<Exits>| exits.0 += 1);
+        world.add_schedule(exiting);
+
+        save_state_for_rollback::<TestState>(&mut world, 1);
+        world.insert_resource(NextMatchableState::Value(TestState::B));
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<Exits>().0, 1);
+
+        assert!(restore_state_for_rollback::<TestState>(&mut world, 1));
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        assert_eq!(world.resource::<Exits>().0, 1);
+    }
+}
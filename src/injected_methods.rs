@@ -1,42 +1,738 @@
+use std::{path::Path, time::Duration};
+
 use bevy::{
-    ecs::schedule::{SystemConfigs, SystemSetConfigs},
+    app::Plugins,
+    ecs::schedule::{ScheduleLabel, SystemConfigs, SystemSetConfigs},
+    log::warn,
     prelude::{
-        run_once, App, IntoSystemConfigs, IntoSystemSetConfigs, State, StateTransition, States,
+        run_once, App, Commands, Component, FixedUpdate, IntoSystem, IntoSystemConfigs,
+        IntoSystemSetConfigs, Last, Main, NextState, Plugin, Res, ResMut, Resource,
+        StateTransition, States, World,
     },
 };
 
 use crate::{
-    state::{apply_state_transition, run_enter_schedule, MatchableState},
-    NextMatchableState, StateMatcher, StateMatcherSystem,
+    apply_entity_state_transitions, bridge_next_state, collect_state_commands, despawn_with,
+    fixed_timestep::snapshot_state_for_fixed_update,
+    frames_in_state::{count_fixed_ticks_in_state, count_frames_in_state, reset_frames_in_state},
+    freeze_schedule_in,
+    frozen_schedules::{put_back_frozen_schedules, set_aside_frozen_schedules},
+    insert_entity_sub_state, record_entity_sub_state_history, remove_entity_sub_state,
+    scoped_plugins::add_plugins_in,
+    state::{
+        apply_state_transition, once_per_entry, run_enter_schedule, ActiveTransition, InitialEnter,
+        InitialStateFn, MatchableState,
+    },
+    state_commands::{hide_initial_enter_from_bevy, restore_initial_enter},
+    validate_transition_table, Entering, EntityStateHooks, ExitSet, Exiting, FixedStateSnapshot,
+    FramesInState, FrozenSchedules, MatchableStatePlugin, MatchesStateTransition,
+    NextMatchableState, RegisterableState, StateCommand, StateGraph, StateLock, StateMatcher,
+    StateMatcherSystem, StateSet, StateSetters, TransitionCooldowns, TransitionDuration,
+    TransitionHooks, TransitionPhase, TransitionRejected, TransitionStepping, TransitionTable,
 };
 
+/// A run condition that is true while a transition of `S` is in progress - not one of another state type -
+/// and `condition` holds for its main and secondary states. Re-entering a state never matches.
+fn in_transition_of<S: MatchableState>(
+    condition: impl Fn(&S, Option<&S>) -> bool + Send + Sync + 'static,
+) -> impl FnMut(Option<Res<ActiveTransition<S>>>) -> bool {
+    move |transition: Option<Res<ActiveTransition<S>>>| {
+        let Some(transition) = transition else {
+            return false;
+        };
+        let (Some(main), secondary) = (transition.get_main(), transition.get_secondary()) else {
+            return false;
+        };
+        secondary != Some(main) && condition(main, secondary)
+    }
+}
+
 /// A trait adding support for state matching to a bevy `App`
 pub trait StateMatchingApp {
     /// Add a state that support state matching to the application
-    fn add_matchable_state<S: MatchableState>(&mut self) -> &mut Self;
+    ///
+    /// With the `bevy_reflect` feature, `State<S>` and [`NextMatchableState<S>`] are also registered
+    /// for reflection, so scene-based save systems and inspectors pick them up. Edits made to
+    /// `State<S>` through reflection are turned into real transitions - see [`forward_state_edits`](crate::forward_state_edits).
+    ///
+    /// Registering the same state twice is a no-op, unless the app has a
+    /// [`DuplicateRegistration::Panic`](crate::DuplicateRegistration::Panic) resource.
+    /// To configure how the state behaves, add a [`MatchableStatePlugin<S>`](crate::MatchableStatePlugin) instead.
+    fn add_matchable_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self;
+
+    /// Add every state of the [`StateSet`] `T` - a tuple like `(A, B, C)` - with their transitions
+    /// applied together, so coupled states never enter a half-updated combination.
+    fn add_matchable_states<T: StateSet>(&mut self) -> &mut Self;
+
+    /// Add a state that supports state matching, with its initial state computed by `initial`
+    /// rather than taken from `S::default()`.
+    ///
+    /// `initial` is evaluated once, right before the initial state is entered - so it can depend on
+    /// resources inserted by plugins or `Startup` systems, like parsed CLI options or loaded settings.
+    /// Until then, `State<S>` holds `S::default()`.
+    fn init_matchable_state_with<S: MatchableState + RegisterableState>(
+        &mut self,
+        initial: impl FnOnce(&World) -> S + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Control how the initial state of `S` is entered - skipping it, delaying it until a condition
+    /// holds, or entering a restored state instead of the default one. See [`InitialEnter<S>`].
+    fn configure_initial_enter<S: MatchableState>(&mut self, initial: InitialEnter<S>)
+        -> &mut Self;
+
+    /// Hold back transitions of `S` while the fixed timestep loop runs, so `State<S>` can't change
+    /// between the substeps of a single frame - even if `apply_state_transition` is called from `FixedUpdate`.
+    ///
+    /// Transitions applied during the loop - like when the state is flushed in `FixedUpdate` with
+    /// [`MatchableStatePlugin::in_schedule`](crate::MatchableStatePlugin::in_schedule) - are held,
+    /// and applied right after the loop finishes. Note that `State<S>` itself can still be overwritten directly.
+    fn defer_transitions_in_fixed_update<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Make transitions of `S` take time, by entering a [`TransitionPhase<S>`]
+    /// lasting for `duration` between exiting the old state and entering the new one.
+    fn add_transition_phase<S: MatchableState>(
+        &mut self,
+        duration: TransitionDuration,
+    ) -> &mut Self;
+
+    /// Despawn (recursively) all entities with the component `C` when exiting
+    /// a state matching `matcher` into one that doesn't match it.
+    ///
+    /// This complements [`StateScoped`](crate::StateScoped) for entities that already have a
+    /// marker component, like UI roots or level entities.
+    fn despawn_on_exit<S: MatchableState, C: Component, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+    ) -> &mut Self;
+
+    /// Add `plugins` to the app, running all the systems they register only while `matcher` holds.
+    ///
+    /// The plugins are built against a fresh set of schedules. Each resulting schedule is then
+    /// run as a single exclusive system in [`ScopedPluginSet`](crate::ScopedPluginSet), within the app's
+    /// schedule with the same label, gated by `matcher` - the schedule is created if the app doesn't have it yet.
+    /// Systems in `First` aren't gated, so the event queues bevy updates there are still cleared
+    /// outside of the matching states.
+    ///
+    /// Bevy can't move systems between schedules once they're added, so the plugins' systems can't be
+    /// placed in the app's schedules directly. This means ordering constraints between them and app systems
+    /// are lost - order against [`ScopedPluginSet`](crate::ScopedPluginSet) instead - as are `configure_sets`
+    /// calls the plugins make on the app's sets. Systems added in `Plugin::finish` are not scoped.
+    fn add_plugins_in<S: MatchableState, M: 'static, P>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+        plugins: impl Plugins<P>,
+    ) -> &mut Self;
+
+    /// Add `systems` to `schedule`, running only while `matcher` holds.
+    ///
+    /// Equivalent to `app.add_systems(schedule, systems.run_in(matcher))`
+    fn add_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        schedule: impl ScheduleLabel,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+
+    /// Add `systems` to `FixedUpdate`, running only while the state matches `matcher`.
+    ///
+    /// Unlike `run_in`, the matcher is checked against a [`FixedStateSnapshot<S>`] taken right before the
+    /// fixed timestep loop starts - so every substep of a frame agrees on whether the systems run,
+    /// even if `S` transitions in the middle of the loop.
+    fn add_fixed_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+
+    /// Freeze the schedule `label` entirely while the current state matches `matcher`.
+    ///
+    /// The check happens in the `StateTransition` schedule, right after transitions of `S` are applied,
+    /// so the schedule is frozen from the same frame the matching state is entered.
+    /// While frozen, the schedule is set aside during each frame - see [`FrozenSchedules`].
+    fn pause_schedule_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        label: impl ScheduleLabel + Clone,
+    ) -> &mut Self;
+
+    /// Add support for per-entity state machines using [`EntityState<S>`] components
+    fn add_entity_state<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Run the one-shot `system` whenever an [`EntityState<S>`] enters a state matching `matcher`.
+    ///
+    /// The entity and the transition are available in the [`EntityTransition<S>`] resource.
+    fn on_entity_enter<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: impl IntoSystem<(), (), Marker> + 'static,
+    ) -> &mut Self;
+
+    /// Run the one-shot `system` whenever an [`EntityState<S>`] exits a state matching `matcher`.
+    ///
+    /// The entity and the transition are available in the [`EntityTransition<S>`] resource.
+    fn on_entity_exit<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: impl IntoSystem<(), (), Marker> + 'static,
+    ) -> &mut Self;
+
+    /// Add per-entity state machines of type `C` nested within machines of type `P`.
+    ///
+    /// Whenever an entity's [`EntityState<P>`] enters a state matching `parent_matcher`,
+    /// a fresh [`EntityState<C>`] starting at `C::default()` is inserted on the same entity.
+    /// Once the parent leaves the matching states, the child machine is removed - so it
+    /// only ticks while the parent matches, and is reset every time it is re-entered.
+    ///
+    /// This registers `C` as an entity state as well, so there is no need to call
+    /// [`add_entity_state`](StateMatchingApp::add_entity_state) for it.
+    fn add_entity_sub_state<P: MatchableState, C: MatchableState, M: 'static>(
+        &mut self,
+        parent_matcher: impl StateMatcher<P, M> + Clone,
+    ) -> &mut Self;
+
+    /// Like [`add_entity_sub_state`](StateMatchingApp::add_entity_sub_state), but the child machine
+    /// remembers where it was: when the parent leaves the matching states, the last state of `C` is
+    /// stored in an [`EntitySubStateHistory<C>`](crate::EntitySubStateHistory) component, and the
+    /// child resumes from it once the parent matches again rather than starting at `C::default()`.
+    fn add_entity_sub_state_with_history<P: MatchableState, C: MatchableState, M: 'static>(
+        &mut self,
+        parent_matcher: impl StateMatcher<P, M> + Clone,
+    ) -> &mut Self;
+
+    /// Restrict transitions of `S` to the ones declared in `machine`, usually a
+    /// [`StateMachineBuilder`](crate::StateMachineBuilder).
+    ///
+    /// Any other queued transition is dropped, and a [`TransitionRejected<S>`] event is sent instead.
+    /// The graph is checked once the initial state is entered, warning about unreachable and dead-end states -
+    /// and transitions into states no system queues, if they were declared with
+    /// [`StateMachineBuilder::queued`](crate::StateMachineBuilder::queued).
+    fn add_state_machine<S: MatchableState>(
+        &mut self,
+        machine: impl Into<TransitionTable<S>>,
+    ) -> &mut Self;
+
+    /// Add a [`StateLock<S>`], letting a single holder take ownership of transitions of `S`.
+    ///
+    /// Transitions queued by anyone else while the lock is held are dropped, and a
+    /// [`TransitionRejected<S>`] event is sent instead.
+    fn add_state_lock<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Rate limit transitions from states matching `from` into states matching `to` - once one is applied,
+    /// any other is rejected with a [`TransitionRejected<S>`] event until `duration` has passed.
+    /// See [`TransitionCooldowns<S>`].
+    fn transition_cooldown<S: MatchableState, M1: 'static, M2: 'static>(
+        &mut self,
+        from: impl StateMatcher<S, M1>,
+        to: impl StateMatcher<S, M2>,
+        duration: Duration,
+    ) -> &mut Self;
+
+    /// Add a [`TransitionStepping<S>`], holding every transition of `S` until it is stepped -
+    /// to reproduce transition ordering bugs one transition at a time.
+    fn add_transition_stepping<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Count the frames and fixed update ticks spent in the current state of `S`, in a [`FramesInState<S>`] resource
+    fn add_frames_in_state<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Write the state machine registered for `S` with [`add_state_machine`](StateMatchingApp::add_state_machine)
+    /// to `path` as a Graphviz DOT graph. Failures are logged rather than returned.
+    ///
+    /// Use [`StateGraph`] directly to include named matchers in the graph.
+    fn export_state_graph<S: MatchableState>(&mut self, path: impl AsRef<Path>) -> &mut Self;
+
+    /// Register `setter` under `name` in the [`StateSetters<S>`], so it can be queued with
+    /// [`NextMatchableState::named_setter`].
+    ///
+    /// Unlike closures queued with [`NextMatchableState::setter`], named setters can be reflected,
+    /// serialized and sent over the network.
+    fn register_state_setter<S: MatchableState>(
+        &mut self,
+        name: impl Into<String>,
+        setter: impl Fn(S) -> S + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Let systems queue transitions of `S` by sending [`StateCommand<S>`] events, which are resolved
+    /// into a single transition right before it is applied - see [`collect_state_commands`].
+    fn add_state_commands<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Forward transitions queued in bevy's `NextState<S>` to [`NextMatchableState<S>`] every frame -
+    /// see [`bridge_next_state`]. This lets third-party plugins that use bevy's states queue transitions,
+    /// which then run the [`Entering`] and [`Exiting`] schedules as usual.
+    ///
+    /// Don't also add `S` with bevy's `add_state`, or both would apply the transitions -
+    /// use [`migrate_bevy_state`](StateMatchingApp::migrate_bevy_state) instead.
+    fn bridge_next_state<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Move `S` from bevy's stock states to this crate, so a project can switch over one state type at a time.
+    ///
+    /// The current `State<S>` is adopted, and transitions queued in bevy's `NextState<S>` are forwarded
+    /// to [`NextMatchableState<S>`] before bevy's own transition system sees them - so it never applies
+    /// them. Existing `in_state`, `OnEnter`, `OnExit` and `OnTransition` call sites keep working, while
+    /// new code can use matchers and the [`Entering`] and [`Exiting`] schedules.
+    ///
+    /// If `S` was also added with bevy's `add_state` - before or after this call - bevy's own transition
+    /// systems are neutralised: its `NextState<S>` is always empty by the time they run, and the `OnEnter`
+    /// schedule of the initial state is hidden from them, so the initial state is only entered once.
+    fn migrate_bevy_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self;
+
+    /// Call `hook` with the exited and entered states on every transition of `S`, for small side
+    /// effects that don't warrant a system - like analytics pings.
+    ///
+    /// Hooks run in the order they were added, once `State<S>` holds the entered state and before
+    /// the `OnTransition`, `OnEnter` and [`Entering`] schedules. Their commands are applied right away.
+    fn on_transition<S: MatchableState>(
+        &mut self,
+        hook: impl Fn(&S, &S, &mut Commands) + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Run `systems` in the [`Entering`] schedule whenever `S` transitions from a state matching
+    /// `from` into a state matching `to`.
+    ///
+    /// The systems only run for transitions of `S` itself - not when other state types transition.
+    fn on_transition_matching<S: MatchableState, M1: 'static, M2: 'static, Marker>(
+        &mut self,
+        from: impl StateMatcher<S, M1>,
+        to: impl StateMatcher<S, M2>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+
+    /// Run `systems` in the [`Entering`] schedule whenever `S` enters a state matching `matcher`
+    /// from one that doesn't match it.
+    ///
+    /// Unlike `OnEnter(S)`, this covers every matching value - and unlike `Entering` with `run_in`,
+    /// the systems don't run when other state types are entered.
+    fn on_enter_matching<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+
+    /// Run `systems` in the [`Exiting`] schedule whenever `S` exits a state matching `matcher`
+    /// into one that doesn't match it.
+    ///
+    /// Unlike `OnExit(S)`, this covers every matching value - and unlike `Exiting` with `run_in`,
+    /// the systems don't run when other state types are exited.
+    fn on_exit_matching<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+
+    /// Keep the marker resource `R` in the world exactly while `S` is in a state matching `matcher`.
+    ///
+    /// `R` is inserted (using its `Default` value) when a matching state is entered, and removed when
+    /// it is exited - so it can be used with `resource_exists::<R>()`, or by crates keyed off marker resources.
+    fn add_state_marker<S: MatchableState, R: Resource + Default, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+    ) -> &mut Self;
+
+    /// Modify the resource `R` with `apply` when a state matching `matcher` is entered, and undo it
+    /// with `revert` when it is exited - like ducking the music volume while in menus:
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_state_matching_prototype::*;
+    /// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+    /// # enum GameState {
+    /// #   #[default]
+    /// #   Menu,
+    /// #   Playing { paused: bool },
+    /// # }
+    /// #[derive(Resource)]
+    /// struct MusicVolume(f32);
+    ///
+    /// App::new()
+    ///     .insert_resource(MusicVolume(1.))
+    ///     .add_matchable_state::<GameState>()
+    ///     .modify_resource_in(
+    ///         state_matches!(GameState, Menu | Playing { paused: true }),
+    ///         |volume: &mut MusicVolume| volume.0 *= 0.25,
+    ///         |volume: &mut MusicVolume| volume.0 /= 0.25,
+    ///     );
+    /// ```
+    ///
+    /// Neither runs while `R` doesn't exist, so it should be inserted before the first matching state is entered.
+    fn modify_resource_in<S: MatchableState, R: Resource, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+        apply: impl Fn(&mut R) + Send + Sync + 'static,
+        revert: impl Fn(&mut R) + Send + Sync + 'static,
+    ) -> &mut Self;
 }
 
 impl StateMatchingApp for App {
-    fn add_matchable_state<S: MatchableState>(&mut self) -> &mut Self {
-        self.init_resource::<State<S>>()
-            .init_resource::<NextMatchableState<S>>()
+    fn add_matchable_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self {
+        MatchableStatePlugin::<S>::default().build(self);
+        self
+    }
+
+    fn add_matchable_states<T: StateSet>(&mut self) -> &mut Self {
+        T::add_states(self);
+        self
+    }
+
+    fn init_matchable_state_with<S: MatchableState + RegisterableState>(
+        &mut self,
+        initial: impl FnOnce(&World) -> S + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_matchable_state::<S>()
+            .insert_resource(InitialStateFn::<S>(Box::new(initial)))
+    }
+
+    fn configure_initial_enter<S: MatchableState>(
+        &mut self,
+        initial: InitialEnter<S>,
+    ) -> &mut Self {
+        self.insert_resource(initial)
+    }
+
+    fn defer_transitions_in_fixed_update<S: MatchableState>(&mut self) -> &mut Self {
+        crate::fixed_timestep::defer_transitions_in_fixed_update::<S>(self);
+        self
+    }
+
+    fn add_transition_phase<S: MatchableState>(
+        &mut self,
+        duration: TransitionDuration,
+    ) -> &mut Self {
+        self.insert_resource(TransitionPhase::<S>::new(duration))
+    }
+
+    fn despawn_on_exit<S: MatchableState, C: Component, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+    ) -> &mut Self {
+        self.add_systems(
+            Exiting,
+            despawn_with::<S, C>
+                .run_in(matcher)
+                .in_set(ExitSet::Cleanup),
+        )
+    }
+
+    fn add_plugins_in<S: MatchableState, M: 'static, P>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+        plugins: impl Plugins<P>,
+    ) -> &mut Self {
+        add_plugins_in(self, matcher, plugins);
+        self
+    }
+
+    fn add_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        schedule: impl ScheduleLabel,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        self.add_systems(
+            schedule,
+            StateMatchingSystems::<SystemConfigs, Marker>::run_in(systems, matcher),
+        )
+    }
+
+    fn add_fixed_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        snapshot_state_for_fixed_update::<S>(self);
+        self.add_systems(
+            FixedUpdate,
+            systems.run_if(move |snapshot: Res<FixedStateSnapshot<S>>| {
+                snapshot
+                    .get()
+                    .is_some_and(|state| matcher.match_state(state))
+            }),
+        )
+    }
+
+    fn pause_schedule_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        label: impl ScheduleLabel + Clone,
+    ) -> &mut Self {
+        if !self.world.contains_resource::<FrozenSchedules>() {
+            self.init_resource::<FrozenSchedules>().add_systems(
+                Main,
+                (
+                    set_aside_frozen_schedules.before(Main::run_main),
+                    put_back_frozen_schedules.after(Main::run_main),
+                ),
+            );
+        }
+        self.add_systems(
+            StateTransition,
+            freeze_schedule_in(matcher, label).after(apply_state_transition::<S>),
+        )
+    }
+
+    fn add_entity_state<S: MatchableState>(&mut self) -> &mut Self {
+        self.init_resource::<EntityStateHooks<S>>()
+            .add_systems(StateTransition, apply_entity_state_transitions::<S>)
+    }
+
+    fn on_entity_enter<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: impl IntoSystem<(), (), Marker> + 'static,
+    ) -> &mut Self {
+        let system = self.world.register_system(system);
+        self.world
+            .get_resource_or_insert_with(EntityStateHooks::<S>::default)
+            .add_on_enter(matcher, system);
+        self
+    }
+
+    fn on_entity_exit<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: impl IntoSystem<(), (), Marker> + 'static,
+    ) -> &mut Self {
+        let system = self.world.register_system(system);
+        self.world
+            .get_resource_or_insert_with(EntityStateHooks::<S>::default)
+            .add_on_exit(matcher, system);
+        self
+    }
+
+    fn add_entity_sub_state<P: MatchableState, C: MatchableState, M: 'static>(
+        &mut self,
+        parent_matcher: impl StateMatcher<P, M> + Clone,
+    ) -> &mut Self {
+        self.init_resource::<EntityStateHooks<C>>()
             .add_systems(
                 StateTransition,
-                (
-                    run_enter_schedule::<S>.run_if(run_once()),
-                    apply_state_transition::<S>,
-                )
-                    .chain(),
+                apply_entity_state_transitions::<C>.after(apply_entity_state_transitions::<P>),
+            )
+            .on_entity_enter(parent_matcher.clone(), insert_entity_sub_state::<P, C>)
+            .on_entity_exit(parent_matcher, remove_entity_sub_state::<P, C>)
+    }
+
+    fn add_entity_sub_state_with_history<P: MatchableState, C: MatchableState, M: 'static>(
+        &mut self,
+        parent_matcher: impl StateMatcher<P, M> + Clone,
+    ) -> &mut Self {
+        self.on_entity_exit(
+            parent_matcher.clone(),
+            record_entity_sub_state_history::<P, C>,
+        )
+        .add_entity_sub_state::<P, C, M>(parent_matcher)
+    }
+
+    fn add_state_machine<S: MatchableState>(
+        &mut self,
+        machine: impl Into<TransitionTable<S>>,
+    ) -> &mut Self {
+        self.insert_resource(machine.into())
+            .add_event::<TransitionRejected<S>>()
+            .add_systems(
+                StateTransition,
+                validate_transition_table::<S>
+                    .after(run_enter_schedule::<S>)
+                    .before(apply_state_transition::<S>),
+            )
+    }
+
+    fn add_state_lock<S: MatchableState>(&mut self) -> &mut Self {
+        self.init_resource::<StateLock<S>>()
+            .add_event::<TransitionRejected<S>>()
+    }
+
+    fn transition_cooldown<S: MatchableState, M1: 'static, M2: 'static>(
+        &mut self,
+        from: impl StateMatcher<S, M1>,
+        to: impl StateMatcher<S, M2>,
+        duration: Duration,
+    ) -> &mut Self {
+        self.init_resource::<TransitionCooldowns<S>>()
+            .add_event::<TransitionRejected<S>>()
+            .world
+            .resource_mut::<TransitionCooldowns<S>>()
+            .add(
+                move |state| from.match_state(state),
+                move |state| to.match_state(state),
+                duration,
+            );
+        self
+    }
+
+    fn add_transition_stepping<S: MatchableState>(&mut self) -> &mut Self {
+        self.init_resource::<TransitionStepping<S>>()
+    }
+
+    fn add_frames_in_state<S: MatchableState>(&mut self) -> &mut Self {
+        if self.world.contains_resource::<FramesInState<S>>() {
+            return self;
+        }
+        self.init_resource::<FramesInState<S>>()
+            .add_systems(
+                StateTransition,
+                reset_frames_in_state::<S>.after(apply_state_transition::<S>),
+            )
+            .add_systems(Last, count_frames_in_state::<S>)
+            .add_systems(FixedUpdate, count_fixed_ticks_in_state::<S>)
+    }
+
+    fn export_state_graph<S: MatchableState>(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        let path = path.as_ref();
+        let Some(table) = self.world.get_resource::<TransitionTable<S>>() else {
+            warn!(
+                "Can't export the state graph for {} - no state machine was added for it",
+                std::any::type_name::<S>()
+            );
+            return self;
+        };
+        if let Err(e) = std::fs::write(path, StateGraph::new(table).to_dot()) {
+            warn!(
+                "Failed to export the state graph to {}: {e}",
+                path.display()
             );
+        }
+        self
+    }
+
+    fn register_state_setter<S: MatchableState>(
+        &mut self,
+        name: impl Into<String>,
+        setter: impl Fn(S) -> S + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(StateSetters::<S>::default)
+            .insert(name, setter);
+        self
+    }
+
+    fn add_state_commands<S: MatchableState>(&mut self) -> &mut Self {
+        self.add_event::<StateCommand<S>>().add_systems(
+            StateTransition,
+            collect_state_commands::<S>
+                .after(run_enter_schedule::<S>)
+                .before(apply_state_transition::<S>),
+        )
+    }
+
+    fn bridge_next_state<S: MatchableState>(&mut self) -> &mut Self {
+        self.init_resource::<NextState<S>>().add_systems(
+            StateTransition,
+            bridge_next_state::<S>
+                .after(run_enter_schedule::<S>)
+                .before(apply_state_transition::<S>),
+        )
+    }
+
+    fn migrate_bevy_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self {
+        self.add_matchable_state::<S>()
+            .init_resource::<NextState<S>>()
+            .add_systems(
+                StateTransition,
+                (
+                    hide_initial_enter_from_bevy::<S>
+                        .before(bevy::ecs::schedule::run_enter_schedule::<S>)
+                        .run_if(run_once()),
+                    restore_initial_enter::<S>
+                        .after(bevy::ecs::schedule::run_enter_schedule::<S>)
+                        .before(run_enter_schedule::<S>)
+                        .run_if(run_once()),
+                    bridge_next_state::<S>
+                        .after(run_enter_schedule::<S>)
+                        .before(apply_state_transition::<S>)
+                        .before(bevy::ecs::schedule::apply_state_transition::<S>),
+                ),
+            )
+    }
 
+    fn on_transition<S: MatchableState>(
+        &mut self,
+        hook: impl Fn(&S, &S, &mut Commands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(TransitionHooks::<S>::default)
+            .add(hook);
         self
     }
+
+    fn on_transition_matching<S: MatchableState, M1: 'static, M2: 'static, Marker>(
+        &mut self,
+        from: impl StateMatcher<S, M1>,
+        to: impl StateMatcher<S, M2>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        self.add_systems(
+            Entering,
+            systems.run_if(in_transition_of(move |entered: &S, exited: Option<&S>| {
+                exited.is_some_and(|exited| from.match_state(exited)) && to.match_state(entered)
+            })),
+        )
+    }
+
+    fn on_enter_matching<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        self.add_systems(
+            Entering,
+            systems.run_if(in_transition_of(move |entered: &S, exited: Option<&S>| {
+                matcher.match_state_transition(Some(entered), exited)
+                    == MatchesStateTransition::TransitionMatches
+            })),
+        )
+    }
+
+    fn on_exit_matching<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        self.add_systems(
+            Exiting,
+            systems.run_if(in_transition_of(move |exited: &S, entered: Option<&S>| {
+                matcher.match_state_transition(Some(exited), entered)
+                    == MatchesStateTransition::TransitionMatches
+            })),
+        )
+    }
+
+    fn add_state_marker<S: MatchableState, R: Resource + Default, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+    ) -> &mut Self {
+        self.on_enter_matching(matcher.clone(), |mut commands: Commands| {
+            commands.init_resource::<R>()
+        })
+        .on_exit_matching(matcher, |mut commands: Commands| {
+            commands.remove_resource::<R>()
+        })
+    }
+
+    fn modify_resource_in<S: MatchableState, R: Resource, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M> + Clone,
+        apply: impl Fn(&mut R) + Send + Sync + 'static,
+        revert: impl Fn(&mut R) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_enter_matching(matcher.clone(), move |resource: Option<ResMut<R>>| {
+            if let Some(mut resource) = resource {
+                apply(&mut resource);
+            }
+        })
+        .on_exit_matching(matcher, move |resource: Option<ResMut<R>>| {
+            if let Some(mut resource) = resource {
+                revert(&mut resource);
+            }
+        })
+    }
 }
 
 /// A trait for adding `run_in` to systems
 pub trait StateMatchingSystems<C, Marker> {
     /// Run a state if in a matching state
     fn run_in<S: States, M: 'static, Sm: StateMatcher<S, M>>(self, matcher: Sm) -> C;
+
+    /// Run a system once each time a matching state is entered - see [`once_per_entry`]
+    fn run_once_in<S: States, M: 'static, Sm: StateMatcher<S, M>>(self, matcher: Sm) -> C;
 }
 
 impl<T: IntoSystemConfigs<Marker>, Marker> StateMatchingSystems<SystemConfigs, Marker> for T {
@@ -44,6 +740,13 @@ impl<T: IntoSystemConfigs<Marker>, Marker> StateMatchingSystems<SystemConfigs, M
         let system = Into::<StateMatcherSystem<S, M, Sm>>::into(matcher);
         self.run_if(system)
     }
+
+    fn run_once_in<S: States, M: 'static, Sm: StateMatcher<S, M>>(
+        self,
+        matcher: Sm,
+    ) -> SystemConfigs {
+        self.run_if(once_per_entry(matcher))
+    }
 }
 
 impl<T: IntoSystemSetConfigs> StateMatchingSystems<SystemSetConfigs, ()> for T {
@@ -54,4 +757,252 @@ impl<T: IntoSystemSetConfigs> StateMatchingSystems<SystemSetConfigs, ()> for T {
         let system = Into::<StateMatcherSystem<S, M, Sm>>::into(matcher);
         IntoSystemSetConfigs::run_if(self, system)
     }
+
+    fn run_once_in<S: States, M: 'static, Sm: StateMatcher<S, M>>(
+        self,
+        matcher: Sm,
+    ) -> SystemSetConfigs {
+        IntoSystemSetConfigs::run_if(self, once_per_entry(matcher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Entering, InitialEnter, MatchableState, NextMatchableState, StateMatchingApp,
+        StateMatchingSystems,
+    };
+    use bevy::prelude::{
+        in_state, App, Commands, IntoSystemConfigs, NextState, OnEnter, PostUpdate, ResMut,
+        Resource, Startup, State, States, Update,
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum OtherState {
+        #[default]
+        X,
+        Y,
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    fn count(mut runs: ResMut<Runs>) {
+        runs.0 += 1;
+    }
+
+    fn queue<S: MatchableState>(app: &mut App, state: S) {
+        app.world.resource_mut::<NextMatchableState<S>>().set(state);
+        app.update();
+    }
+
+    #[test]
+    fn transition_systems_only_run_for_matching_pairs() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_matchable_state::<OtherState>()
+            .init_resource::<Runs>()
+            .on_transition_matching(TestState::A, TestState::B, count);
+        app.update();
+
+        queue(&mut app, TestState::C);
+        queue(&mut app, TestState::A);
+        queue(&mut app, TestState::B);
+        queue(&mut app, OtherState::Y);
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+    }
+
+    #[test]
+    fn run_once_in_runs_once_per_entry() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>()
+            .add_systems(Update, count.run_once_in(TestState::B));
+        app.update();
+
+        queue(&mut app, TestState::B);
+        app.update();
+        queue(&mut app, TestState::C);
+        queue(&mut app, TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
+
+    #[test]
+    fn scheduled_systems_only_run_while_matching() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>()
+            .add_systems_in(TestState::B, PostUpdate, count);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 0);
+
+        queue(&mut app, TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+
+        queue(&mut app, TestState::C);
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
+
+    #[test]
+    fn initial_enter_can_be_skipped_delayed_or_restored() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>()
+            .configure_initial_enter(InitialEnter::<TestState>::Skip)
+            .on_enter_matching(TestState::A, count);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 0);
+
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>()
+            .configure_initial_enter(InitialEnter::<TestState>::when(|world| {
+                world.contains_resource::<InMenu>()
+            }))
+            .on_enter_matching(TestState::A, count);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 0);
+        app.init_resource::<InMenu>();
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>()
+            .configure_initial_enter(InitialEnter::Restored(TestState::C))
+            .on_enter_matching(TestState::C, count);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::C
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct InMenu;
+
+    #[test]
+    fn initial_state_can_be_computed_from_the_world() {
+        let mut app = App::new();
+        app.init_matchable_state_with(|world| match world.contains_resource::<InMenu>() {
+            true => TestState::B,
+            false => TestState::C,
+        })
+        .init_resource::<Runs>()
+        .on_enter_matching(TestState::B, count)
+        .add_systems(Startup, |mut commands: Commands| {
+            commands.init_resource::<InMenu>()
+        });
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+    }
+
+    #[test]
+    fn state_markers_follow_matching_states() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_state_marker::<_, InMenu, _>(|state: &TestState| *state != TestState::C);
+        app.update();
+        assert!(app.world.contains_resource::<InMenu>());
+
+        queue(&mut app, TestState::B);
+        assert!(app.world.contains_resource::<InMenu>());
+        queue(&mut app, TestState::C);
+        assert!(!app.world.contains_resource::<InMenu>());
+        queue(&mut app, TestState::A);
+        assert!(app.world.contains_resource::<InMenu>());
+    }
+
+    #[test]
+    fn resource_modifications_are_reverted_on_exit() {
+        let mut app = App::new();
+        app.insert_resource(Runs(10))
+            .add_matchable_state::<TestState>()
+            .modify_resource_in(
+                |state: &TestState| *state != TestState::A,
+                |runs: &mut Runs| runs.0 /= 2,
+                |runs: &mut Runs| runs.0 *= 2,
+            );
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 10);
+
+        queue(&mut app, TestState::B);
+        assert_eq!(app.world.resource::<Runs>().0, 5);
+        queue(&mut app, TestState::C);
+        assert_eq!(app.world.resource::<Runs>().0, 5);
+        queue(&mut app, TestState::A);
+        assert_eq!(app.world.resource::<Runs>().0, 10);
+    }
+
+    #[test]
+    fn migrated_bevy_states_keep_bevy_call_sites_working() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_state::<TestState>()
+            .add_systems(OnEnter(TestState::A), count)
+            .add_systems(OnEnter(TestState::B), count)
+            .add_systems(Update, count.run_if(in_state(TestState::B)))
+            .migrate_bevy_state::<TestState>()
+            .add_systems(Entering, count.run_in(TestState::B));
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+
+        app.world
+            .resource_mut::<NextState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert_eq!(app.world.resource::<Runs>().0, 4);
+    }
+
+    #[test]
+    fn migrating_before_add_state_enters_the_initial_state_once() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .migrate_bevy_state::<TestState>()
+            .add_state::<TestState>()
+            .add_systems(OnEnter(TestState::A), count)
+            .add_systems(Entering, count.run_in(TestState::A));
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
+
+    #[test]
+    fn enter_and_exit_systems_run_for_matching_states_only() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_matchable_state::<OtherState>()
+            .init_resource::<Runs>()
+            .on_enter_matching(|state: &TestState| *state != TestState::A, count)
+            .on_exit_matching(|state: &TestState| *state != TestState::A, count);
+        app.update();
+
+        queue(&mut app, TestState::B);
+        queue(&mut app, TestState::C);
+        queue(&mut app, OtherState::Y);
+        queue(&mut app, TestState::A);
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
 }
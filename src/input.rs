@@ -0,0 +1,182 @@
+use bevy::{
+    input::InputSystem,
+    prelude::{
+        App, GamepadButton, GamepadButtonType, Gamepads, Input, IntoSystemConfigs, KeyCode,
+        PreUpdate, Res, ResMut,
+    },
+};
+
+use crate::{
+    MatchableState, NextMatchableState, StateMatcher, StateMatchingApp, StateMatchingSystems,
+    StateTarget, TransitionStepping,
+};
+
+/// A trait adding input-triggered transitions to a bevy `App`
+pub trait StateMatchingInputApp {
+    /// Queue `target` whenever `key` is pressed while the current state matches `matcher`.
+    ///
+    /// The transition is queued in `PreUpdate`, right after input is processed,
+    /// so it gets applied during the same frame's `StateTransition` schedule.
+    ///
+    /// `target` can be a value of `S` or a `Fn(S) -> S`, so toggles like pausing
+    /// can be expressed directly:
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_state_matching_prototype::*;
+    /// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+    /// enum GameState {
+    ///   #[default]
+    ///   Menu,
+    ///   Playing { paused: bool },
+    /// }
+    ///
+    /// App::new().add_matchable_state::<GameState>().transition_on_key(
+    ///     KeyCode::Escape,
+    ///     state_matches!(GameState, Playing { .. }),
+    ///     |s: GameState| match s {
+    ///         GameState::Playing { paused } => GameState::Playing { paused: !paused },
+    ///         _ => s,
+    ///     },
+    /// );
+    /// ```
+    fn transition_on_key<S: MatchableState, M: 'static, TM>(
+        &mut self,
+        key: KeyCode,
+        matcher: impl StateMatcher<S, M>,
+        target: impl StateTarget<S, TM>,
+    ) -> &mut Self;
+
+    /// Queue `target` whenever `button` is pressed on any connected gamepad
+    /// while the current state matches `matcher`.
+    fn transition_on_gamepad_button<S: MatchableState, M: 'static, TM>(
+        &mut self,
+        button: GamepadButtonType,
+        matcher: impl StateMatcher<S, M>,
+        target: impl StateTarget<S, TM>,
+    ) -> &mut Self;
+
+    /// Let the transition held by the [`TransitionStepping<S>`] through whenever `key` is pressed,
+    /// adding the stepping if needed.
+    fn step_transitions_on_key<S: MatchableState>(&mut self, key: KeyCode) -> &mut Self;
+}
+
+impl StateMatchingInputApp for App {
+    fn transition_on_key<S: MatchableState, M: 'static, TM>(
+        &mut self,
+        key: KeyCode,
+        matcher: impl StateMatcher<S, M>,
+        target: impl StateTarget<S, TM>,
+    ) -> &mut Self {
+        self.add_systems(
+            PreUpdate,
+            (move |input: Option<Res<Input<KeyCode>>>,
+                   mut next_state: ResMut<NextMatchableState<S>>| {
+                if input.is_some_and(|input| input.just_pressed(key)) {
+                    target.queue(&mut next_state);
+                }
+            })
+            .after(InputSystem)
+            .run_in(matcher),
+        )
+    }
+
+    fn transition_on_gamepad_button<S: MatchableState, M: 'static, TM>(
+        &mut self,
+        button: GamepadButtonType,
+        matcher: impl StateMatcher<S, M>,
+        target: impl StateTarget<S, TM>,
+    ) -> &mut Self {
+        self.add_systems(
+            PreUpdate,
+            (move |gamepads: Option<Res<Gamepads>>,
+                   input: Option<Res<Input<GamepadButton>>>,
+                   mut next_state: ResMut<NextMatchableState<S>>| {
+                let (Some(gamepads), Some(input)) = (gamepads, input) else {
+                    return;
+                };
+                if gamepads
+                    .iter()
+                    .any(|gamepad| input.just_pressed(GamepadButton::new(gamepad, button)))
+                {
+                    target.queue(&mut next_state);
+                }
+            })
+            .after(InputSystem)
+            .run_in(matcher),
+        )
+    }
+
+    fn step_transitions_on_key<S: MatchableState>(&mut self, key: KeyCode) -> &mut Self {
+        if !self.world.contains_resource::<TransitionStepping<S>>() {
+            self.add_transition_stepping::<S>();
+        }
+        self.add_systems(
+            PreUpdate,
+            (move |input: Option<Res<Input<KeyCode>>>,
+                   mut stepping: ResMut<TransitionStepping<S>>| {
+                if input.is_some_and(|input| input.just_pressed(key)) {
+                    stepping.step();
+                }
+            })
+            .after(InputSystem),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateMatchingApp, StateMatchingInputApp};
+    use bevy::prelude::{App, Input, KeyCode, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum GameState {
+        #[default]
+        Menu,
+        Playing {
+            paused: bool,
+        },
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.update();
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.release(key);
+        input.clear();
+    }
+
+    #[test]
+    fn pressing_a_key_queues_the_transition() {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .add_matchable_state::<GameState>()
+            .transition_on_key(
+                KeyCode::Return,
+                GameState::Menu,
+                GameState::Playing { paused: false },
+            )
+            .transition_on_key(
+                KeyCode::Escape,
+                |state: &GameState| matches!(state, GameState::Playing { .. }),
+                |state: GameState| match state {
+                    GameState::Playing { paused } => GameState::Playing { paused: !paused },
+                    _ => state,
+                },
+            );
+        app.update();
+
+        press(&mut app, KeyCode::Escape);
+        assert_eq!(
+            app.world.resource::<State<GameState>>().get(),
+            &GameState::Menu
+        );
+
+        press(&mut app, KeyCode::Return);
+        press(&mut app, KeyCode::Escape);
+        assert_eq!(
+            app.world.resource::<State<GameState>>().get(),
+            &GameState::Playing { paused: true }
+        );
+    }
+}
@@ -2,10 +2,159 @@
 #![warn(clippy::doc_markdown)]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "bevy_reflect")]
+mod console;
+mod cooldowns;
+mod coverage;
+#[cfg(feature = "data_driven")]
+mod data_driven;
+#[cfg(feature = "dev_tools")]
+mod dev_tools;
+mod diagnostics;
+mod dwell_times;
+#[cfg(feature = "bevy_reflect")]
+mod dynamic_matcher;
+#[cfg(feature = "egui")]
+mod egui_overlay;
+mod entity_state;
+mod fixed_timestep;
+mod frame_budget;
+mod frames_in_state;
+mod frozen_schedules;
+mod history;
 mod injected_methods;
+#[cfg(feature = "input")]
+mod input;
+#[cfg(feature = "assets")]
+mod loading;
+mod matcher_properties;
+mod one_shot;
+mod pause;
+mod payload;
+mod plugin;
+mod recorder;
+mod regions;
+mod registration;
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "serde")]
+mod replication;
+#[cfg(feature = "replicon")]
+mod replicon;
+mod scoped_events;
+mod scoped_plugins;
+#[cfg(feature = "scripting")]
+mod scripting;
+#[cfg(feature = "scxml")]
+mod scxml;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "serde")]
+mod snapshot;
 mod state;
+mod state_commands;
+mod state_graph;
+mod state_lock;
 mod state_matching;
+mod state_scoped;
+mod state_set;
+mod state_switch;
+mod state_tags;
+mod stepping;
+mod sub_app;
+mod testing;
+mod transactions;
+mod transition_hooks;
+mod transition_phase;
+mod transition_source;
+mod transition_table;
+#[cfg(feature = "ui")]
+mod ui;
+#[cfg(feature = "web")]
+mod web;
+mod weighted;
 
+#[cfg(feature = "bevy_reflect")]
+pub use console::*;
+pub use cooldowns::*;
+pub use coverage::*;
+#[cfg(feature = "data_driven")]
+pub use data_driven::*;
+#[cfg(feature = "dev_tools")]
+pub use dev_tools::*;
+pub use diagnostics::*;
+pub use dwell_times::*;
+#[cfg(feature = "bevy_reflect")]
+pub use dynamic_matcher::*;
+#[cfg(feature = "egui")]
+pub use egui_overlay::*;
+pub use entity_state::*;
+pub use fixed_timestep::*;
+pub use frame_budget::*;
+pub use frames_in_state::*;
+pub use frozen_schedules::*;
+pub use history::*;
 pub use injected_methods::*;
+#[cfg(feature = "input")]
+pub use input::*;
+#[cfg(feature = "assets")]
+pub use loading::*;
+pub use matcher_properties::*;
+pub use one_shot::*;
+pub use pause::*;
+pub use payload::*;
+pub use plugin::*;
+pub use recorder::*;
+pub use regions::*;
+pub use registration::*;
+#[cfg(feature = "render")]
+pub use render::*;
+#[cfg(feature = "serde")]
+pub use replication::*;
+#[cfg(feature = "replicon")]
+pub use replicon::*;
+pub use scoped_events::*;
+pub use scoped_plugins::*;
+#[cfg(feature = "scripting")]
+pub use scripting::*;
+#[cfg(feature = "scxml")]
+pub use scxml::*;
+#[cfg(feature = "serde")]
+pub use serde_support::*;
+#[cfg(feature = "serde")]
+pub use snapshot::*;
 pub use state::*;
+pub use state_commands::*;
+pub use state_graph::*;
+pub use state_lock::*;
 pub use state_matching::*;
+pub use state_scoped::*;
+pub use state_set::*;
+pub use state_switch::*;
+pub use state_tags::*;
+pub use stepping::*;
+pub use sub_app::*;
+pub use testing::*;
+pub use transactions::*;
+pub use transition_hooks::*;
+pub use transition_phase::*;
+pub use transition_source::*;
+pub use transition_table::*;
+#[cfg(feature = "ui")]
+pub use ui::*;
+#[cfg(feature = "web")]
+pub use web::*;
+pub use weighted::*;
+
+/// The most commonly used types, traits, schedules and macros.
+///
+/// ```
+/// use bevy_state_matching_prototype::prelude::*;
+/// ```
+pub mod prelude {
+    pub use crate::{
+        state_matches, Entering, Exiting, MatchableState, MatchesStateTransition,
+        NextMatchableState, OnReenter, StateMatcher, StateMatchingApp, StateMatchingSystems,
+    };
+    pub use bevy::prelude::{OnEnter, OnExit, OnTransition, StateTransition};
+}
@@ -0,0 +1,281 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetServer, LoadState, RecursiveDependencyLoadState, UntypedHandle},
+    prelude::{App, IntoSystemConfigs, Res, ResMut, Resource, Update},
+};
+
+use crate::{
+    state::ActiveTransition, Entering, ExitSet, Exiting, MatchableState, NextMatchableState,
+    StateMatcher, StateMatchingSystems, StateTarget,
+};
+
+/// The loading progress of the assets tracked while in a loading state of type `S`.
+///
+/// Updated every frame while the loading matcher holds, and reset when leaving it.
+#[derive(Resource, Debug)]
+pub struct LoadingProgress<S: MatchableState> {
+    /// The number of tracked assets that are fully loaded, including their dependencies
+    pub loaded: usize,
+    /// The number of tracked assets that failed to load, or had a dependency fail to load
+    pub failed: usize,
+    /// The total number of tracked assets
+    pub total: usize,
+    _phantom: PhantomData<fn() -> S>,
+}
+
+impl<S: MatchableState> Default for LoadingProgress<S> {
+    fn default() -> Self {
+        Self {
+            loaded: 0,
+            failed: 0,
+            total: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S: MatchableState> LoadingProgress<S> {
+    /// The fraction of tracked assets that are loaded, between `0.` and `1.`
+    ///
+    /// Returns `1.` if no assets are tracked.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+
+    /// Whether all tracked assets finished loading - successfully or not
+    pub fn is_done(&self) -> bool {
+        self.loaded + self.failed >= self.total
+    }
+}
+
+#[derive(Resource)]
+struct TrackedAssets<S: MatchableState>(Vec<UntypedHandle>, PhantomData<fn() -> S>);
+
+impl<S: MatchableState> Default for TrackedAssets<S> {
+    fn default() -> Self {
+        Self(vec![], PhantomData)
+    }
+}
+
+/// A builder for configuring a loading state, returned from [`StateMatchingLoadingApp::continue_to`]
+pub struct LoadingStateBuilder<'a, S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone> {
+    app: &'a mut App,
+    matcher: Sm,
+    _phantom: PhantomData<fn() -> (S, M)>,
+}
+
+impl<'a, S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone>
+    LoadingStateBuilder<'a, S, M, Sm>
+{
+    /// Track the handles returned by `collector` whenever a matching state is entered.
+    ///
+    /// Can be called multiple times to track assets from multiple sources.
+    pub fn with_assets(
+        self,
+        collector: impl Fn(&AssetServer) -> Vec<UntypedHandle> + Send + Sync + 'static,
+    ) -> Self {
+        self.app.add_systems(
+            Entering,
+            (move |transition: Option<Res<ActiveTransition<S>>>,
+                   asset_server: Res<AssetServer>,
+                   mut tracked: ResMut<TrackedAssets<S>>| {
+                if transition.is_some() {
+                    tracked.0.extend(collector(&asset_server));
+                }
+            })
+            .run_in(self.matcher.clone()),
+        );
+        self
+    }
+}
+
+/// A trait adding asset-gated loading states to a bevy `App`
+pub trait StateMatchingLoadingApp {
+    /// While the current state matches `matcher`, wait for all tracked assets to load,
+    /// and then queue `target`.
+    ///
+    /// Assets that fail to load count as finished, so loading never gets stuck on them -
+    /// check [`LoadingProgress::failed`] to handle them. Handles the `AssetServer` doesn't track,
+    /// like the ones returned by `Assets::add`, count as loaded.
+    ///
+    /// Assets are tracked using [`LoadingStateBuilder::with_assets`], and the progress is
+    /// exposed in the [`LoadingProgress<S>`] resource.
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_state_matching_prototype::*;
+    /// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+    /// enum AppState {
+    ///   #[default]
+    ///   Loading,
+    ///   Menu,
+    /// }
+    ///
+    /// App::new()
+    ///     .add_matchable_state::<AppState>()
+    ///     .continue_to(AppState::Loading, AppState::Menu)
+    ///     .with_assets(|asset_server| vec![asset_server.load::<Image>("icon.png").untyped()]);
+    /// ```
+    fn continue_to<S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone, TM>(
+        &mut self,
+        matcher: Sm,
+        target: impl StateTarget<S, TM>,
+    ) -> LoadingStateBuilder<'_, S, M, Sm>;
+}
+
+impl StateMatchingLoadingApp for App {
+    fn continue_to<S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone, TM>(
+        &mut self,
+        matcher: Sm,
+        target: impl StateTarget<S, TM>,
+    ) -> LoadingStateBuilder<'_, S, M, Sm> {
+        self.init_resource::<TrackedAssets<S>>()
+            .init_resource::<LoadingProgress<S>>()
+            .add_systems(
+                Update,
+                (move |asset_server: Res<AssetServer>,
+                       tracked: Res<TrackedAssets<S>>,
+                       mut progress: ResMut<LoadingProgress<S>>,
+                       mut next_state: ResMut<NextMatchableState<S>>| {
+                    progress.total = tracked.0.len();
+                    progress.loaded = tracked
+                        .0
+                        .iter()
+                        .filter(|handle| {
+                            asset_server.get_load_states(handle.id()).map_or(
+                                true,
+                                |(_, _, recursive)| {
+                                    recursive == RecursiveDependencyLoadState::Loaded
+                                },
+                            )
+                        })
+                        .count();
+                    progress.failed = tracked
+                        .0
+                        .iter()
+                        .filter(|handle| {
+                            asset_server.get_load_states(handle.id()).is_some_and(
+                                |(load, _, recursive)| {
+                                    load == LoadState::Failed
+                                        || recursive == RecursiveDependencyLoadState::Failed
+                                },
+                            )
+                        })
+                        .count();
+                    if progress.is_done() {
+                        target.queue(&mut next_state);
+                    }
+                })
+                .run_in(matcher.clone()),
+            )
+            .add_systems(
+                Exiting,
+                (|transition: Option<Res<ActiveTransition<S>>>,
+                  mut tracked: ResMut<TrackedAssets<S>>,
+                  mut progress: ResMut<LoadingProgress<S>>| {
+                    if transition.is_some() {
+                        tracked.0.clear();
+                        *progress = LoadingProgress::default();
+                    }
+                })
+                .run_in(matcher.clone())
+                .in_set(ExitSet::Cleanup),
+            );
+
+        LoadingStateBuilder {
+            app: self,
+            matcher,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{LoadingProgress, StateMatchingApp, StateMatchingLoadingApp};
+    use bevy::{
+        asset::{Asset, AssetApp, AssetPlugin, Assets},
+        core::TaskPoolPlugin,
+        prelude::{App, State, States},
+        reflect::TypePath,
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum AppState {
+        #[default]
+        Loading,
+        Menu,
+    }
+
+    #[derive(Asset, TypePath)]
+    struct Blob;
+
+    #[test]
+    fn loading_continues_once_assets_are_loaded() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .init_asset::<Blob>();
+        let blob = app.world.resource_mut::<Assets<Blob>>().add(Blob).untyped();
+        app.add_matchable_state::<AppState>()
+            .continue_to(AppState::Loading, AppState::Menu)
+            .with_assets(move |_| vec![blob.clone()]);
+
+        app.update();
+        let progress = app.world.resource::<LoadingProgress<AppState>>();
+        assert_eq!((progress.loaded, progress.total), (1, 1));
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::Loading
+        );
+
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::Menu
+        );
+        assert_eq!(app.world.resource::<LoadingProgress<AppState>>().total, 0);
+    }
+
+    #[test]
+    fn loading_progress_fractions() {
+        let mut progress = LoadingProgress::<AppState>::default();
+        assert_eq!(progress.fraction(), 1.);
+        assert!(progress.is_done());
+
+        progress.total = 4;
+        progress.loaded = 1;
+        progress.failed = 1;
+        assert_eq!(progress.fraction(), 0.25);
+        assert!(!progress.is_done());
+
+        progress.loaded = 3;
+        assert_eq!(progress.fraction(), 0.75);
+        assert!(progress.is_done());
+    }
+
+    #[test]
+    fn failed_assets_dont_block_loading() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()))
+            .init_asset::<Blob>()
+            .add_matchable_state::<AppState>()
+            .continue_to(AppState::Loading, AppState::Menu)
+            .with_assets(|asset_server| vec![asset_server.load::<Blob>("missing.blob").untyped()]);
+
+        for _ in 0..200 {
+            app.update();
+            if app.world.resource::<State<AppState>>().get() == &AppState::Menu {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("loading never finished");
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{MatchableState, MatchesStateTransition, StateMatcher, TransitionTable};
+
+/// A violation of one of the invariants checked by [`MatcherProperties`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatcherViolation<S: MatchableState> {
+    /// Matching the state on its own disagrees with matching a transition into it from nothing
+    Inconsistent(S),
+    /// Matching the same transition twice gave different results
+    NonDeterministic(S),
+    /// `m.and(m.not())` matched the state, or the transition into the second state -
+    /// evaluating the matcher and its negation separately
+    Contradiction(S, Option<S>),
+    /// `m.or(m.not())` didn't match the state, or the transition into the second state -
+    /// evaluating the matcher and its negation separately
+    NotExhaustive(S, Option<S>),
+    /// The transition reported `MainMatches`, while the main state on its own doesn't match
+    MainMismatch(S, S),
+}
+
+/// Checks that a matcher upholds the invariants the rest of the crate relies on, across
+/// every state - and every pair of states - in a list.
+///
+/// Since `States` doesn't expose its variants, the states are either listed directly,
+/// taken from a [`TransitionTable<S>`], or produced by a generator for property-based testing.
+/// Note that matchers wrapped in [`invert_transition`](StateMatcher::invert_transition) look at
+/// the other side of each transition on purpose, so they report [`MatcherViolation::Inconsistent`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame { paused: bool },
+/// }
+///
+/// MatcherProperties::generated(64, |i| match i % 3 {
+///     0 => AppState::Menu,
+///     n => AppState::InGame { paused: n == 1 },
+/// })
+/// .assert_holds(&|state: &AppState| matches!(state, AppState::InGame { .. }));
+/// ```
+pub struct MatcherProperties<S: MatchableState> {
+    states: Vec<S>,
+}
+
+impl<S: MatchableState> MatcherProperties<S> {
+    /// Check the properties over the given states
+    pub fn new(states: impl IntoIterator<Item = S>) -> Self {
+        let mut checked = vec![];
+        for state in states {
+            if !checked.contains(&state) {
+                checked.push(state);
+            }
+        }
+        Self { states: checked }
+    }
+
+    /// Check the properties over the states declared in `table`
+    pub fn from_table(table: &TransitionTable<S>) -> Self {
+        Self::new(table.states().iter().cloned())
+    }
+
+    /// Check the properties over `count` states produced by `generator`, which is passed the index of each state.
+    ///
+    /// Duplicate states are only checked once.
+    pub fn generated(count: usize, generator: impl FnMut(usize) -> S) -> Self {
+        Self::new((0..count).map(generator))
+    }
+
+    /// Every violation found for `matcher`
+    pub fn check<M: 'static>(&self, matcher: &impl StateMatcher<S, M>) -> Vec<MatcherViolation<S>> {
+        let mut violations = vec![];
+        for main in self.states.iter() {
+            let matches = matcher.match_state(main);
+            let negated = !matcher.match_state(main);
+            if matches && negated {
+                violations.push(MatcherViolation::Contradiction(main.clone(), None));
+                continue;
+            } else if !matches && !negated {
+                violations.push(MatcherViolation::NotExhaustive(main.clone(), None));
+                continue;
+            }
+            let from_nothing = matcher.match_state_transition(Some(main), None);
+            if matches != (from_nothing != MatchesStateTransition::NoMatch) {
+                violations.push(MatcherViolation::Inconsistent(main.clone()));
+            }
+            for secondary in self.states.iter() {
+                // `MainMatches` is its own negation, so it never counts as matching or not matching
+                let transition = matcher.match_state_transition(Some(main), Some(secondary));
+                let negated = matcher
+                    .match_state_transition(Some(main), Some(secondary))
+                    .invert();
+                if transition.and(negated) == MatchesStateTransition::TransitionMatches {
+                    violations.push(MatcherViolation::Contradiction(
+                        main.clone(),
+                        Some(secondary.clone()),
+                    ));
+                } else if transition.or(negated) == MatchesStateTransition::NoMatch {
+                    violations.push(MatcherViolation::NotExhaustive(
+                        main.clone(),
+                        Some(secondary.clone()),
+                    ));
+                } else if transition != negated.invert() {
+                    violations.push(MatcherViolation::NonDeterministic(main.clone()));
+                } else if transition == MatchesStateTransition::MainMatches && !matches {
+                    violations.push(MatcherViolation::MainMismatch(
+                        main.clone(),
+                        secondary.clone(),
+                    ));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Panic if `matcher` violates any of the properties
+    pub fn assert_holds<M: 'static>(&self, matcher: &impl StateMatcher<S, M>) {
+        let violations = self.check(matcher);
+        assert!(
+            violations.is_empty(),
+            "Matcher for {} violates its invariants: {violations:?}",
+            std::any::type_name::<S>()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{MatcherProperties, MatcherViolation, MatchesStateTransition, StateMatcher};
+    use bevy::prelude::States;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn properties() -> MatcherProperties<TestState> {
+        MatcherProperties::new([TestState::A, TestState::B, TestState::A])
+    }
+
+    #[test]
+    fn well_behaved_matchers_hold() {
+        properties().assert_holds(&TestState::A);
+        properties().assert_holds(&TestState::A.combine(TestState::B));
+        properties().assert_holds(&|_: &TestState, _: &TestState| {
+            MatchesStateTransition::TransitionMatches
+        });
+    }
+
+    #[test]
+    fn main_matches_without_a_matching_state_is_a_violation() {
+        let violations =
+            properties().check(&|_: &TestState, _: &TestState| MatchesStateTransition::MainMatches);
+        assert!(violations.contains(&MatcherViolation::MainMismatch(TestState::A, TestState::B)));
+    }
+
+    #[test]
+    fn matchers_disagreeing_with_their_negation_are_violations() {
+        let flip = AtomicBool::new(false);
+        let violations =
+            properties().check(&move |_: &TestState| !flip.fetch_xor(true, Ordering::Relaxed));
+        assert!(violations.contains(&MatcherViolation::Contradiction(TestState::A, None)));
+
+        let flip = AtomicBool::new(false);
+        let violations =
+            properties().check(&move |_: &TestState| flip.fetch_xor(true, Ordering::Relaxed));
+        assert!(violations.contains(&MatcherViolation::NotExhaustive(TestState::A, None)));
+
+        let flip = AtomicBool::new(false);
+        let violations = properties().check(&move |_: &TestState, _: &TestState| {
+            if flip.fetch_xor(true, Ordering::Relaxed) {
+                MatchesStateTransition::NoMatch
+            } else {
+                MatchesStateTransition::TransitionMatches
+            }
+        });
+        assert!(violations.contains(&MatcherViolation::Contradiction(
+            TestState::A,
+            Some(TestState::A)
+        )));
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::{
+    ecs::system::SystemId,
+    prelude::{Commands, State, World},
+};
+
+use crate::{
+    state::ActiveTransition, state_matching::evaluate_matcher, DisabledStateMachine,
+    MatchableState, StateMatcher, TransitionPhase,
+};
+
+/// Whether `matcher` matches the state of `S` in `world` - the way [`StateMatcherSystem`](crate::StateMatcherSystem) evaluates it
+fn world_matches<S: MatchableState, M>(world: &World, matcher: &impl StateMatcher<S, M>) -> bool {
+    evaluate_matcher(
+        matcher,
+        world.get_resource::<State<S>>(),
+        world.get_resource::<ActiveTransition<S>>(),
+        world.get_resource::<TransitionPhase<S>>(),
+        world.get_resource::<DisabledStateMachine<S>>(),
+        None,
+    )
+}
+
+/// A trait for running one-shot systems on a `World` only in matching states
+pub trait StateMatchingWorldSystems {
+    /// Run the one-shot system `system` if the current state of `S` matches `matcher`,
+    /// returning whether it ran
+    fn run_system_in<S: MatchableState, M>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    ) -> bool;
+}
+
+impl StateMatchingWorldSystems for World {
+    fn run_system_in<S: MatchableState, M>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    ) -> bool {
+        world_matches(self, &matcher) && self.run_system(system).is_ok()
+    }
+}
+
+/// A trait for queueing one-shot systems with `Commands` that only run in matching states
+pub trait StateMatchingCommands {
+    /// Run the one-shot system `system` when the commands are applied, if the state of `S`
+    /// matches `matcher` at that point - see [`StateMatchingWorldSystems::run_system_in`]
+    fn run_system_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    );
+}
+
+impl<'w, 's> StateMatchingCommands for Commands<'w, 's> {
+    fn run_system_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        system: SystemId,
+    ) {
+        self.add(move |world: &mut World| {
+            world.run_system_in(matcher, system);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DisabledMatching, NextMatchableState, StateMatchingApp, StateMatchingSwitchWorld,
+        StateMatchingWorldSystems,
+    };
+    use bevy::prelude::{App, ResMut, Resource, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    #[test]
+    fn one_shot_systems_only_run_in_matching_states() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>();
+        app.update();
+        let system = app
+            .world
+            .register_system(|mut runs: ResMut<Runs>| runs.0 += 1);
+
+        assert!(!app.world.run_system_in(TestState::B, system));
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert!(app.world.run_system_in(TestState::B, system));
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+    }
+
+    #[test]
+    fn one_shot_systems_respect_disabled_state_machines() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Runs>();
+        app.update();
+        let system = app
+            .world
+            .register_system(|mut runs: ResMut<Runs>| runs.0 += 1);
+
+        app.world
+            .disable_state_machine::<TestState>(DisabledMatching::NoMatch);
+        assert!(!app.world.run_system_in(TestState::A, system));
+
+        app.world
+            .disable_state_machine::<TestState>(DisabledMatching::Frozen);
+        assert!(app.world.run_system_in(TestState::A, system));
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+    }
+}
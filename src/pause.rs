@@ -0,0 +1,179 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::schedule::ScheduleLabel,
+    prelude::{
+        App, Event, EventWriter, IntoSystemConfigs, Local, Plugin, Res, ResMut, State,
+        StateTransition,
+    },
+    time::{Time, Virtual},
+};
+
+use crate::{apply_state_transition, MatchableState, StateMatcher, StateMatchingApp};
+
+/// Sent by the [`PausePlugin<S>`] when the game is paused or resumed
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum PauseEvent<S: MatchableState> {
+    /// A paused state was entered from one that isn't
+    Paused(S),
+    /// A state that isn't paused was entered from a paused one
+    Resumed(S),
+}
+
+type GateSchedule<Sm> = Box<dyn Fn(&mut App, Sm) + Send + Sync>;
+
+/// Pauses the game while the state of `S` matches a matcher.
+///
+/// While paused, virtual time is paused - so `Time` stops advancing in `Update` and `FixedUpdate` - and
+/// the schedules added with [`gate_schedule`](PausePlugin::gate_schedule) are frozen, as with
+/// [`StateMatchingApp::pause_schedule_in`]. A [`PauseEvent<S>`] is sent whenever the game is paused or resumed.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum GameState {
+///   #[default]
+///   Menu,
+///   Playing { paused: bool },
+/// }
+///
+/// App::new()
+///     .add_matchable_state::<GameState>()
+///     .add_plugins(
+///         PausePlugin::new(state_matches!(GameState, Playing { paused: true })).gate_schedule(Update),
+///     );
+/// ```
+pub struct PausePlugin<S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone> {
+    matcher: Sm,
+    virtual_time: bool,
+    schedules: Vec<GateSchedule<Sm>>,
+    marker: PhantomData<fn() -> (S, M)>,
+}
+
+impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone> PausePlugin<S, M, Sm> {
+    /// Pause the game while the state matches `matcher`
+    pub fn new(matcher: Sm) -> Self {
+        Self {
+            matcher,
+            virtual_time: true,
+            schedules: vec![],
+            marker: PhantomData,
+        }
+    }
+
+    /// Freeze the schedule `label` while paused
+    pub fn gate_schedule(mut self, label: impl ScheduleLabel + Clone) -> Self {
+        self.schedules.push(Box::new(move |app, matcher| {
+            app.pause_schedule_in(matcher, label.clone());
+        }));
+        self
+    }
+
+    /// Keep virtual time running while paused
+    pub fn without_virtual_time(mut self) -> Self {
+        self.virtual_time = false;
+        self
+    }
+}
+
+impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M> + Clone> Plugin
+    for PausePlugin<S, M, Sm>
+{
+    fn build(&self, app: &mut App) {
+        let matcher = self.matcher.clone();
+        let virtual_time = self.virtual_time;
+        app.add_event::<PauseEvent<S>>().add_systems(
+            StateTransition,
+            (move |state: Option<Res<State<S>>>,
+                   time: Option<ResMut<Time<Virtual>>>,
+                   mut events: EventWriter<PauseEvent<S>>,
+                   mut paused: Local<bool>| {
+                let Some(state) = state else {
+                    return;
+                };
+                let should_pause = matcher.match_state(state.get());
+                if should_pause == *paused {
+                    return;
+                }
+                *paused = should_pause;
+                let state = state.get().clone();
+                if should_pause {
+                    events.send(PauseEvent::Paused(state));
+                } else {
+                    events.send(PauseEvent::Resumed(state));
+                }
+                let Some(mut time) = time.filter(|_| virtual_time) else {
+                    return;
+                };
+                if should_pause {
+                    time.pause();
+                } else {
+                    time.unpause();
+                }
+            })
+            .after(apply_state_transition::<S>),
+        );
+        for gate in self.schedules.iter() {
+            gate(app, self.matcher.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, PauseEvent, PausePlugin, StateMatchingApp};
+    use bevy::{
+        prelude::{App, Events, ResMut, Resource, States, Update},
+        time::{Time, Virtual},
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        Playing,
+        Paused,
+    }
+
+    #[derive(Resource, Default)]
+    struct Ticks(u32);
+
+    fn set(app: &mut App, state: TestState) {
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(state);
+        app.update();
+    }
+
+    #[test]
+    fn paused_states_freeze_time_and_gated_schedules() {
+        let mut app = App::new();
+        app.init_resource::<Ticks>()
+            .init_resource::<Time<Virtual>>()
+            .add_matchable_state::<TestState>()
+            .add_plugins(PausePlugin::new(TestState::Paused).gate_schedule(Update))
+            .add_systems(Update, |mut ticks: ResMut<Ticks>| ticks.0 += 1);
+        app.update();
+
+        set(&mut app, TestState::Paused);
+        assert_eq!(app.world.resource::<Ticks>().0, 1);
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+
+        set(&mut app, TestState::Playing);
+        assert_eq!(app.world.resource::<Ticks>().0, 2);
+        assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+
+        let events = app
+            .world
+            .resource_mut::<Events<PauseEvent<TestState>>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                PauseEvent::Paused(TestState::Paused),
+                PauseEvent::Resumed(TestState::Playing)
+            ]
+        );
+    }
+}
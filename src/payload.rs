@@ -0,0 +1,145 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::{Resource, World};
+
+use crate::{MatchableState, NextMatchableState};
+
+/// The payload attached to the transition currently being applied, queued with
+/// [`NextMatchableState::set_with_payload`].
+///
+/// It exists while the exit, transition and enter schedules of that transition run - including
+/// during a [`TransitionPhase<S>`](crate::TransitionPhase) - and is removed right after. Since it's
+/// keyed by the payload type alone, payloads of different state types shouldn't share a type.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum AppState {
+/// #   #[default]
+/// #   Menu,
+/// #   InGame,
+/// # }
+/// struct LevelParams {
+///     seed: u64,
+/// }
+///
+/// fn start_game(mut next_state: ResMut<NextMatchableState<AppState>>) {
+///     next_state.set_with_payload(AppState::InGame, LevelParams { seed: 42 });
+/// }
+///
+/// fn generate_level(params: Option<Res<TransitionPayload<LevelParams>>>) {
+///     let seed = params.map_or(0, |params| params.get().seed);
+///     // ...
+/// }
+/// ```
+#[derive(Resource, Debug)]
+pub struct TransitionPayload<T: Send + Sync + 'static>(T);
+
+impl<T: Send + Sync + 'static> TransitionPayload<T> {
+    /// The payload
+    pub fn get(&self) -> &T {
+        &self.0
+    }
+}
+
+trait AnyPayload: Send + Sync + 'static {
+    /// Insert the payload as a [`TransitionPayload`], returning a function that removes it again
+    fn insert(self: Box<Self>, world: &mut World) -> fn(&mut World);
+}
+
+struct Payload<T>(T);
+
+impl<T: Send + Sync + 'static> AnyPayload for Payload<T> {
+    fn insert(self: Box<Self>, world: &mut World) -> fn(&mut World) {
+        world.insert_resource(TransitionPayload(self.0));
+        |world| {
+            world.remove_resource::<TransitionPayload<T>>();
+        }
+    }
+}
+
+/// A payload waiting in [`NextMatchableState::ValueWithPayload`] for its transition to be applied
+#[derive(Default)]
+pub struct QueuedPayload(Option<Box<dyn AnyPayload>>);
+
+impl QueuedPayload {
+    pub(crate) fn new<T: Send + Sync + 'static>(payload: T) -> Self {
+        Self(Some(Box::new(Payload(payload))))
+    }
+}
+
+/// Removes the [`TransitionPayload`] of the transition of `S` in progress
+#[derive(Resource)]
+struct PayloadCleanup<S: MatchableState>(fn(&mut World), PhantomData<fn() -> S>);
+
+/// Move the payload queued in [`NextMatchableState<S>`], if any, into a [`TransitionPayload`]
+pub(crate) fn insert_queued_payload<S: MatchableState>(world: &mut World) {
+    let Some(payload) =
+        world
+            .get_resource_mut::<NextMatchableState<S>>()
+            .and_then(|mut next_state| match &mut *next_state {
+                NextMatchableState::ValueWithPayload(_, payload) => payload.0.take(),
+                _ => None,
+            })
+    else {
+        return;
+    };
+    let cleanup = payload.insert(world);
+    world.insert_resource(PayloadCleanup::<S>(cleanup, PhantomData));
+}
+
+/// Remove the [`TransitionPayload`] inserted by [`insert_queued_payload`]
+pub(crate) fn clear_payload<S: MatchableState>(world: &mut World) {
+    if let Some(PayloadCleanup(cleanup, _)) = world.remove_resource::<PayloadCleanup<S>>() {
+        cleanup(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp, TransitionPayload};
+    use bevy::prelude::{App, OnEnter, OnExit, Res, ResMut, Resource, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    struct Seed(u64);
+
+    #[derive(Resource, Default)]
+    struct Seen(Vec<Option<u64>>);
+
+    fn record(payload: Option<Res<TransitionPayload<Seed>>>, mut seen: ResMut<Seen>) {
+        seen.0.push(payload.map(|payload| payload.get().0));
+    }
+
+    #[test]
+    fn payloads_are_visible_during_their_transition() {
+        let mut app = App::new();
+        app.init_resource::<Seen>()
+            .add_matchable_state::<TestState>()
+            .add_systems(OnExit(TestState::A), record)
+            .add_systems(OnEnter(TestState::B), record)
+            .add_systems(OnEnter(TestState::A), record);
+        app.update();
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set_with_payload(TestState::B, Seed(42));
+        app.update();
+        assert!(!app.world.contains_resource::<TransitionPayload<Seed>>());
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::A);
+        app.update();
+        assert_eq!(
+            app.world.resource::<Seen>().0,
+            vec![None, Some(42), Some(42), None]
+        );
+    }
+}
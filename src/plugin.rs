@@ -0,0 +1,423 @@
+use std::{collections::VecDeque, marker::PhantomData};
+
+use bevy::{
+    ecs::schedule::ScheduleLabel,
+    prelude::{
+        App, Event, IntoSystemConfigs, IntoSystemSetConfigs, Plugin, Resource, State,
+        StateTransition, World,
+    },
+};
+
+use crate::{
+    despawn_out_of_scope,
+    registration::register_state_type,
+    state::{apply_state_transition, initial_enter_ready, run_enter_schedule},
+    EnterSet, Entering, ExitSet, Exiting, MatchableState, NextMatchableState, PendingTransition,
+    QueuedTransitionSource, RegisterableState, RegisteredStates, StateMachineError,
+    TransitionBatchStats, TransitionBudget,
+};
+
+/// What [`apply_state_transition`] does when the queued state equals the current one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdentityTransitions {
+    /// Run the [`OnReenter`](crate::OnReenter) schedule
+    #[default]
+    Reenter,
+    /// Drop the transition without running any schedules
+    Ignore,
+}
+
+/// When the `OnTransition` schedule runs, relative to the exit and enter schedules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransitionOrder {
+    /// `OnExit`, `Exiting`, `OnTransition`, `OnEnter`, `Entering`
+    #[default]
+    AfterExit,
+    /// `OnTransition`, `OnExit`, `Exiting`, `OnEnter`, `Entering`
+    BeforeExit,
+    /// `OnExit`, `Exiting`, `OnEnter`, `Entering`, `OnTransition`
+    AfterEnter,
+}
+
+/// Sent whenever a state of `S` is entered, if enabled with [`MatchableStatePlugin::send_events`]
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct StateTransitionEvent<S: MatchableState> {
+    /// The state that was exited, or `None` when entering the initial state
+    pub exited: Option<S>,
+    /// The state that was entered
+    pub entered: S,
+}
+
+/// Transitions of `S` waiting to be applied, one per run of [`apply_state_transition`].
+///
+/// Added by [`MatchableStatePlugin::queued`]. A queued transition is only moved into
+/// [`NextMatchableState<S>`] once nothing else is waiting there - so transitions queued in
+/// the same frame are applied one after the other instead of overwriting each other.
+#[derive(Resource)]
+pub struct TransitionQueue<S: MatchableState>(VecDeque<S>);
+
+impl<S: MatchableState> Default for TransitionQueue<S> {
+    fn default() -> Self {
+        Self(VecDeque::new())
+    }
+}
+
+impl<S: MatchableState> TransitionQueue<S> {
+    /// Add a transition into `state` to the back of the queue
+    pub fn push(&mut self, state: S) {
+        self.0.push_back(state);
+    }
+
+    /// The number of transitions waiting
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no transitions are waiting
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Drop all the waiting transitions
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// The options of a [`MatchableStatePlugin<S>`] that [`apply_state_transition`] reads at runtime
+#[derive(Resource, Debug)]
+pub(crate) struct StateOptions<S: MatchableState> {
+    pub(crate) identity_transitions: IdentityTransitions,
+    pub(crate) transition_order: TransitionOrder,
+    pub(crate) log_transitions: bool,
+    pub(crate) frame_budget: Option<TransitionBudget>,
+    marker: PhantomData<fn() -> S>,
+}
+
+impl<S: MatchableState> Clone for StateOptions<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: MatchableState> Copy for StateOptions<S> {}
+
+impl<S: MatchableState> Default for StateOptions<S> {
+    fn default() -> Self {
+        Self {
+            identity_transitions: IdentityTransitions::default(),
+            transition_order: TransitionOrder::default(),
+            log_transitions: false,
+            frame_budget: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Move the next transition from the [`TransitionQueue<S>`] into [`NextMatchableState<S>`],
+/// if one is waiting and nothing else is queued
+pub(crate) fn pop_transition_queue<S: MatchableState>(world: &mut World) {
+    if !matches!(
+        world.get_resource::<NextMatchableState<S>>(),
+        Some(NextMatchableState::Keep)
+    ) {
+        return;
+    }
+    let Some(state) = world
+        .get_resource_mut::<TransitionQueue<S>>()
+        .and_then(|mut queue| queue.0.pop_front())
+    else {
+        return;
+    };
+    world.resource_mut::<NextMatchableState<S>>().set(state);
+}
+
+/// Registers a matchable state of type `S`, with all of its options in one place.
+///
+/// [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state) adds
+/// this plugin with the default options.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+/// }
+///
+/// App::new().add_plugins(
+///     MatchableStatePlugin::<AppState>::default()
+///         .identity_transitions(IdentityTransitions::Ignore)
+///         .queued()
+///         .send_events()
+///         .log_transitions(),
+/// );
+/// ```
+pub struct MatchableStatePlugin<S: MatchableState, L: ScheduleLabel + Clone = StateTransition> {
+    schedule: L,
+    queued: bool,
+    send_events: bool,
+    scoped_entities: bool,
+    options: StateOptions<S>,
+}
+
+impl<S: MatchableState> Default for MatchableStatePlugin<S> {
+    fn default() -> Self {
+        Self {
+            schedule: StateTransition,
+            queued: false,
+            send_events: false,
+            scoped_entities: true,
+            options: StateOptions::default(),
+        }
+    }
+}
+
+impl<S: MatchableState, L: ScheduleLabel + Clone> MatchableStatePlugin<S, L> {
+    /// Apply transitions in `schedule` rather than `StateTransition`.
+    ///
+    /// Helpers that order their own systems around the transition systems - like
+    /// [`add_state_commands`](crate::StateMatchingApp::add_state_commands) - assume `StateTransition`.
+    pub fn in_schedule<L2: ScheduleLabel + Clone>(
+        self,
+        schedule: L2,
+    ) -> MatchableStatePlugin<S, L2> {
+        MatchableStatePlugin {
+            schedule,
+            queued: self.queued,
+            send_events: self.send_events,
+            scoped_entities: self.scoped_entities,
+            options: self.options,
+        }
+    }
+
+    /// Choose what happens when the queued state equals the current one - see [`IdentityTransitions`]
+    pub fn identity_transitions(mut self, policy: IdentityTransitions) -> Self {
+        self.options.identity_transitions = policy;
+        self
+    }
+
+    /// Choose when the `OnTransition` schedule runs - see [`TransitionOrder`]
+    pub fn transition_order(mut self, order: TransitionOrder) -> Self {
+        self.options.transition_order = order;
+        self
+    }
+
+    /// Queue transitions in a [`TransitionQueue<S>`], rather than only keeping the latest one
+    pub fn queued(mut self) -> Self {
+        self.queued = true;
+        self
+    }
+
+    /// Queue transitions like [`queued`](MatchableStatePlugin::queued), but apply as many of them
+    /// per run as `budget` allows rather than one - recording a [`TransitionBatchStats<S>`]
+    pub fn frame_budget(mut self, budget: TransitionBudget) -> Self {
+        self.queued = true;
+        self.options.frame_budget = Some(budget);
+        self
+    }
+
+    /// Send a [`StateTransitionEvent<S>`] whenever a state is entered
+    pub fn send_events(mut self) -> Self {
+        self.send_events = true;
+        self
+    }
+
+    /// Don't clean up [`StateScoped`](crate::StateScoped) entities of `S` when exiting states
+    pub fn without_scoped_entities(mut self) -> Self {
+        self.scoped_entities = false;
+        self
+    }
+
+    /// Log every transition of `S` at the info level
+    pub fn log_transitions(mut self) -> Self {
+        self.options.log_transitions = true;
+        self
+    }
+}
+
+impl<S: MatchableState + RegisterableState, L: ScheduleLabel + Clone> Plugin
+    for MatchableStatePlugin<S, L>
+{
+    fn build(&self, app: &mut App) {
+        if !register_state_type::<S>(&mut app.world) {
+            return;
+        }
+        if app.world.resource::<RegisteredStates>().len() == 1 {
+            app.configure_sets(
+                Entering,
+                (EnterSet::Cleanup, EnterSet::Spawn, EnterSet::PostSpawn).chain(),
+            )
+            .configure_sets(
+                Exiting,
+                (ExitSet::Teardown, ExitSet::Cleanup, ExitSet::PostCleanup).chain(),
+            );
+        }
+        app.init_resource::<State<S>>()
+            .init_resource::<NextMatchableState<S>>()
+            .init_resource::<PendingTransition<S>>()
+            .init_resource::<QueuedTransitionSource<S>>()
+            .insert_resource(self.options)
+            .add_event::<StateMachineError>()
+            .add_systems(
+                self.schedule.clone(),
+                (
+                    run_enter_schedule::<S>.run_if(initial_enter_ready::<S>),
+                    apply_state_transition::<S>,
+                )
+                    .chain(),
+            );
+
+        if self.queued {
+            app.init_resource::<TransitionQueue<S>>();
+        }
+
+        if self.options.frame_budget.is_some() {
+            app.init_resource::<TransitionBatchStats<S>>();
+        }
+
+        if self.send_events {
+            app.add_event::<StateTransitionEvent<S>>();
+        }
+
+        if self.scoped_entities {
+            app.add_systems(Exiting, despawn_out_of_scope::<S>.in_set(ExitSet::Cleanup));
+
+            #[cfg(feature = "render")]
+            app.add_systems(
+                Exiting,
+                crate::hide_out_of_scope::<S>.in_set(ExitSet::Cleanup),
+            );
+        }
+
+        #[cfg(feature = "ui")]
+        app.add_systems(
+            bevy::prelude::PreUpdate,
+            crate::ui::set_state_on_click::<S>.after(bevy::ui::UiSystem::Focus),
+        );
+
+        #[cfg(feature = "bevy_reflect")]
+        app.register_type::<State<S>>()
+            .register_type::<NextMatchableState<S>>()
+            .add_systems(
+                self.schedule.clone(),
+                crate::forward_state_edits::<S>
+                    .after(run_enter_schedule::<S>)
+                    .before(apply_state_transition::<S>),
+            );
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        IdentityTransitions, MatchableStatePlugin, NextMatchableState, OnReenter,
+        StateTransitionEvent, TransitionOrder, TransitionQueue,
+    };
+    use bevy::prelude::{App, Events, OnExit, OnTransition, ResMut, Resource, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[derive(Resource, Default)]
+    struct Reentered(u32);
+
+    #[test]
+    fn identity_transitions_can_be_ignored() {
+        let mut app = App::new();
+        app.init_resource::<Reentered>()
+            .add_plugins(
+                MatchableStatePlugin::<TestState>::default()
+                    .identity_transitions(IdentityTransitions::Ignore),
+            )
+            .add_systems(
+                OnReenter(TestState::A),
+                |mut reentered: ResMut<Reentered>| reentered.0 += 1,
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::A);
+        app.update();
+        assert_eq!(app.world.resource::<Reentered>().0, 0);
+    }
+
+    #[derive(Resource, Default)]
+    struct Order(Vec<&'static str>);
+
+    #[test]
+    fn on_transition_can_run_before_exiting() {
+        let mut app = App::new();
+        app.init_resource::<Order>()
+            .add_plugins(
+                MatchableStatePlugin::<TestState>::default()
+                    .transition_order(TransitionOrder::BeforeExit),
+            )
+            .add_systems(OnExit(TestState::A), |mut order: ResMut<Order>| {
+                order.0.push("exit")
+            })
+            .add_systems(
+                OnTransition {
+                    from: TestState::A,
+                    to: TestState::B,
+                },
+                |mut order: ResMut<Order>| order.0.push("transition"),
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Order>().0, vec!["transition", "exit"]);
+    }
+
+    #[test]
+    fn queued_transitions_are_applied_in_order_and_sent_as_events() {
+        let mut app = App::new();
+        app.add_plugins(
+            MatchableStatePlugin::<TestState>::default()
+                .queued()
+                .send_events(),
+        );
+        app.update();
+
+        let mut queue = app.world.resource_mut::<TransitionQueue<TestState>>();
+        queue.push(TestState::B);
+        queue.push(TestState::C);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::C
+        );
+
+        let entered = app
+            .world
+            .resource_mut::<Events<StateTransitionEvent<TestState>>>()
+            .drain()
+            .map(|event| (event.exited, event.entered))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entered,
+            vec![
+                (Some(TestState::A), TestState::B),
+                (Some(TestState::B), TestState::C)
+            ]
+        );
+    }
+}
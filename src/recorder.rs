@@ -0,0 +1,241 @@
+use bevy::{
+    core::FrameCount,
+    prelude::{
+        App, DetectChanges, IntoSystemConfigs, Res, ResMut, Resource, State, StateTransition,
+    },
+    time::Time,
+};
+
+use crate::{apply_state_transition, MatchableState, NextMatchableState};
+
+/// A single transition captured by a [`TransitionRecorder<S>`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedTransition<S: MatchableState> {
+    /// The frame the state was entered on, relative to the start of the recording
+    pub frame: u32,
+    /// The time the state was entered at, in seconds since the app started
+    pub seconds: f64,
+    /// The state that was entered
+    pub state: S,
+}
+
+/// A sequence of recorded transitions, which can be replayed with
+/// [`StateMatchingRecorderApp::replay_transitions`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransitionRecording<S: MatchableState> {
+    /// The recorded transitions, in order
+    pub transitions: Vec<RecordedTransition<S>>,
+}
+
+impl<S: MatchableState> Default for TransitionRecording<S> {
+    fn default() -> Self {
+        Self {
+            transitions: vec![],
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S: MatchableState + serde::Serialize> TransitionRecording<S> {
+    /// Export the recording as RON
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    /// Export the recording as JSON
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Records every transition of `S`, including the initial state.
+///
+/// Added by [`StateMatchingRecorderApp::record_transitions`].
+#[derive(Resource, Debug)]
+pub struct TransitionRecorder<S: MatchableState> {
+    /// Whether transitions are currently being recorded
+    pub enabled: bool,
+    start_frame: Option<u32>,
+    recording: TransitionRecording<S>,
+}
+
+impl<S: MatchableState> Default for TransitionRecorder<S> {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            start_frame: None,
+            recording: TransitionRecording::default(),
+        }
+    }
+}
+
+impl<S: MatchableState> TransitionRecorder<S> {
+    /// The transitions recorded so far
+    pub fn recording(&self) -> &TransitionRecording<S> {
+        &self.recording
+    }
+
+    /// Take the transitions recorded so far, starting a new recording
+    pub fn take(&mut self) -> TransitionRecording<S> {
+        self.start_frame = None;
+        std::mem::take(&mut self.recording)
+    }
+}
+
+/// Replays a [`TransitionRecording<S>`], queueing each transition on its recorded frame
+/// relative to when the playback started.
+#[derive(Resource, Debug)]
+pub struct TransitionPlayback<S: MatchableState> {
+    recording: TransitionRecording<S>,
+    start_frame: Option<u32>,
+    cursor: usize,
+}
+
+impl<S: MatchableState> TransitionPlayback<S> {
+    /// Whether every recorded transition has been queued
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.recording.transitions.len()
+    }
+}
+
+fn record_transition<S: MatchableState>(
+    state: Res<State<S>>,
+    frame: Res<FrameCount>,
+    time: Option<Res<Time>>,
+    mut recorder: ResMut<TransitionRecorder<S>>,
+) {
+    if !recorder.enabled || (!state.is_changed() && recorder.start_frame.is_some()) {
+        return;
+    }
+    let start_frame = *recorder.start_frame.get_or_insert(frame.0);
+    recorder.recording.transitions.push(RecordedTransition {
+        frame: frame.0.wrapping_sub(start_frame),
+        seconds: time
+            .map(|time| time.elapsed_seconds_f64())
+            .unwrap_or_default(),
+        state: state.get().clone(),
+    });
+}
+
+fn play_transitions<S: MatchableState>(
+    frame: Res<FrameCount>,
+    mut playback: ResMut<TransitionPlayback<S>>,
+    mut next_state: ResMut<NextMatchableState<S>>,
+) {
+    let start_frame = *playback.start_frame.get_or_insert(frame.0);
+    let frame = frame.0.wrapping_sub(start_frame);
+    while let Some(transition) = playback
+        .recording
+        .transitions
+        .get(playback.cursor)
+        .filter(|transition| transition.frame <= frame)
+    {
+        next_state.set(transition.state.clone());
+        playback.cursor += 1;
+    }
+}
+
+/// A trait adding transition recording and playback to a bevy `App`
+pub trait StateMatchingRecorderApp {
+    /// Record every transition of `S` into a [`TransitionRecorder<S>`]
+    fn record_transitions<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Replay `recording`, queueing each transition on the same frame (relative to the start)
+    /// it was originally recorded on - useful for reproducing bugs.
+    fn replay_transitions<S: MatchableState>(
+        &mut self,
+        recording: TransitionRecording<S>,
+    ) -> &mut Self;
+}
+
+impl StateMatchingRecorderApp for App {
+    fn record_transitions<S: MatchableState>(&mut self) -> &mut Self {
+        self.init_resource::<TransitionRecorder<S>>()
+            .init_resource::<FrameCount>()
+            .add_systems(
+                StateTransition,
+                record_transition::<S>.after(apply_state_transition::<S>),
+            )
+    }
+
+    fn replay_transitions<S: MatchableState>(
+        &mut self,
+        recording: TransitionRecording<S>,
+    ) -> &mut Self {
+        self.insert_resource(TransitionPlayback {
+            recording,
+            start_frame: None,
+            cursor: 0,
+        })
+        .init_resource::<FrameCount>()
+        .add_systems(
+            StateTransition,
+            play_transitions::<S>.before(apply_state_transition::<S>),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        NextMatchableState, StateMatchingApp, StateMatchingRecorderApp, TransitionPlayback,
+        TransitionRecorder,
+    };
+    use bevy::{
+        core::FrameCount,
+        prelude::{App, State, States},
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn update(app: &mut App) {
+        app.update();
+        app.world.resource_mut::<FrameCount>().0 += 1;
+    }
+
+    #[test]
+    fn recorded_transitions_replay_on_the_same_frames() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .record_transitions::<TestState>();
+        update(&mut app);
+        update(&mut app);
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        update(&mut app);
+        let recording = app
+            .world
+            .resource_mut::<TransitionRecorder<TestState>>()
+            .take();
+        assert_eq!(recording.transitions.len(), 2);
+        assert_eq!(recording.transitions[1].frame, 2);
+
+        let mut replay = App::new();
+        replay
+            .add_matchable_state::<TestState>()
+            .replay_transitions(recording);
+        update(&mut replay);
+        update(&mut replay);
+        assert_eq!(
+            replay.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+        update(&mut replay);
+        assert_eq!(
+            replay.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert!(replay
+            .world
+            .resource::<TransitionPlayback<TestState>>()
+            .is_finished());
+    }
+}
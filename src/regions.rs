@@ -0,0 +1,222 @@
+use std::marker::PhantomData;
+
+use crate::{
+    state_matching::{sealed, TransitionStateMatcher},
+    MatchableState, MatchesStateTransition, NextMatchableState, StateMatcher,
+};
+
+/// An independent region of the state `S` - like the audio or connection state within an
+/// `InGame` state - that can be matched and transitioned without disturbing the other regions.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum Connection {
+///   #[default]
+///   Online,
+///   Offline,
+/// }
+///
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame { connection: Connection, paused: bool },
+/// }
+///
+/// impl StateRegion<AppState> for Connection {
+///     fn get(state: &AppState) -> Option<Self> {
+///         match state {
+///             AppState::InGame { connection, .. } => Some(*connection),
+///             _ => None,
+///         }
+///     }
+///
+///     fn set(state: &mut AppState, value: Self) {
+///         if let AppState::InGame { connection, .. } = state {
+///             *connection = value;
+///         }
+///     }
+/// }
+///
+/// let state = AppState::InGame { connection: Connection::Offline, paused: true };
+/// assert!(state.matches(in_region(Connection::Offline)));
+/// ```
+pub trait StateRegion<S: MatchableState>: MatchableState {
+    /// The value of this region within `state`, or `None` if `state` doesn't contain it
+    fn get(state: &S) -> Option<Self>;
+
+    /// Replace the value of this region within `state`, leaving the other regions as they are.
+    /// Does nothing if `state` doesn't contain the region.
+    fn set(state: &mut S, value: Self);
+}
+
+/// A matcher applying a matcher for the region `R` to states of `S`, created with [`in_region`]
+pub struct RegionMatcher<S: MatchableState, R: StateRegion<S>, M: 'static, Sm: StateMatcher<R, M>>(
+    Sm,
+    PhantomData<fn() -> (S, R, M)>,
+);
+
+impl<S: MatchableState, R: StateRegion<S>, M: 'static, Sm: StateMatcher<R, M>>
+    TransitionStateMatcher<S, sealed::IsRegionMatcher<(R, M)>> for RegionMatcher<S, R, M, Sm>
+{
+    fn match_transition(&self, main: Option<&S>, secondary: Option<&S>) -> MatchesStateTransition {
+        let main = main.and_then(R::get);
+        if main.is_none() {
+            return MatchesStateTransition::NoMatch;
+        }
+        let secondary = secondary.and_then(R::get);
+        self.0
+            .match_state_transition(main.as_ref(), secondary.as_ref())
+    }
+}
+
+/// Match states of `S` by the value of their region `R`.
+///
+/// When matching transitions, only changes to the region itself count - so a transition that
+/// only changes other regions is a `MainMatches` rather than a `TransitionMatches`.
+pub fn in_region<S: MatchableState, R: StateRegion<S>, M: 'static, Sm: StateMatcher<R, M>>(
+    matcher: Sm,
+) -> RegionMatcher<S, R, M, Sm> {
+    RegionMatcher(matcher, PhantomData)
+}
+
+impl<S: MatchableState> NextMatchableState<S> {
+    /// Queue a transition of the region `R` into `value`, leaving the other regions as they are.
+    ///
+    /// This composes with a value or setter queued earlier in the same frame, so several systems can
+    /// transition different regions at once. A named setter queued earlier is replaced.
+    pub fn set_region<R: StateRegion<S>>(&mut self, value: R) {
+        match std::mem::take(self) {
+            Self::Value(mut state) => {
+                R::set(&mut state, value);
+                *self = Self::Value(state);
+            }
+            Self::ValueWithPayload(mut state, payload) => {
+                R::set(&mut state, value);
+                *self = Self::ValueWithPayload(state, payload);
+            }
+            Self::Setter(setter) => self.setter(move |state| {
+                let mut state = setter(state);
+                R::set(&mut state, value.clone());
+                state
+            }),
+            Self::Keep | Self::NamedSetter(_) => self.setter(move |mut state| {
+                R::set(&mut state, value.clone());
+                state
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        in_region, state_matching::sealed::InternalStateMatcher, MatchesStateTransition,
+        NextMatchableState, StateMatchingApp, StateRegion,
+    };
+    use bevy::prelude::{App, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Copy)]
+    enum Audio {
+        #[default]
+        Music,
+        Muted,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Copy)]
+    enum Connection {
+        #[default]
+        Online,
+        Offline,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum GameState {
+        #[default]
+        Menu,
+        InGame {
+            audio: Audio,
+            connection: Connection,
+        },
+    }
+
+    impl StateRegion<GameState> for Audio {
+        fn get(state: &GameState) -> Option<Self> {
+            match state {
+                GameState::InGame { audio, .. } => Some(*audio),
+                _ => None,
+            }
+        }
+
+        fn set(state: &mut GameState, value: Self) {
+            if let GameState::InGame { audio, .. } = state {
+                *audio = value;
+            }
+        }
+    }
+
+    impl StateRegion<GameState> for Connection {
+        fn get(state: &GameState) -> Option<Self> {
+            match state {
+                GameState::InGame { connection, .. } => Some(*connection),
+                _ => None,
+            }
+        }
+
+        fn set(state: &mut GameState, value: Self) {
+            if let GameState::InGame { connection, .. } = state {
+                *connection = value;
+            }
+        }
+    }
+
+    const IN_GAME: GameState = GameState::InGame {
+        audio: Audio::Music,
+        connection: Connection::Online,
+    };
+
+    #[test]
+    fn region_matchers_only_see_their_region() {
+        let offline = GameState::InGame {
+            audio: Audio::Music,
+            connection: Connection::Offline,
+        };
+        let muted_offline = GameState::InGame {
+            audio: Audio::Muted,
+            connection: Connection::Offline,
+        };
+        let matcher = in_region(Connection::Offline);
+        assert!(matcher.match_state(&offline));
+        assert!(!matcher.match_state(&GameState::Menu));
+        assert_eq!(
+            matcher.match_state_transition(Some(&offline), Some(&IN_GAME)),
+            MatchesStateTransition::TransitionMatches
+        );
+        assert_eq!(
+            matcher.match_state_transition(Some(&muted_offline), Some(&offline)),
+            MatchesStateTransition::MainMatches
+        );
+    }
+
+    #[test]
+    fn region_transitions_compose() {
+        let mut app = App::new();
+        app.add_matchable_state::<GameState>();
+        app.world.insert_resource(State::new(IN_GAME));
+        app.update();
+
+        let mut next_state = app.world.resource_mut::<NextMatchableState<GameState>>();
+        next_state.set_region(Audio::Muted);
+        next_state.set_region(Connection::Offline);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<GameState>>().get(),
+            &GameState::InGame {
+                audio: Audio::Muted,
+                connection: Connection::Offline
+            }
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use std::{any::TypeId, fmt::Display};
+
+use bevy::{
+    log::error,
+    prelude::{Event, Events, Resource, State, World},
+    utils::HashMap,
+};
+
+use crate::{MatchableState, NextMatchableState};
+
+/// A mistake in how a state was registered, sent by [`apply_state_transition`](crate::apply_state_transition)
+/// while [`StrictStateChecks`] are enabled
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum StateMachineError {
+    /// `State<S>` is missing for the state type with this name
+    MissingState(&'static str),
+    /// [`NextMatchableState<S>`] is missing for the state type with this name
+    MissingNextState(&'static str),
+}
+
+impl Display for StateMachineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingState(state) => write!(f, "State<{state}> doesn't exist"),
+            Self::MissingNextState(state) => write!(f, "NextMatchableState<{state}> doesn't exist"),
+        }
+    }
+}
+
+impl std::error::Error for StateMachineError {}
+
+/// Whether [`apply_state_transition`](crate::apply_state_transition) reports missing state resources,
+/// logging an error and sending a [`StateMachineError`] event, rather than silently doing nothing.
+///
+/// Without this resource, the checks are enabled in debug builds only.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrictStateChecks(pub bool);
+
+impl Default for StrictStateChecks {
+    fn default() -> Self {
+        Self(cfg!(debug_assertions))
+    }
+}
+
+pub(crate) fn report_state_error(world: &mut World, error: StateMachineError) {
+    let strict = world
+        .get_resource::<StrictStateChecks>()
+        .copied()
+        .unwrap_or_default();
+    if !strict.0 {
+        return;
+    }
+    error!(
+        "Can't apply state transitions: {error} - was the state added with `add_matchable_state`?"
+    );
+    if let Some(mut events) = world.get_resource_mut::<Events<StateMachineError>>() {
+        events.send(error);
+    }
+}
+
+/// What [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state)
+/// does when called again for a state type that is already registered
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateRegistration {
+    /// Keep the existing registration, making the call a no-op
+    #[default]
+    Ignore,
+    /// Panic, naming the state type
+    Panic,
+}
+
+/// A registered state type, as listed by [`StateMatchingWorld::states_summary`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSummary {
+    /// The full type name of the state
+    pub type_name: &'static str,
+    /// The `Debug` representation of the current value, or `None` if `State<S>` is missing
+    pub current: Option<String>,
+    /// Whether a transition is queued in [`NextMatchableState<S>`]
+    pub queued: bool,
+}
+
+fn summarize<S: MatchableState>(world: &World) -> StateSummary {
+    StateSummary {
+        type_name: std::any::type_name::<S>(),
+        current: world
+            .get_resource::<State<S>>()
+            .map(|state| format!("{:?}", state.get())),
+        queued: world
+            .get_resource::<NextMatchableState<S>>()
+            .is_some_and(|next| !matches!(next, NextMatchableState::Keep)),
+    }
+}
+
+/// The state types registered with [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state)
+#[derive(Resource, Default)]
+pub struct RegisteredStates(HashMap<TypeId, fn(&World) -> StateSummary>);
+
+impl RegisteredStates {
+    /// Whether `S` is registered
+    pub fn contains<S: MatchableState>(&self) -> bool {
+        self.0.contains_key(&TypeId::of::<S>())
+    }
+
+    /// The number of registered state types
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no state types are registered
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Record that `S` is being registered, returning false if it already was.
+/// Panics instead if [`DuplicateRegistration::Panic`] is configured.
+pub(crate) fn register_state_type<S: MatchableState>(world: &mut World) -> bool {
+    let first = world
+        .get_resource_or_insert_with(RegisteredStates::default)
+        .0
+        .insert(TypeId::of::<S>(), summarize::<S>)
+        .is_none();
+    if !first
+        && world.get_resource::<DuplicateRegistration>() == Some(&DuplicateRegistration::Panic)
+    {
+        panic!(
+            "{} was registered with `add_matchable_state` more than once",
+            std::any::type_name::<S>()
+        );
+    }
+    first
+}
+
+/// A trait for checking state registration on a `World`
+pub trait StateMatchingWorld {
+    /// Panic with a descriptive message unless `State<S>` and [`NextMatchableState<S>`] both exist
+    fn assert_state_registered<S: MatchableState>(&self);
+
+    /// List every state type registered with
+    /// [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state),
+    /// sorted by type name - for debug overlays, logging and external tools.
+    fn states_summary(&self) -> Vec<StateSummary>;
+}
+
+impl StateMatchingWorld for World {
+    fn assert_state_registered<S: MatchableState>(&self) {
+        let name = std::any::type_name::<S>();
+        let missing = [
+            (!self.contains_resource::<State<S>>()).then(|| StateMachineError::MissingState(name)),
+            (!self.contains_resource::<NextMatchableState<S>>())
+                .then(|| StateMachineError::MissingNextState(name)),
+        ];
+        let missing = missing
+            .iter()
+            .flatten()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        assert!(
+            missing.is_empty(),
+            "{name} isn't registered: {} - add it with `add_matchable_state`",
+            missing.join(", ")
+        );
+    }
+
+    fn states_summary(&self) -> Vec<StateSummary> {
+        let Some(registered) = self.get_resource::<RegisteredStates>() else {
+            return vec![];
+        };
+        let mut summary = registered
+            .0
+            .values()
+            .map(|summarize| summarize(self))
+            .collect::<Vec<_>>();
+        summary.sort_by_key(|state| state.type_name);
+        summary
+    }
+}
+
+/// Type-erased entries for state types, looked up by their short or full type path -
+/// shared by the dev console and the scripting API
+#[cfg(feature = "bevy_reflect")]
+pub(crate) struct NamedStates<E>(Vec<NamedState<E>>);
+
+#[cfg(feature = "bevy_reflect")]
+pub(crate) struct NamedState<E> {
+    pub(crate) name: &'static str,
+    type_name: &'static str,
+    pub(crate) entry: E,
+}
+
+#[cfg(feature = "bevy_reflect")]
+impl<E> Default for NamedStates<E> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+#[cfg(feature = "bevy_reflect")]
+impl<E> NamedStates<E> {
+    /// Add `entry` for `S`, unless `S` already has one
+    pub(crate) fn register<S: bevy::reflect::TypePath>(&mut self, entry: impl FnOnce() -> E) {
+        let type_name = S::type_path();
+        if !self.0.iter().any(|state| state.type_name == type_name) {
+            self.0.push(NamedState {
+                name: S::short_type_path(),
+                type_name,
+                entry: entry(),
+            });
+        }
+    }
+
+    /// The state called `name`, by its short or full type path
+    pub(crate) fn find(&self, name: &str) -> Option<&NamedState<E>> {
+        self.0
+            .iter()
+            .find(|state| state.name == name || state.type_name == name)
+    }
+
+    /// Every registered state, in registration order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &NamedState<E>> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply_state_transition, DuplicateRegistration, NextMatchableState, RegisteredStates,
+        StateMachineError, StateMatchingApp, StateMatchingWorld, StateSummary, StrictStateChecks,
+    };
+    use bevy::prelude::{App, Events, OnEnter, ResMut, Resource, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+    }
+
+    #[test]
+    fn missing_states_are_reported_in_strict_mode() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .insert_resource(StrictStateChecks(true));
+        app.update();
+        app.world.assert_state_registered::<TestState>();
+
+        app.world.remove_resource::<State<TestState>>();
+        apply_state_transition::<TestState>(&mut app.world);
+        let errors = app
+            .world
+            .resource::<Events<StateMachineError>>()
+            .iter_current_update_events()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![StateMachineError::MissingState(std::any::type_name::<
+                TestState,
+            >())]
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct Entered(u32);
+
+    #[test]
+    fn registering_a_state_twice_is_a_no_op() {
+        let mut app = App::new();
+        app.init_resource::<Entered>()
+            .add_matchable_state::<TestState>()
+            .add_matchable_state::<TestState>()
+            .add_systems(OnEnter(TestState::A), |mut entered: ResMut<Entered>| {
+                entered.0 += 1
+            });
+        app.update();
+        assert!(app
+            .world
+            .resource::<RegisteredStates>()
+            .contains::<TestState>());
+        assert_eq!(app.world.resource::<Entered>().0, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn registering_a_state_twice_can_panic() {
+        App::new()
+            .insert_resource(DuplicateRegistration::Panic)
+            .add_matchable_state::<TestState>()
+            .add_matchable_state::<TestState>();
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum OtherState {
+        #[default]
+        X,
+        Y,
+    }
+
+    #[test]
+    fn registered_states_are_summarized() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_matchable_state::<OtherState>();
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<OtherState>>()
+            .set(OtherState::Y);
+
+        let mut expected = vec![
+            StateSummary {
+                type_name: std::any::type_name::<TestState>(),
+                current: Some("A".to_string()),
+                queued: false,
+            },
+            StateSummary {
+                type_name: std::any::type_name::<OtherState>(),
+                current: Some("X".to_string()),
+                queued: true,
+            },
+        ];
+        expected.sort_by_key(|state| state.type_name);
+        assert_eq!(app.world.states_summary(), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn asserting_an_unregistered_state_panics() {
+        App::new().world.assert_state_registered::<TestState>();
+    }
+}
@@ -0,0 +1,32 @@
+use bevy::{
+    prelude::{App, Mut, World},
+    render::{ExtractSchedule, MainWorld, RenderApp},
+};
+
+use crate::{sync_state, MatchableState};
+
+/// Mirror `State<S>` from the main world into the render world during extraction
+fn extract_state<S: MatchableState>(world: &mut World) {
+    world.resource_scope(|world, main_world: Mut<MainWorld>| {
+        sync_state::<S>(&main_world, world);
+    });
+}
+
+/// A trait for gating render world systems on the game's states
+pub trait StateMatchingRenderApp {
+    /// Extract `State<S>` into the render world every frame, as a read-only mirror.
+    ///
+    /// This lets custom render passes and extraction systems use `run_in` with the same matchers
+    /// as the main world. The render world doesn't run any exit or enter schedules.
+    /// Does nothing if the `RenderApp` doesn't exist, so it has to be called after adding the `RenderPlugin`.
+    fn extract_matchable_state<S: MatchableState>(&mut self) -> &mut Self;
+}
+
+impl StateMatchingRenderApp for App {
+    fn extract_matchable_state<S: MatchableState>(&mut self) -> &mut Self {
+        if let Ok(render_app) = self.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(ExtractSchedule, extract_state::<S>);
+        }
+        self
+    }
+}
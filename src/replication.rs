@@ -0,0 +1,173 @@
+use bevy::prelude::{
+    App, DetectChanges, IntoSystemConfigs, Res, ResMut, Resource, State, StateTransition,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{apply_state_transition, MatchableState, NextMatchableState};
+
+/// A state change of a replicated state type, ready to be sent over the network
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateReplicationMessage {
+    /// Identifies the state type - its type name
+    pub key: String,
+    /// The new value, serialized as RON
+    pub value: String,
+}
+
+impl StateReplicationMessage {
+    /// Encode the value of a state of type `S`
+    pub fn encode<S: MatchableState + Serialize>(state: &S) -> Result<Self, ron::Error> {
+        Ok(Self {
+            key: std::any::type_name::<S>().to_string(),
+            value: ron::to_string(state)?,
+        })
+    }
+
+    /// Decode the value, if the message is for states of type `S`
+    pub fn decode<S: MatchableState + DeserializeOwned>(&self) -> Option<S> {
+        if self.key != std::any::type_name::<S>() {
+            return None;
+        }
+        ron::from_str(&self.value).ok()
+    }
+}
+
+/// Messages produced by server-authoritative states, to be drained and sent by the transport
+#[derive(Resource, Default, Debug)]
+pub struct OutgoingStateMessages(Vec<StateReplicationMessage>);
+
+impl OutgoingStateMessages {
+    /// Take all the messages produced since the last drain
+    pub fn drain(&mut self) -> impl Iterator<Item = StateReplicationMessage> + '_ {
+        self.0.drain(..)
+    }
+}
+
+/// Messages received by the transport, to be applied to replicated states
+#[derive(Resource, Default, Debug)]
+pub struct IncomingStateMessages(Vec<StateReplicationMessage>);
+
+impl IncomingStateMessages {
+    /// Queue a received message to be applied
+    pub fn push(&mut self, message: StateReplicationMessage) {
+        self.0.push(message);
+    }
+}
+
+fn send_replicated_state<S: MatchableState + Serialize>(
+    state: Option<Res<State<S>>>,
+    mut outgoing: ResMut<OutgoingStateMessages>,
+) {
+    let Some(state) = state.filter(|state| state.is_changed()) else {
+        return;
+    };
+    if let Ok(message) = StateReplicationMessage::encode(state.get()) {
+        outgoing.0.push(message);
+    }
+}
+
+fn receive_replicated_state<S: MatchableState + DeserializeOwned>(
+    mut incoming: ResMut<IncomingStateMessages>,
+    next_state: Option<ResMut<NextMatchableState<S>>>,
+) {
+    let key = std::any::type_name::<S>();
+    let mut latest = None;
+    incoming.0.retain(|message| {
+        if message.key != key {
+            return true;
+        }
+        latest = message.decode::<S>().or(latest.take());
+        false
+    });
+    if let (Some(state), Some(mut next_state)) = (latest, next_state) {
+        next_state.set(state);
+    }
+}
+
+/// A trait adding state replication to a bevy `App`.
+///
+/// The server calls [`replicate_state`](StateMatchingReplicationApp::replicate_state) and sends the
+/// contents of [`OutgoingStateMessages`], while clients call
+/// [`receive_replicated_state`](StateMatchingReplicationApp::receive_replicated_state) and push
+/// whatever they receive into [`IncomingStateMessages`]. The transport itself is up to the game.
+pub trait StateMatchingReplicationApp {
+    /// Produce a [`StateReplicationMessage`] whenever `S` changes, including its initial value
+    fn replicate_state<S: MatchableState + Serialize>(&mut self) -> &mut Self;
+
+    /// Apply incoming messages for `S` by queueing them in [`NextMatchableState<S>`], so the
+    /// exit and enter schedules run on the client as well.
+    ///
+    /// If multiple messages arrive before they are applied, only the latest is used.
+    fn receive_replicated_state<S: MatchableState + DeserializeOwned>(&mut self) -> &mut Self;
+}
+
+impl StateMatchingReplicationApp for App {
+    fn replicate_state<S: MatchableState + Serialize>(&mut self) -> &mut Self {
+        self.init_resource::<OutgoingStateMessages>().add_systems(
+            StateTransition,
+            send_replicated_state::<S>.after(apply_state_transition::<S>),
+        )
+    }
+
+    fn receive_replicated_state<S: MatchableState + DeserializeOwned>(&mut self) -> &mut Self {
+        self.init_resource::<IncomingStateMessages>().add_systems(
+            StateTransition,
+            receive_replicated_state::<S>.before(apply_state_transition::<S>),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        IncomingStateMessages, NextMatchableState, OutgoingStateMessages, StateMatchingApp,
+        StateMatchingReplicationApp,
+    };
+    use bevy::prelude::{App, State, States};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Serialize, Deserialize)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn server_transitions_are_applied_on_the_client() {
+        let mut server = App::new();
+        server
+            .add_matchable_state::<TestState>()
+            .replicate_state::<TestState>();
+        let mut client = App::new();
+        client
+            .add_matchable_state::<TestState>()
+            .receive_replicated_state::<TestState>();
+
+        server.update();
+        server
+            .world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        server.update();
+
+        let messages = server
+            .world
+            .resource_mut::<OutgoingStateMessages>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2);
+        for message in messages {
+            client
+                .world
+                .resource_mut::<IncomingStateMessages>()
+                .push(message);
+        }
+        client.update();
+
+        assert_eq!(
+            client.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::{
+    ecs::event::ManualEventReader,
+    prelude::{
+        App, DetectChanges, Event, EventReader, EventWriter, Events, IntoSystemConfigs, Local, Res,
+        ResMut, State, StateTransition,
+    },
+};
+use bevy_replicon::{
+    prelude::{EventType, SendMode, ServerEventAppExt, ToClients},
+    renet::ServerEvent,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{apply_state_transition, MatchableState, NextMatchableState};
+
+/// A server event carrying the new value of a replicated state of type `S`
+#[derive(Event, Serialize, Deserialize, Debug, Clone)]
+pub struct StateReplicated<S: MatchableState>(pub S);
+
+/// Broadcast the state whenever it changes, and send it to newly connected clients otherwise
+fn broadcast_state<S: MatchableState + Serialize + DeserializeOwned>(
+    state: Option<Res<State<S>>>,
+    server_events: Option<Res<Events<ServerEvent>>>,
+    mut connections: Local<ManualEventReader<ServerEvent>>,
+    mut events: EventWriter<ToClients<StateReplicated<S>>>,
+) {
+    let connected = server_events
+        .map(|server_events| {
+            connections
+                .read(&server_events)
+                .filter_map(|event| match event {
+                    ServerEvent::ClientConnected { client_id } => Some(*client_id),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let Some(state) = state else {
+        return;
+    };
+    if state.is_changed() {
+        events.send(ToClients {
+            mode: SendMode::Broadcast,
+            event: StateReplicated(state.get().clone()),
+        });
+        return;
+    }
+    for client_id in connected {
+        events.send(ToClients {
+            mode: SendMode::Direct(client_id),
+            event: StateReplicated(state.get().clone()),
+        });
+    }
+}
+
+fn queue_replicated_state<S: MatchableState + Serialize + DeserializeOwned>(
+    mut events: EventReader<StateReplicated<S>>,
+    current: Option<Res<State<S>>>,
+    next_state: Option<ResMut<NextMatchableState<S>>>,
+) {
+    let Some(StateReplicated(state)) = events.read().last() else {
+        return;
+    };
+    if current.is_some_and(|current| current.get() == state) {
+        return;
+    }
+    if let Some(mut next_state) = next_state {
+        next_state.set(state.clone());
+    }
+}
+
+/// A trait for replicating states with `bevy_replicon`
+pub trait StateMatchingRepliconApp {
+    /// Replicate `S` from the server to all clients using a `bevy_replicon` server event.
+    ///
+    /// Rather than overwriting `State<S>` on the clients, received values are queued in
+    /// [`NextMatchableState<S>`], so the exit and enter schedules run there as well.
+    /// Every change is broadcast, and clients connecting later are sent the current value.
+    /// Values matching the client's current state are ignored.
+    fn replicate_state_with_replicon<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl StateMatchingRepliconApp for App {
+    fn replicate_state_with_replicon<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+    ) -> &mut Self {
+        self.add_server_event::<StateReplicated<S>>(EventType::Ordered)
+            .add_systems(
+                StateTransition,
+                (
+                    queue_replicated_state::<S>.before(apply_state_transition::<S>),
+                    broadcast_state::<S>.after(apply_state_transition::<S>),
+                ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{broadcast_state, StateReplicated};
+    use bevy::prelude::{Events, Schedule, State, States, World};
+    use bevy_replicon::{
+        prelude::{SendMode, ToClients},
+        renet::{ClientId, ServerEvent},
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Serialize, Deserialize)]
+    enum NetState {
+        #[default]
+        Lobby,
+        InMatch,
+    }
+
+    #[test]
+    fn connecting_clients_receive_the_current_state() {
+        let mut world = World::new();
+        world.insert_resource(State::new(NetState::InMatch));
+        world.init_resource::<Events<ServerEvent>>();
+        world.init_resource::<Events<ToClients<StateReplicated<NetState>>>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(broadcast_state::<NetState>);
+
+        schedule.run(&mut world);
+        let sent = world
+            .resource_mut::<Events<ToClients<StateReplicated<NetState>>>>()
+            .drain()
+            .map(|sent| (sent.mode, sent.event.0))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            sent.as_slice(),
+            [(SendMode::Broadcast, NetState::InMatch)]
+        ));
+
+        let client_id = ClientId::from_raw(7);
+        world.send_event(ServerEvent::ClientConnected { client_id });
+        schedule.run(&mut world);
+        let sent = world
+            .resource_mut::<Events<ToClients<StateReplicated<NetState>>>>()
+            .drain()
+            .map(|sent| (sent.mode, sent.event.0))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            sent.as_slice(),
+            [(SendMode::Direct(id), NetState::InMatch)] if *id == client_id
+        ));
+    }
+}
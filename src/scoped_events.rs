@@ -0,0 +1,204 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{
+        App, Event, EventReader, EventWriter, IntoSystemConfigs, Res, ResMut, Resource, State,
+        StateTransition,
+    },
+};
+
+use crate::{apply_state_transition, MatchableState, StateMatcher};
+
+/// What happens to events of a type registered with
+/// [`StateMatchingEventsApp::add_state_scoped_event`] that are sent outside of the matching states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfStateEvents {
+    /// The events are never delivered
+    #[default]
+    Drop,
+    /// The events are kept, and delivered as soon as a matching state is entered
+    Buffer,
+}
+
+/// An event of type `E` delivered to [`StateScopedEvents<E, S>`] while the state of `S` matched its scope
+#[derive(Event, Debug, Clone)]
+pub struct ScopedEvent<E: Event + Clone, S: MatchableState>(E, PhantomData<fn() -> S>);
+
+impl<E: Event + Clone, S: MatchableState> ScopedEvent<E, S> {
+    /// The event
+    pub fn get(&self) -> &E {
+        &self.0
+    }
+}
+
+#[derive(Resource)]
+struct ScopedEventBuffer<E: Event + Clone, S: MatchableState> {
+    policy: OutOfStateEvents,
+    buffered: Vec<E>,
+    marker: PhantomData<fn() -> S>,
+}
+
+/// A system param for reading events of type `E` only while the state of `S` is in their scope,
+/// registered with [`StateMatchingEventsApp::add_state_scoped_event`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum GameState {
+/// #   #[default]
+/// #   Menu,
+/// #   Playing { paused: bool },
+/// # }
+/// #[derive(Event, Clone)]
+/// struct PlayerJoined(u32);
+///
+/// fn spawn_players(mut joined: StateScopedEvents<PlayerJoined, GameState>) {
+///     for PlayerJoined(id) in joined.read() {
+///         info!("Spawning player {id}");
+///     }
+/// }
+///
+/// App::new()
+///     .add_matchable_state::<GameState>()
+///     .add_state_scoped_event::<PlayerJoined, GameState, _>(
+///         state_matches!(GameState, Playing { .. }),
+///         OutOfStateEvents::Buffer,
+///     )
+///     .add_systems(Update, spawn_players);
+/// ```
+#[derive(SystemParam)]
+pub struct StateScopedEvents<'w, 's, E: Event + Clone, S: MatchableState> {
+    reader: EventReader<'w, 's, ScopedEvent<E, S>>,
+}
+
+impl<'w, 's, E: Event + Clone, S: MatchableState> StateScopedEvents<'w, 's, E, S> {
+    /// Read the events delivered since the last time this system ran
+    pub fn read(&mut self) -> impl Iterator<Item = &E> + '_ {
+        self.reader.read().map(ScopedEvent::get)
+    }
+
+    /// Whether no events were delivered since the last time this system ran
+    pub fn is_empty(&self) -> bool {
+        self.reader.is_empty()
+    }
+
+    /// Skip the events delivered since the last time this system ran
+    pub fn clear(&mut self) {
+        self.reader.clear();
+    }
+}
+
+/// A trait for scoping events to matching states in a bevy `App`
+pub trait StateMatchingEventsApp {
+    /// Deliver events of type `E` to [`StateScopedEvents<E, S>`] only while the state of `S` matches `matcher`.
+    ///
+    /// Events sent outside of the matching states are dropped or buffered according to `policy`.
+    /// The state is checked right after it transitions in `StateTransition`, so events sent in `PreUpdate` -
+    /// like input and network events - are delivered during the same frame. Only one scope can be
+    /// registered for each pair of `E` and `S`.
+    fn add_state_scoped_event<E: Event + Clone, S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        policy: OutOfStateEvents,
+    ) -> &mut Self;
+}
+
+impl StateMatchingEventsApp for App {
+    fn add_state_scoped_event<E: Event + Clone, S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        policy: OutOfStateEvents,
+    ) -> &mut Self {
+        self.add_event::<E>()
+            .add_event::<ScopedEvent<E, S>>()
+            .insert_resource(ScopedEventBuffer::<E, S> {
+                policy,
+                buffered: vec![],
+                marker: PhantomData,
+            })
+            .add_systems(
+                StateTransition,
+                (move |mut events: EventReader<E>,
+                       state: Option<Res<State<S>>>,
+                       mut buffer: ResMut<ScopedEventBuffer<E, S>>,
+                       mut scoped: EventWriter<ScopedEvent<E, S>>| {
+                    let in_scope = state.is_some_and(|state| matcher.match_state(state.get()));
+                    if in_scope {
+                        scoped.send_batch(
+                            buffer
+                                .buffered
+                                .drain(..)
+                                .chain(events.read().cloned())
+                                .map(|event| ScopedEvent(event, PhantomData)),
+                        );
+                    } else if buffer.policy == OutOfStateEvents::Buffer {
+                        buffer.buffered.extend(events.read().cloned());
+                    } else {
+                        events.clear();
+                    }
+                })
+                .after(apply_state_transition::<S>),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        NextMatchableState, OutOfStateEvents, StateMatchingApp, StateMatchingEventsApp,
+        StateScopedEvents,
+    };
+    use bevy::prelude::{App, Event, Events, ResMut, Resource, States, Update};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    #[derive(Event, Clone)]
+    struct Ping(u32);
+
+    #[derive(Resource, Default)]
+    struct Received(Vec<u32>);
+
+    fn send(app: &mut App, ping: u32) {
+        app.world.resource_mut::<Events<Ping>>().send(Ping(ping));
+    }
+
+    #[test]
+    fn events_outside_the_scope_are_buffered_until_it_is_entered() {
+        let mut app = App::new();
+        app.init_resource::<Received>()
+            .add_matchable_state::<TestState>()
+            .add_state_scoped_event::<Ping, TestState, _>(
+                TestState::Playing,
+                OutOfStateEvents::Buffer,
+            )
+            .add_systems(
+                Update,
+                |mut pings: StateScopedEvents<Ping, TestState>, mut received: ResMut<Received>| {
+                    received.0.extend(pings.read().map(|ping| ping.0));
+                },
+            );
+        app.update();
+
+        send(&mut app, 1);
+        app.update();
+        assert!(app.world.resource::<Received>().0.is_empty());
+
+        send(&mut app, 2);
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::Playing);
+        app.update();
+        assert_eq!(app.world.resource::<Received>().0, vec![1, 2]);
+
+        send(&mut app, 3);
+        app.update();
+        assert_eq!(app.world.resource::<Received>().0, vec![1, 2, 3]);
+    }
+}
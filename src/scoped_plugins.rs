@@ -0,0 +1,116 @@
+use bevy::{
+    app::Plugins,
+    ecs::schedule::Schedules,
+    prelude::{App, First, IntoSystemConfigs, Schedule, SystemSet, World},
+};
+
+use crate::{MatchableState, StateMatcher, StateMatchingSystems};
+
+/// The set containing the systems that run the schedules of plugins added with
+/// [`StateMatchingApp::add_plugins_in`](crate::StateMatchingApp::add_plugins_in) -
+/// use it to order app systems before or after them.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScopedPluginSet;
+
+/// Builds `plugins` into a fresh set of schedules, and then runs each of the resulting
+/// schedules from within the app's schedule of the same label - only while `matcher` holds.
+///
+/// Schedules the app doesn't have yet are created, so the plugins' systems are gated there as well.
+/// `First` is left out of the gate, so the event queues bevy updates there keep being cleared.
+pub(crate) fn add_plugins_in<S: MatchableState, M: 'static, P>(
+    app: &mut App,
+    matcher: impl StateMatcher<S, M> + Clone,
+    plugins: impl Plugins<P>,
+) {
+    let mut original = std::mem::replace(
+        &mut *app.world.resource_mut::<Schedules>(),
+        Schedules::new(),
+    );
+    if let Some(first) = original.remove(&First) {
+        app.world.resource_mut::<Schedules>().insert(first);
+    }
+    app.add_plugins(plugins);
+    let mut scoped = std::mem::replace(&mut *app.world.resource_mut::<Schedules>(), original);
+    if let Some(first) = scoped.remove(&First) {
+        app.world.resource_mut::<Schedules>().insert(first);
+    }
+
+    let labels = scoped
+        .iter()
+        .map(|(label, _)| label.dyn_clone())
+        .collect::<Vec<_>>();
+
+    let mut schedules = app.world.resource_mut::<Schedules>();
+    for label in labels {
+        let Some(mut schedule) = scoped.remove(label.as_ref()) else {
+            continue;
+        };
+        if !schedules.contains(label.as_ref()) {
+            schedules.insert(Schedule::new(label.dyn_clone()));
+        }
+        let Some(target) = schedules.get_mut(label.as_ref()) else {
+            continue;
+        };
+        target.add_systems(
+            (move |world: &mut World| schedule.run(world))
+                .run_in(matcher.clone())
+                .in_set(ScopedPluginSet),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{
+        App, Event, EventReader, Events, Plugin, ResMut, Resource, States, Update,
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    #[derive(Event)]
+    struct Ping;
+
+    #[derive(Resource, Default)]
+    struct Pings(usize);
+
+    struct PingPlugin;
+
+    impl Plugin for PingPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_event::<Ping>().add_systems(
+                Update,
+                |mut pings: EventReader<Ping>, mut count: ResMut<Pings>| {
+                    count.0 += pings.read().count();
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn scoped_plugins_only_run_in_matching_states() {
+        let mut app = App::new();
+        app.init_resource::<Pings>()
+            .add_matchable_state::<TestState>()
+            .add_plugins_in(TestState::Playing, PingPlugin);
+        app.update();
+
+        app.world.send_event(Ping);
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<Pings>().0, 0);
+        assert!(app.world.resource::<Events<Ping>>().is_empty());
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::Playing);
+        app.world.send_event(Ping);
+        app.update();
+        assert_eq!(app.world.resource::<Pings>().0, 1);
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use bevy::{
+    prelude::{App, Reflect, Resource, State, World},
+    reflect::TypePath,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    registration::{NamedState, NamedStates},
+    snapshot::{can_queue_state, capture_state, parse_state, QueueState},
+    DynamicMatcher, MatchableState,
+};
+
+struct ScriptStateEntry {
+    current: fn(&World) -> Option<Result<String, ron::Error>>,
+    parse: fn(&str) -> Result<QueueState, ron::error::SpannedError>,
+    can_queue: fn(&World) -> bool,
+    matches: fn(&World, &DynamicMatcher) -> Option<bool>,
+}
+
+/// The state types available to a [`ScriptStateApi`], registered with
+/// [`StateMatchingScriptingApp::register_script_state`]
+#[derive(Resource, Default)]
+pub struct ScriptStateRegistry(NamedStates<ScriptStateEntry>);
+
+/// An error returned by the [`ScriptStateApi`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptStateError {
+    /// No state with this name was registered
+    UnknownState(String),
+    /// The state is registered, but doesn't exist in the world
+    MissingState(String),
+    /// The state with the given name couldn't be serialized
+    Serialize(String, String),
+    /// The value for the state with the given name couldn't be deserialized
+    Deserialize(String, String),
+}
+
+impl Display for ScriptStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownState(state) => write!(f, "unknown state {state}"),
+            Self::MissingState(state) => write!(f, "state {state} doesn't exist in the world"),
+            Self::Serialize(state, e) => write!(f, "couldn't serialize state {state}: {e}"),
+            Self::Deserialize(state, e) => write!(f, "couldn't deserialize state {state}: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ScriptStateError {}
+
+/// A type-erased facade over the states registered with
+/// [`StateMatchingScriptingApp::register_script_state`], for driving game flow from scripts.
+///
+/// States are referred to by their short or full type path, and values are passed around as RON,
+/// so the methods map directly onto Lua or Rhai bindings (or `bevy_mod_scripting` APIs).
+pub struct ScriptStateApi<'w> {
+    world: &'w mut World,
+}
+
+impl<'w> ScriptStateApi<'w> {
+    /// Access the registered states in `world`
+    pub fn new(world: &'w mut World) -> Self {
+        Self { world }
+    }
+
+    fn with_entry<T>(
+        &mut self,
+        type_name: &str,
+        f: impl FnOnce(&NamedState<ScriptStateEntry>, &mut World) -> Result<T, ScriptStateError>,
+    ) -> Result<T, ScriptStateError> {
+        let registry = self
+            .world
+            .remove_resource::<ScriptStateRegistry>()
+            .unwrap_or_default();
+        let result = registry
+            .0
+            .find(type_name)
+            .ok_or_else(|| ScriptStateError::UnknownState(type_name.to_string()))
+            .and_then(|state| f(state, self.world));
+        self.world.insert_resource(registry);
+        result
+    }
+
+    /// Queue a transition of the state called `type_name` into `value_ron`.
+    ///
+    /// The transition is queued in [`NextMatchableState`], so all the exit and enter schedules run as usual.
+    pub fn set_state(&mut self, type_name: &str, value_ron: &str) -> Result<(), ScriptStateError> {
+        self.with_entry(type_name, |state, world| {
+            let queue = (state.entry.parse)(value_ron).map_err(|e| {
+                ScriptStateError::Deserialize(state.name.to_string(), e.to_string())
+            })?;
+            if !(state.entry.can_queue)(world) {
+                return Err(ScriptStateError::MissingState(state.name.to_string()));
+            }
+            queue(world);
+            Ok(())
+        })
+    }
+
+    /// The current value of the state called `type_name`, as RON
+    pub fn current_state(&mut self, type_name: &str) -> Result<String, ScriptStateError> {
+        self.with_entry(type_name, |state, world| {
+            match (state.entry.current)(world) {
+                Some(Ok(value)) => Ok(value),
+                Some(Err(e)) => Err(ScriptStateError::Serialize(
+                    state.name.to_string(),
+                    e.to_string(),
+                )),
+                None => Err(ScriptStateError::MissingState(state.name.to_string())),
+            }
+        })
+    }
+
+    /// Whether the state called `type_name` is currently the enum variant called `variant`
+    pub fn matches(&mut self, type_name: &str, variant: &str) -> Result<bool, ScriptStateError> {
+        let matcher = DynamicMatcher::variant(variant);
+        self.with_entry(type_name, |state, world| {
+            (state.entry.matches)(world, &matcher)
+                .ok_or_else(|| ScriptStateError::MissingState(state.name.to_string()))
+        })
+    }
+}
+
+fn state_matches<S: MatchableState + Reflect>(
+    world: &World,
+    matcher: &DynamicMatcher,
+) -> Option<bool> {
+    world
+        .get_resource::<State<S>>()
+        .map(|state| matcher.matches(state.get()))
+}
+
+/// A trait exposing states to scripts through the [`ScriptStateApi`]
+pub trait StateMatchingScriptingApp {
+    /// Make `S` available to the [`ScriptStateApi`], under its short and full type path
+    fn register_script_state<
+        S: MatchableState + Serialize + DeserializeOwned + Reflect + TypePath,
+    >(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl StateMatchingScriptingApp for App {
+    fn register_script_state<
+        S: MatchableState + Serialize + DeserializeOwned + Reflect + TypePath,
+    >(
+        &mut self,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ScriptStateRegistry::default)
+            .0
+            .register::<S>(|| ScriptStateEntry {
+                current: capture_state::<S>,
+                parse: parse_state::<S>,
+                can_queue: can_queue_state::<S>,
+                matches: state_matches::<S>,
+            });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ScriptStateApi, ScriptStateError, StateMatchingApp, StateMatchingScriptingApp};
+    use bevy::prelude::{App, Reflect, State, States};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(
+        States, Reflect, Serialize, Deserialize, PartialEq, Eq, Debug, Default, Hash, Clone,
+    )]
+    enum AppState {
+        #[default]
+        MainMenu,
+        InGame {
+            paused: bool,
+        },
+    }
+
+    #[test]
+    fn scripts_can_drive_states() {
+        let mut app = App::new();
+        app.add_matchable_state::<AppState>()
+            .register_script_state::<AppState>();
+        app.update();
+
+        let mut api = ScriptStateApi::new(&mut app.world);
+        assert_eq!(api.current_state("AppState"), Ok("MainMenu".to_string()));
+        assert_eq!(
+            api.matches("Unknown", "MainMenu"),
+            Err(ScriptStateError::UnknownState("Unknown".to_string()))
+        );
+        api.set_state("AppState", "InGame(paused: false)").unwrap();
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::InGame { paused: false }
+        );
+        let mut api = ScriptStateApi::new(&mut app.world);
+        assert_eq!(api.matches("AppState", "InGame"), Ok(true));
+    }
+    #[test]
+    fn setting_missing_states_fails() {
+        let mut app = App::new();
+        app.register_script_state::<AppState>();
+
+        let mut api = ScriptStateApi::new(&mut app.world);
+        assert_eq!(
+            api.set_state("AppState", "MainMenu"),
+            Err(ScriptStateError::MissingState("AppState".to_string()))
+        );
+    }
+}
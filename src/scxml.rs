@@ -0,0 +1,168 @@
+use std::fmt::{Display, Write};
+
+use quick_xml::{events::Event, Reader};
+
+use crate::{MatchableState, StateMachineBuilder, TransitionTable};
+
+/// An error encountered while importing an SCXML document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScxmlError {
+    /// The document isn't valid XML
+    Xml(String),
+    /// A state id couldn't be converted into a state
+    UnknownState(String),
+}
+
+impl Display for ScxmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScxmlError::Xml(e) => write!(f, "invalid SCXML document: {e}"),
+            ScxmlError::UnknownState(id) => write!(f, "unknown state id in SCXML document: {id}"),
+        }
+    }
+}
+
+impl std::error::Error for ScxmlError {}
+
+impl<S: MatchableState> TransitionTable<S> {
+    /// Export the table as an SCXML statechart, using the `Debug` representation of each state as its id.
+    ///
+    /// Transitions from any state are expanded into a transition in every state.
+    /// Guards can't be represented, so guarded transitions are exported as if they were unguarded.
+    pub fn to_scxml(&self) -> String {
+        let id = |state: &S| escape(&format!("{state:?}"));
+        let mut xml = String::new();
+        writeln!(
+            xml,
+            "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" initial=\"{}\">",
+            id(&S::default())
+        )
+        .ok();
+        for state in self.states() {
+            writeln!(xml, "    <state id=\"{}\">", id(state)).ok();
+            for edge in self.edges() {
+                if edge.from.as_ref().map_or(true, |from| from == state) {
+                    writeln!(xml, "        <transition target=\"{}\"/>", id(&edge.to)).ok();
+                }
+            }
+            writeln!(xml, "    </state>").ok();
+        }
+        xml.push_str("</scxml>\n");
+        xml
+    }
+}
+
+impl<S: MatchableState> StateMachineBuilder<S> {
+    /// Import the states and transitions of an SCXML statechart, converting state ids with `parse`.
+    ///
+    /// Nested states are flattened, and everything beyond states and transition targets
+    /// (events, conditions, executable content) is ignored.
+    pub fn from_scxml(xml: &str, parse: impl Fn(&str) -> Option<S>) -> Result<Self, ScxmlError> {
+        let parse = |id: &str| parse(id).ok_or_else(|| ScxmlError::UnknownState(id.to_string()));
+        let mut builder = Self::new();
+        let mut parents: Vec<Option<S>> = vec![];
+        let mut reader = Reader::from_str(xml);
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| ScxmlError::Xml(e.to_string()))?;
+            let (element, is_empty) = match &event {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::End(end) => {
+                    if matches!(end.name().as_ref(), b"state" | b"final" | b"parallel") {
+                        parents.pop();
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+            let attribute = |name: &str| -> Result<Option<String>, ScxmlError> {
+                element
+                    .try_get_attribute(name)
+                    .map_err(|e| ScxmlError::Xml(e.to_string()))?
+                    .map(|value| {
+                        value
+                            .unescape_value()
+                            .map(|value| value.to_string())
+                            .map_err(|e| ScxmlError::Xml(e.to_string()))
+                    })
+                    .transpose()
+            };
+            match element.name().as_ref() {
+                b"state" | b"final" | b"parallel" => {
+                    let state = attribute("id")?.map(|id| parse(&id)).transpose()?;
+                    if let Some(state) = &state {
+                        builder = builder.state(state.clone());
+                    }
+                    if !is_empty {
+                        parents.push(state);
+                    }
+                }
+                b"transition" => {
+                    let Some(Some(from)) = parents.last().cloned() else {
+                        continue;
+                    };
+                    for target in attribute("target")?.unwrap_or_default().split_whitespace() {
+                        builder = builder.transition(from.clone(), parse(target)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(builder)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StateMachineBuilder;
+    use bevy::prelude::States;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn parse(id: &str) -> Option<TestState> {
+        match id {
+            "A" => Some(TestState::A),
+            "B" => Some(TestState::B),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_scxml() {
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .transition(TestState::B, TestState::A)
+            .build();
+        let xml = table.to_scxml();
+        let imported = StateMachineBuilder::from_scxml(&xml, parse)
+            .expect("the exported document should be importable")
+            .build();
+        assert_eq!(imported.states(), table.states());
+        assert_eq!(imported.to_scxml(), xml);
+    }
+
+    #[test]
+    fn unknown_states_are_an_error() {
+        let result = StateMachineBuilder::from_scxml(
+            "<scxml><state id=\"A\"><transition target=\"C\"/></state></scxml>",
+            parse,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{MatchableState, NextMatchableState};
+
+#[derive(Serialize)]
+#[serde(rename = "NextMatchableState")]
+enum SerializedNextState<'a, S> {
+    Keep,
+    Value(&'a S),
+    NamedSetter(&'a str),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "NextMatchableState")]
+enum DeserializedNextState<S> {
+    Keep,
+    Value(S),
+    NamedSetter(String),
+}
+
+/// Only the `Keep`, `Value` and `NamedSetter` variants can be serialized - serializing a `Setter` or `ValueWithPayload` is an error.
+impl<S: MatchableState + Serialize> Serialize for NextMatchableState<S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match self {
+            NextMatchableState::Keep => SerializedNextState::<S>::Keep.serialize(serializer),
+            NextMatchableState::Value(value) => {
+                SerializedNextState::Value(value).serialize(serializer)
+            }
+            NextMatchableState::NamedSetter(name) => {
+                SerializedNextState::<S>::NamedSetter(name).serialize(serializer)
+            }
+            NextMatchableState::Setter(_) => Err(Ser::Error::custom(
+                "NextMatchableState::Setter can't be serialized",
+            )),
+            NextMatchableState::ValueWithPayload(..) => Err(Ser::Error::custom(
+                "NextMatchableState::ValueWithPayload can't be serialized",
+            )),
+        }
+    }
+}
+
+impl<'de, S: MatchableState + Deserialize<'de>> Deserialize<'de> for NextMatchableState<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match DeserializedNextState::deserialize(deserializer)? {
+            DeserializedNextState::Keep => NextMatchableState::Keep,
+            DeserializedNextState::Value(value) => NextMatchableState::Value(value),
+            DeserializedNextState::NamedSetter(name) => NextMatchableState::NamedSetter(name),
+        })
+    }
+}
+
+/// Serialize and deserialize a bevy `State<S>` as its current value.
+///
+/// `State<S>` is defined in bevy, so it can't implement the serde traits directly -
+/// instead, use this module with `#[serde(with = ...)]`:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct SaveFile {
+///     #[serde(with = "serde_state")]
+///     state: State<AppState>,
+///     next_state: NextMatchableState<AppState>,
+/// }
+/// ```
+pub mod serde_state {
+    use bevy::prelude::State;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::MatchableState;
+
+    /// Serialize the current value of `state`
+    pub fn serialize<S: MatchableState + Serialize, Ser: Serializer>(
+        state: &State<S>,
+        serializer: Ser,
+    ) -> Result<Ser::Ok, Ser::Error> {
+        state.get().serialize(serializer)
+    }
+
+    /// Deserialize a value into a new `State<S>`
+    pub fn deserialize<'de, S: MatchableState + Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<State<S>, D::Error> {
+        S::deserialize(deserializer).map(State::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{serde_state, NextMatchableState};
+    use bevy::prelude::{State, States};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Serialize, Deserialize)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Saved {
+        #[serde(with = "serde_state")]
+        state: State<TestState>,
+        next: NextMatchableState<TestState>,
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let json = serde_json::to_string(&Saved {
+            state: State::new(TestState::B),
+            next: NextMatchableState::Value(TestState::A),
+        })
+        .unwrap();
+        let saved: Saved = serde_json::from_str(&json).unwrap();
+        assert_eq!(saved.state.get(), &TestState::B);
+        assert!(matches!(
+            saved.next,
+            NextMatchableState::Value(TestState::A)
+        ));
+    }
+
+    #[test]
+    fn named_setters_round_trip() {
+        let json = serde_json::to_string(&NextMatchableState::<TestState>::NamedSetter(
+            "toggle".into(),
+        ))
+        .unwrap();
+        let next: NextMatchableState<TestState> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(next, NextMatchableState::NamedSetter(name) if name == "toggle"));
+    }
+
+    #[test]
+    fn setters_fail_to_serialize() {
+        let next = NextMatchableState::<TestState>::Setter(Box::new(|s| s));
+        assert!(serde_json::to_string(&next).is_err());
+    }
+}
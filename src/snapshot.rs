@@ -0,0 +1,267 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use bevy::prelude::{App, Resource, State, World};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{MatchableState, NextMatchableState};
+
+/// A parsed state value, queuing a transition into it once called
+pub(crate) type QueueState = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+struct SnapshotEntry {
+    key: &'static str,
+    capture: fn(&World) -> Option<Result<String, ron::Error>>,
+    parse: fn(&str) -> Result<QueueState, ron::error::SpannedError>,
+    can_queue: fn(&World) -> bool,
+}
+
+/// The state types included in a [`StateSnapshot`], registered with
+/// [`StateMatchingSnapshotApp::register_state_snapshot`]
+#[derive(Resource, Default)]
+pub struct SnapshotRegistry(Vec<SnapshotEntry>);
+
+/// An error encountered while capturing or applying a [`StateSnapshot`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateSnapshotError {
+    /// The state with the given key couldn't be serialized
+    Serialize(String, String),
+    /// The state with the given key couldn't be deserialized
+    Deserialize(String, String),
+    /// The state with the given key is registered, but has no [`NextMatchableState`] in the world
+    MissingState(String),
+}
+
+impl Display for StateSnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Serialize(key, e) => write!(f, "couldn't serialize state {key}: {e}"),
+            Self::Deserialize(key, e) => write!(f, "couldn't deserialize state {key}: {e}"),
+            Self::MissingState(key) => write!(f, "state {key} doesn't exist in the world"),
+        }
+    }
+}
+
+impl std::error::Error for StateSnapshotError {}
+
+/// A serializable snapshot of every registered state, for save games and checkpoints.
+///
+/// Each state is stored as a RON string, keyed by its type name - so snapshots are only
+/// guaranteed to be compatible with builds using the same state types.
+/// Only states opted in with [`StateMatchingSnapshotApp::register_state_snapshot`] are included -
+/// adding a state with `add_matchable_state` isn't enough, since it needs to be serializable.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct StateSnapshot {
+    states: BTreeMap<String, String>,
+}
+
+impl StateSnapshot {
+    /// Capture the current value of every registered state that exists in `world`
+    pub fn capture(world: &World) -> Result<Self, StateSnapshotError> {
+        let mut states = BTreeMap::new();
+        let Some(registry) = world.get_resource::<SnapshotRegistry>() else {
+            return Ok(Self { states });
+        };
+        for entry in registry.0.iter() {
+            match (entry.capture)(world) {
+                Some(Ok(value)) => {
+                    states.insert(entry.key.to_string(), value);
+                }
+                Some(Err(e)) => {
+                    return Err(StateSnapshotError::Serialize(
+                        entry.key.to_string(),
+                        e.to_string(),
+                    ))
+                }
+                None => {}
+            }
+        }
+        Ok(Self { states })
+    }
+
+    /// Queue a transition into the captured value of every registered state in the snapshot.
+    ///
+    /// The transitions are applied by [`apply_state_transition`](crate::apply_state_transition)
+    /// as usual, so all the exit and enter schedules run.
+    /// Every value is deserialized before anything is queued, so a corrupt snapshot - or one containing
+    /// a state missing from `world` - queues nothing. States in the snapshot that aren't registered are ignored.
+    pub fn apply(&self, world: &mut World) -> Result<(), StateSnapshotError> {
+        let Some(registry) = world.get_resource::<SnapshotRegistry>() else {
+            return Ok(());
+        };
+        let queued = registry
+            .0
+            .iter()
+            .filter_map(|entry| Some((entry, self.states.get(entry.key)?)))
+            .map(|(entry, value)| {
+                if !(entry.can_queue)(world) {
+                    return Err(StateSnapshotError::MissingState(entry.key.to_string()));
+                }
+                (entry.parse)(value).map_err(|e| {
+                    StateSnapshotError::Deserialize(entry.key.to_string(), e.to_string())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for queue in queued {
+            queue(world);
+        }
+        Ok(())
+    }
+
+    /// Whether the snapshot contains a value for `S`
+    pub fn contains<S: MatchableState>(&self) -> bool {
+        self.states.contains_key(std::any::type_name::<S>())
+    }
+}
+
+/// The current value of `S` as RON, if it exists in `world`
+pub(crate) fn capture_state<S: MatchableState + Serialize>(
+    world: &World,
+) -> Option<Result<String, ron::Error>> {
+    world
+        .get_resource::<State<S>>()
+        .map(|state| ron::to_string(state.get()))
+}
+
+/// Parse `value` into `S`, returning a closure that queues a transition of `S` into it
+pub(crate) fn parse_state<S: MatchableState + DeserializeOwned>(
+    value: &str,
+) -> Result<QueueState, ron::error::SpannedError> {
+    let state = ron::from_str::<S>(value)?;
+    Ok(Box::new(move |world: &mut World| {
+        if let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() {
+            next_state.set(state);
+        }
+    }))
+}
+
+/// Whether transitions of `S` can be queued in `world`
+pub(crate) fn can_queue_state<S: MatchableState>(world: &World) -> bool {
+    world.contains_resource::<NextMatchableState<S>>()
+}
+
+/// A trait adding state snapshots to a bevy `App`
+pub trait StateMatchingSnapshotApp {
+    /// Include `S` in every [`StateSnapshot`].
+    ///
+    /// States registered with `add_matchable_state` aren't included unless they are opted in here.
+    fn register_state_snapshot<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl StateMatchingSnapshotApp for App {
+    fn register_state_snapshot<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+    ) -> &mut Self {
+        let key = std::any::type_name::<S>();
+        let mut registry = self
+            .world
+            .get_resource_or_insert_with(SnapshotRegistry::default);
+        if !registry.0.iter().any(|entry| entry.key == key) {
+            registry.0.push(SnapshotEntry {
+                key,
+                capture: capture_state::<S>,
+                parse: parse_state::<S>,
+                can_queue: can_queue_state::<S>,
+            });
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply_state_transition, NextMatchableState, StateMatchingSnapshotApp, StateSnapshot,
+        StateSnapshotError,
+    };
+    use bevy::prelude::{App, State, States};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Serialize, Deserialize)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone, Serialize, Deserialize)]
+    struct Level(u32);
+
+    #[test]
+    fn snapshots_queue_transitions_back_to_captured_states() {
+        let mut app = App::new();
+        app.register_state_snapshot::<TestState>()
+            .register_state_snapshot::<Level>();
+        app.world.insert_resource(State::new(TestState::B));
+        app.world.insert_resource(State::new(Level(3)));
+        app.world.init_resource::<NextMatchableState<TestState>>();
+        app.world.init_resource::<NextMatchableState<Level>>();
+
+        let snapshot = StateSnapshot::capture(&app.world).unwrap();
+        assert!(snapshot.contains::<Level>());
+
+        app.world.insert_resource(State::new(TestState::A));
+        app.world.insert_resource(State::new(Level(0)));
+        snapshot.apply(&mut app.world).unwrap();
+        apply_state_transition::<TestState>(&mut app.world);
+        apply_state_transition::<Level>(&mut app.world);
+
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert_eq!(app.world.resource::<State<Level>>().get(), &Level(3));
+    }
+
+    #[test]
+    fn corrupt_snapshots_queue_nothing() {
+        let mut app = App::new();
+        app.register_state_snapshot::<TestState>()
+            .register_state_snapshot::<Level>();
+        app.world.insert_resource(State::new(TestState::B));
+        app.world.insert_resource(State::new(Level(3)));
+        app.world.init_resource::<NextMatchableState<TestState>>();
+        app.world.init_resource::<NextMatchableState<Level>>();
+
+        let mut snapshot = StateSnapshot::capture(&app.world).unwrap();
+        snapshot.states.insert(
+            std::any::type_name::<Level>().to_string(),
+            "not a level".to_string(),
+        );
+
+        app.world.insert_resource(State::new(TestState::A));
+        assert!(matches!(
+            snapshot.apply(&mut app.world),
+            Err(StateSnapshotError::Deserialize(..))
+        ));
+        apply_state_transition::<TestState>(&mut app.world);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+    }
+    #[test]
+    fn snapshots_of_missing_states_queue_nothing() {
+        let mut app = App::new();
+        app.register_state_snapshot::<TestState>()
+            .register_state_snapshot::<Level>();
+        app.world.insert_resource(State::new(TestState::B));
+        app.world.insert_resource(State::new(Level(3)));
+        app.world.init_resource::<NextMatchableState<TestState>>();
+        let snapshot = StateSnapshot::capture(&app.world).unwrap();
+
+        app.world.insert_resource(State::new(TestState::A));
+        assert_eq!(
+            snapshot.apply(&mut app.world),
+            Err(StateSnapshotError::MissingState(
+                std::any::type_name::<Level>().to_string()
+            ))
+        );
+        apply_state_transition::<TestState>(&mut app.world);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+    }
+}
@@ -1,9 +1,27 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
-use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*, tasks::Task};
 
-use super::state_matching::{MatchesStateTransition, StateMatcher};
+use super::cooldowns::{check_cooldowns, start_cooldowns};
+use super::fixed_timestep::transitions_deferred;
+use super::frame_budget::apply_within_budget;
+use super::payload::{clear_payload, insert_queued_payload, QueuedPayload};
+use super::plugin::{
+    pop_transition_queue, IdentityTransitions, StateOptions, StateTransitionEvent, TransitionOrder,
+};
+use super::registration::{report_state_error, StateMachineError};
+use super::state_lock::StateLock;
+use super::state_matching::{
+    evaluate_matcher_tracked, sealed, MatchesStateTransition, StateMatcher,
+};
+use super::state_switch::{DisabledStateMachine, StateMatchingSwitchWorld};
+use super::stepping::transition_held;
+use super::transition_hooks::run_transition_hooks;
+use super::transition_phase::{PhaseStatus, TransitionPhase};
+use super::transition_source::QueuedTransitionSource;
+use super::transition_table::{TransitionRejected, TransitionTable};
 
 /// Types that can define world-wide states in a finite-state machine.
 ///
@@ -104,6 +122,28 @@ pub trait MatchableState: bevy::ecs::schedule::States {
 
 impl<S: bevy::ecs::schedule::States> MatchableState for S {}
 
+/// States that [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state) can register.
+///
+/// With the `bevy_reflect` feature, this requires the state to be reflectable, and `State<S>` and
+/// [`NextMatchableState<S>`] get registered in the type registry - so they can be extracted into,
+/// and applied from, a `DynamicScene`. Otherwise, it is implemented for every type.
+#[cfg(feature = "bevy_reflect")]
+pub trait RegisterableState: bevy::reflect::GetTypeRegistration + FromReflect + TypePath {}
+
+#[cfg(feature = "bevy_reflect")]
+impl<S: bevy::reflect::GetTypeRegistration + FromReflect + TypePath> RegisterableState for S {}
+
+/// States that [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state) can register.
+///
+/// With the `bevy_reflect` feature, this requires the state to be reflectable, and `State<S>` and
+/// [`NextMatchableState<S>`] get registered in the type registry - so they can be extracted into,
+/// and applied from, a `DynamicScene`. Otherwise, it is implemented for every type.
+#[cfg(not(feature = "bevy_reflect"))]
+pub trait RegisterableState {}
+
+#[cfg(not(feature = "bevy_reflect"))]
+impl<S> RegisterableState for S {}
+
 /// A schedule that runs whenever any state is entered.
 /// This occurs either:
 ///
@@ -125,6 +165,101 @@ pub struct Entering;
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct Exiting;
 
+/// Ordered system sets within the [`Entering`] schedule, so plugins adding enter behavior
+/// can order themselves deterministically. They run in the order they are declared.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EnterSet {
+    /// Clearing out anything left over from before the state was entered
+    Cleanup,
+    /// Spawning the entities and resources of the entered state
+    Spawn,
+    /// Logic relying on everything spawned in [`EnterSet::Spawn`]
+    PostSpawn,
+}
+
+/// Ordered system sets within the [`Exiting`] schedule, so plugins adding exit behavior
+/// can order themselves deterministically. They run in the order they are declared.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExitSet {
+    /// Logic that still needs the entities and resources of the exited state, like saving progress
+    Teardown,
+    /// Despawning the entities of the exited state - where the crate's own cleanup, like
+    /// [`StateScoped`](crate::StateScoped) entities, runs
+    Cleanup,
+    /// Logic relying on the exited state being cleaned up
+    PostCleanup,
+}
+
+/// A schedule that runs when a queued transition leads back into the current state `S`.
+///
+/// Neither the exit nor the enter schedules run for such identity transitions, so restart-style
+/// flows can be handled here with lighter-weight logic than a full exit and enter cycle.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OnReenter<S: States>(pub S);
+
+/// The state re-entered by the last run of [`apply_state_transition`], if any - see [`OnReenter`]
+#[derive(Resource, Debug)]
+pub struct Reentered<S: MatchableState>(pub Option<S>);
+
+/// The state being transitioned into, only present while the exit schedules of `S` run -
+/// so teardown logic in [`OnExit`] and [`Exiting`] can branch on where the transition is going.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum AppState { #[default] InGame, Paused, Menu }
+/// fn save_progress(incoming: Res<IncomingState<AppState>>) {
+///     if incoming.get() == &AppState::Menu {
+///         // Only save when leaving the game for good
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug)]
+pub struct IncomingState<S: MatchableState>(S);
+
+impl<S: MatchableState> IncomingState<S> {
+    /// The state being entered
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+}
+
+/// The last transition of `S` that was applied - inserted once the first one completes
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct LastTransition<S: MatchableState> {
+    /// The state that was exited
+    pub from: S,
+    /// The state that was entered
+    pub to: S,
+}
+
+/// A run condition that is true the first time it is checked after a transition from a state matching `from`
+/// into a state matching `to` completed - so post-transition reactions can run in any schedule,
+/// rather than inside the transition schedules.
+pub fn on_transition<S: MatchableState, M1: 'static, M2: 'static>(
+    from: impl StateMatcher<S, M1>,
+    to: impl StateMatcher<S, M2>,
+) -> impl FnMut(Option<Res<LastTransition<S>>>) -> bool {
+    move |last: Option<Res<LastTransition<S>>>| {
+        last.is_some_and(|last| {
+            last.is_changed() && from.match_state(&last.from) && to.match_state(&last.to)
+        })
+    }
+}
+
+/// A run condition that is true on the frame a state matching `matcher` was re-entered - see [`OnReenter`]
+pub fn state_reentered<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+) -> impl FnMut(Option<Res<Reentered<S>>>) -> bool {
+    move |reentered: Option<Res<Reentered<S>>>| {
+        reentered
+            .as_ref()
+            .and_then(|reentered| reentered.0.as_ref())
+            .is_some_and(|state| matcher.match_state(state))
+    }
+}
+
 #[derive(Resource, Default, Debug)]
 #[cfg_attr(
     feature = "bevy_reflect",
@@ -138,12 +273,6 @@ impl<S: MatchableState> ActiveTransition<S> {
         Self(main, secondary)
     }
 
-    pub(crate) fn swap(&mut self) {
-        let main = self.0.clone();
-        self.0 = self.1.clone();
-        self.1 = main;
-    }
-
     pub(crate) fn get_main(&self) -> Option<&S> {
         self.0.as_ref()
     }
@@ -153,6 +282,154 @@ impl<S: MatchableState> ActiveTransition<S> {
     }
 }
 
+/// The value of [`State<S>`] as last set through a proper transition (or the initial enter),
+/// used to tell apart edits made to [`State<S>`] from the outside - such as by a reflection-based inspector.
+#[derive(Resource, Debug)]
+pub(crate) struct AppliedState<S: MatchableState>(pub(crate) S);
+
+/// A run condition that is true if `State<S>` exists and its value matches `matcher`.
+///
+/// Like bevy's `state_exists_and_equals`, this is false rather than panicking when the state is absent.
+/// It respects [`DisabledStateMachine<S>`] the way [`StateMatcherSystem`](crate::StateMatcherSystem) does.
+pub fn state_exists_and_matches<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+) -> impl FnMut(Option<Res<State<S>>>, Option<Res<DisabledStateMachine<S>>>, Local<Option<bool>>) -> bool
+{
+    move |state: Option<Res<State<S>>>,
+          disabled: Option<Res<DisabledStateMachine<S>>>,
+          mut last: Local<Option<bool>>| {
+        state_matches_now(&matcher, state, disabled, &mut last)
+    }
+}
+
+/// Whether `matcher` matches the current state, respecting [`DisabledStateMachine<S>`]
+fn state_matches_now<S: MatchableState, M>(
+    matcher: &impl StateMatcher<S, M>,
+    state: Option<Res<State<S>>>,
+    disabled: Option<Res<DisabledStateMachine<S>>>,
+    last: &mut Option<bool>,
+) -> bool {
+    evaluate_matcher_tracked(
+        matcher,
+        state.as_deref(),
+        None,
+        None,
+        disabled.as_deref(),
+        last,
+    )
+}
+
+/// A run condition that is true the first time it is checked after entering a state matching `matcher`.
+///
+/// It resets once it sees a state that doesn't match, so the system runs again on the next entry.
+/// Used by [`StateMatchingSystems::run_once_in`](crate::StateMatchingSystems::run_once_in).
+pub fn once_per_entry<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+) -> impl FnMut(
+    Option<Res<State<S>>>,
+    Option<Res<DisabledStateMachine<S>>>,
+    Local<bool>,
+    Local<Option<bool>>,
+) -> bool {
+    move |state: Option<Res<State<S>>>,
+          disabled: Option<Res<DisabledStateMachine<S>>>,
+          mut ran: Local<bool>,
+          mut last: Local<Option<bool>>| {
+        if !state_matches_now(&matcher, state, disabled, &mut last) {
+            *ran = false;
+            return false;
+        }
+        !std::mem::replace(&mut *ran, true)
+    }
+}
+
+/// A run condition that is true every `duration`, counting only the time spent in states matching `matcher`.
+///
+/// The timer resets once it sees a state that doesn't match, so periodic systems - like autosaving
+/// while in game - don't keep counting while paused or in menus.
+pub fn on_timer_in<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+    duration: std::time::Duration,
+) -> impl FnMut(
+    Option<Res<State<S>>>,
+    Option<Res<DisabledStateMachine<S>>>,
+    Local<Option<bool>>,
+    Res<Time>,
+) -> bool {
+    let mut timer = Timer::new(duration, TimerMode::Repeating);
+    move |state: Option<Res<State<S>>>,
+          disabled: Option<Res<DisabledStateMachine<S>>>,
+          mut last: Local<Option<bool>>,
+          time: Res<Time>| {
+        if !state_matches_now(&matcher, state, disabled, &mut last) {
+            timer.reset();
+            return false;
+        }
+        timer.tick(time.delta()).just_finished()
+    }
+}
+
+/// A run condition that is true if `State<S>` doesn't exist
+pub fn state_missing<S: MatchableState>() -> impl FnMut(Option<Res<State<S>>>) -> bool {
+    |state: Option<Res<State<S>>>| state.is_none()
+}
+
+/// A run condition that is true while a transition of `S` is in flight - so systems in the untyped
+/// [`Entering`] and [`Exiting`] schedules only run for the state types they care about.
+pub fn for_state<S: MatchableState>() -> impl FnMut(&World) -> bool {
+    |world: &World| world.contains_resource::<ActiveTransition<S>>()
+}
+
+/// Marks a transition of `S` as in progress, storing the state it started from.
+///
+/// If it is still around when [`apply_state_transition`] runs, an exit or enter schedule
+/// panicked mid-transition (and the panic was caught), so the transition is rolled back.
+#[derive(Resource, Debug)]
+struct TransitionInProgress<S: MatchableState>(S);
+
+/// Roll back a transition of `S` interrupted by a panic, if there was one - discarding the queued
+/// transition, so it isn't retried
+fn repair_interrupted_transition<S: MatchableState>(world: &mut World) {
+    let Some(TransitionInProgress(previous)) = world.remove_resource::<TransitionInProgress<S>>()
+    else {
+        return;
+    };
+    warn!(
+        "A transition of {} was interrupted by a panic - rolling back to {previous:?}",
+        std::any::type_name::<S>()
+    );
+    world.remove_resource::<ActiveTransition<S>>();
+    world.remove_resource::<IncomingState<S>>();
+    clear_payload::<S>(world);
+    world.insert_resource(State::new(previous.clone()));
+    world.insert_resource(AppliedState(previous));
+    if let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() {
+        *next_state = NextMatchableState::Keep;
+    }
+}
+
+/// Turns outside edits of [`State<S>`] (for example, through reflection) into real transitions:
+/// the edit is reverted, and the edited value is queued in [`NextMatchableState<S>`] instead.
+///
+/// Added by [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state)
+/// when the `bevy_reflect` feature is enabled.
+pub fn forward_state_edits<S: MatchableState>(world: &mut World) {
+    let (Some(state), Some(applied)) = (
+        world.get_resource::<State<S>>(),
+        world.get_resource::<AppliedState<S>>(),
+    ) else {
+        return;
+    };
+    if state.get() == &applied.0 {
+        return;
+    }
+    let (edited, applied) = (state.get().clone(), applied.0.clone());
+    world.insert_resource(State::new(applied));
+    if let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() {
+        next_state.set(edited);
+    }
+}
+
 /// The next state of [`State<S>`].
 ///
 /// To queue a transition, just set the contained value to `Some(next_state)`.
@@ -168,6 +445,16 @@ pub enum NextMatchableState<S: MatchableState> {
     Value(S),
     /// Change the state to a value determined by the given closure
     Setter(#[reflect(ignore, default = "default_setter")] Box<dyn Fn(S) -> S + Sync + Send>),
+    /// Change the state to a specific value, making a payload available while the transition is applied -
+    /// see [`set_with_payload`](NextMatchableState::set_with_payload)
+    ValueWithPayload(
+        S,
+        #[reflect(ignore, default = "QueuedPayload::default")] QueuedPayload,
+    ),
+    /// Change the state to a value determined by the setter registered under this name in [`StateSetters<S>`].
+    ///
+    /// Unlike [`Setter`](NextMatchableState::Setter), this can be reflected and serialized.
+    NamedSetter(String),
 }
 
 fn default_setter<S: MatchableState>() -> Box<dyn Fn(S) -> S + Sync + Send> {
@@ -179,7 +466,11 @@ impl<S: MatchableState> Debug for NextMatchableState<S> {
         match self {
             Self::Keep => write!(f, "Keep"),
             Self::Value(arg0) => f.debug_tuple("Value").field(arg0).finish(),
+            Self::ValueWithPayload(arg0, _) => {
+                f.debug_tuple("ValueWithPayload").field(arg0).finish()
+            }
             Self::Setter(_) => write!(f, "Setter"),
+            Self::NamedSetter(name) => f.debug_tuple("NamedSetter").field(name).finish(),
         }
     }
 }
@@ -189,6 +480,30 @@ impl<S: MatchableState> NextMatchableState<S> {
     pub fn set(&mut self, state: S) {
         *self = Self::Value(state);
     }
+
+    /// Tentatively set a planned state transition to `Some(state)`, passing `payload` along with it.
+    ///
+    /// While the transition is applied, the payload is available as a [`TransitionPayload<T>`](crate::TransitionPayload)
+    /// resource - so the exit and enter schedules know why, or with what, the state was entered.
+    /// It is dropped if the transition is rejected.
+    pub fn set_with_payload<T: Send + Sync + 'static>(&mut self, state: S, payload: T) {
+        *self = Self::ValueWithPayload(state, QueuedPayload::new(payload));
+    }
+
+    /// Set a planned state transition to `Some(state)`, unless a different transition is already queued.
+    ///
+    /// On conflict, the queued transition is left untouched and `state` is handed back in the error,
+    /// so the caller can decide whether to [`set`](NextMatchableState::set) it anyway or give up.
+    pub fn try_set(&mut self, state: S) -> Result<(), AlreadyQueued<S>> {
+        match self {
+            Self::Keep => {
+                *self = Self::Value(state);
+                Ok(())
+            }
+            Self::Value(queued) if queued == &state => Ok(()),
+            _ => Err(AlreadyQueued(state)),
+        }
+    }
     /// Tentatively set a planned state transition to `Some(state)`.
     ///
     /// # Example
@@ -235,58 +550,999 @@ impl<S: MatchableState> NextMatchableState<S> {
     pub fn setter(&mut self, setter: impl Fn(S) -> S + 'static + Sync + Send) {
         *self = Self::Setter(Box::new(setter));
     }
+
+    /// Tentatively set a planned state transition to the result of the setter registered as `name`
+    /// with [`StateMatchingApp::register_state_setter`](crate::StateMatchingApp::register_state_setter).
+    pub fn named_setter(&mut self, name: impl Into<String>) {
+        *self = Self::NamedSetter(name.into());
+    }
+
+    /// The state this would transition `current` into, if any
+    pub(crate) fn resolve(&self, current: &S, setters: Option<&StateSetters<S>>) -> Option<S> {
+        match self {
+            Self::Keep => None,
+            Self::Value(value) | Self::ValueWithPayload(value, _) => Some(value.clone()),
+            Self::Setter(setter) => Some(setter(current.clone())),
+            Self::NamedSetter(name) => {
+                let entered = setters.and_then(|setters| setters.apply(name, current.clone()));
+                if entered.is_none() {
+                    warn!(
+                        "No state setter named {name:?} is registered for {}",
+                        std::any::type_name::<S>()
+                    );
+                }
+                entered
+            }
+        }
+    }
+}
+
+/// Setters registered by name, so they can be queued with [`NextMatchableState::named_setter`]
+///
+/// Added by [`StateMatchingApp::register_state_setter`](crate::StateMatchingApp::register_state_setter).
+#[derive(Resource)]
+pub struct StateSetters<S: MatchableState>(HashMap<String, Box<dyn Fn(S) -> S + Sync + Send>>);
+
+impl<S: MatchableState> Default for StateSetters<S> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<S: MatchableState> StateSetters<S> {
+    /// Register `setter` under `name`, replacing any setter with the same name
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        setter: impl Fn(S) -> S + Sync + Send + 'static,
+    ) {
+        self.0.insert(name.into(), Box::new(setter));
+    }
+
+    /// Whether a setter is registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Run the setter registered under `name` on `state`
+    pub fn apply(&self, name: &str, state: S) -> Option<S> {
+        self.0.get(name).map(|setter| setter(state))
+    }
+}
+
+/// The error returned by [`NextMatchableState::try_set`] when a different transition is already queued,
+/// holding the state that wasn't queued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlreadyQueued<S: MatchableState>(pub S);
+
+impl<S: MatchableState> std::fmt::Display for AlreadyQueued<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "can't queue {:?}, a different transition of {} is already queued",
+            self.0,
+            std::any::type_name::<S>()
+        )
+    }
+}
+
+impl<S: MatchableState> std::error::Error for AlreadyQueued<S> {}
+
+/// A transition that is waiting on an asynchronous task, or a condition, before being queued.
+///
+/// Once the task completes (or the condition holds), [`apply_state_transition`] queues the target state
+/// in [`NextMatchableState<S>`] and applies it as usual.
+/// Useful for save/load, network handshakes, world generation and the like.
+#[derive(Resource)]
+pub struct PendingTransition<S: MatchableState>(Option<(Box<dyn PendingTask>, S)>);
+
+trait PendingTask: Send + Sync + 'static {
+    fn is_finished(&self, world: &World) -> bool;
+}
+
+impl<T: Send + 'static> PendingTask for Task<T> {
+    fn is_finished(&self, _: &World) -> bool {
+        Task::is_finished(self)
+    }
+}
+
+struct PendingCondition<F: Fn(&World) -> bool + Send + Sync + 'static>(F);
+
+impl<F: Fn(&World) -> bool + Send + Sync + 'static> PendingTask for PendingCondition<F> {
+    fn is_finished(&self, world: &World) -> bool {
+        (self.0)(world)
+    }
+}
+
+impl<S: MatchableState> Default for PendingTransition<S> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<S: MatchableState> Debug for PendingTransition<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some((_, target)) => f.debug_tuple("Pending").field(target).finish(),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl<S: MatchableState> PendingTransition<S> {
+    /// Queue a transition to `target` once `task` completes.
+    ///
+    /// Replaces any previously pending transition. The output of the task is discarded,
+    /// so store it elsewhere (or in a resource from within the task) if you need it.
+    pub fn set_when_done<T: Send + 'static>(&mut self, task: Task<T>, target: S) {
+        self.0 = Some((Box::new(task), target));
+    }
+
+    /// Queue a transition to `target` once `condition` holds, checking it every time
+    /// [`apply_state_transition`] runs - like going to a game over screen once the death animation finished.
+    ///
+    /// Replaces any previously pending transition.
+    pub fn set_when(
+        &mut self,
+        target: S,
+        condition: impl Fn(&World) -> bool + Send + Sync + 'static,
+    ) {
+        self.0 = Some((Box::new(PendingCondition(condition)), target));
+    }
+
+    /// Whether a transition is currently waiting on a task or condition
+    pub fn is_pending(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Cancel the pending transition, returning its target if there was one.
+    ///
+    /// The task itself is dropped, which cancels it if it is still running.
+    pub fn cancel(&mut self) -> Option<S> {
+        self.0.take().map(|(_, target)| target)
+    }
+
+    pub(crate) fn take_if_finished(&mut self, world: &World) -> Option<S> {
+        if self
+            .0
+            .as_ref()
+            .is_some_and(|(task, _)| task.is_finished(world))
+        {
+            self.cancel()
+        } else {
+            None
+        }
+    }
+}
+
+/// A target that can be queued into [`NextMatchableState<S>`] any number of times.
+///
+/// Implemented for:
+///
+/// - `S` itself, queueing a clone of the value
+/// - `Fn(S) -> S + Clone`, queueing the function as a setter
+pub trait StateTarget<S: MatchableState, Marker>: Send + Sync + 'static {
+    /// Queue this target in the provided [`NextMatchableState<S>`]
+    fn queue(&self, next_state: &mut NextMatchableState<S>);
+}
+
+impl<S: MatchableState> StateTarget<S, sealed::IsState> for S {
+    fn queue(&self, next_state: &mut NextMatchableState<S>) {
+        next_state.set(self.clone());
+    }
+}
+
+impl<S: MatchableState, F: Fn(S) -> S + Clone + Send + Sync + 'static>
+    StateTarget<S, sealed::IsFn<sealed::IsState, sealed::IsState>> for F
+{
+    fn queue(&self, next_state: &mut NextMatchableState<S>) {
+        next_state.setter(self.clone());
+    }
+}
+
+/// How the initial state of `S` is entered, configured with
+/// [`StateMatchingApp::configure_initial_enter`](crate::StateMatchingApp::configure_initial_enter).
+///
+/// Without this resource, the initial state is entered on the first run of the `StateTransition` schedule.
+#[derive(Resource, Default)]
+pub enum InitialEnter<S: MatchableState> {
+    /// Enter the current state on the first run of the `StateTransition` schedule
+    #[default]
+    Immediate,
+    /// Never run the enter schedules for the initial state
+    Skip,
+    /// Wait until the condition holds, checking it on every run of the `StateTransition` schedule
+    When(Box<dyn Fn(&World) -> bool + Send + Sync>),
+    /// Replace the current state with this one before entering it - after loading a save, for example
+    Restored(S),
+}
+
+impl<S: MatchableState> InitialEnter<S> {
+    /// Wait until `condition` holds before entering the initial state
+    pub fn when(condition: impl Fn(&World) -> bool + Send + Sync + 'static) -> Self {
+        Self::When(Box::new(condition))
+    }
+}
+
+/// Computes the initial state of `S`, registered with
+/// [`StateMatchingApp::init_matchable_state_with`](crate::StateMatchingApp::init_matchable_state_with).
+#[derive(Resource)]
+pub(crate) struct InitialStateFn<S: MatchableState>(
+    pub(crate) Box<dyn FnOnce(&World) -> S + Send + Sync>,
+);
+
+/// A run condition that is true once, as soon as the initial state of `S` should be entered - see [`InitialEnter<S>`]
+pub(crate) fn initial_enter_ready<S: MatchableState>(
+    world: &World,
+    mut entered: Local<bool>,
+) -> bool {
+    if *entered {
+        return false;
+    }
+    *entered = match world.get_resource::<InitialEnter<S>>() {
+        Some(InitialEnter::When(condition)) => condition(world),
+        _ => true,
+    };
+    *entered
 }
 
 /// Run the enter schedule (if it exists) for the current state.
+///
+/// If the initial state is computed by a closure, it is evaluated here first.
+/// The [`InitialEnter<S>`] resource can skip the schedules, or replace the state before entering it.
 pub fn run_enter_schedule<S: MatchableState>(world: &mut World) {
+    if let Some(initial) = world.remove_resource::<InitialStateFn<S>>() {
+        let state = (initial.0)(world);
+        world.insert_resource(State::new(state));
+    }
+    match world.get_resource::<InitialEnter<S>>() {
+        Some(InitialEnter::Skip) => {
+            if let Some(state) = world.get_resource::<State<S>>().map(|s| s.get().clone()) {
+                world.insert_resource(AppliedState(state));
+            }
+            return;
+        }
+        Some(InitialEnter::Restored(state)) => {
+            let state = state.clone();
+            world.insert_resource(State::new(state));
+        }
+        _ => {}
+    }
     let Some(state) = world.get_resource::<State<S>>().map(|s| s.get().clone()) else {
         return;
     };
     world.insert_resource(ActiveTransition::new(Some(state.clone()), None));
-    world.try_run_schedule(OnEnter(state)).ok();
-    world.try_run_schedule(Entering).ok();
+    world.insert_resource(AppliedState(state.clone()));
+    send_transition_event(world, None, state.clone());
+    run_schedule::<S>(world, OnEnter(state.clone()), "OnEnter", &state);
+    run_schedule::<S>(world, Entering, "Entering", &state);
     world.remove_resource::<ActiveTransition<S>>();
 }
 
+/// If a [`PendingTransition<S>`] has completed, its target is queued in [`NextMatchableState<S>`] first.
+///
 /// If a new state is queued in [`NextMatchableState<S>`], this system:
 /// - Takes the new state value from [`NextMatchableState<S>`] and updates [`State<S>`].
 /// - Runs the [`OnExit(exited_state)`] and [`Exiting`] schedules, if they exist.
 /// - Runs the [`OnTransition { from: exited_state, to: entered_state }`](OnTransition) schedule, if they exist.
+///   Its position can be changed with [`MatchableStatePlugin::transition_order`](crate::MatchableStatePlugin::transition_order).
 /// - Runs the [`OnEnter(entered_state)`] and [`Entering`] schedules, if they exist.
+///
+/// If the queued state is the current one, only the [`OnReenter(state)`](OnReenter) schedule runs -
+/// or nothing at all, with [`IdentityTransitions::Ignore`](crate::IdentityTransitions::Ignore).
+///
+/// If a [`TransitionPhase<S>`] resource exists, the exit schedules run as soon as the
+/// transition is queued, while [`State<S>`] is only updated and the remaining schedules
+/// only run once the phase completes.
+///
+/// While a [`TransitionStepping<S>`](crate::TransitionStepping) is enabled, queued transitions are
+/// held until it is stepped.
+///
+/// With a [`TransitionBudget`](crate::TransitionBudget), queued transitions are applied one after the
+/// other until the queue is empty or the budget runs out.
+///
+/// Nothing happens while the state machine of `S` is disabled - see
+/// [`StateMatchingSwitchWorld::set_state_machine_enabled`].
+///
+/// If a previous transition was interrupted by a panic in one of its schedules (and the panic was caught),
+/// it is rolled back to the state it started from first.
 pub fn apply_state_transition<S: MatchableState>(world: &mut World) {
-    let Some(next_state_resource) = world.get_resource::<NextMatchableState<S>>() else {
-        return;
+    let budget = world
+        .get_resource::<StateOptions<S>>()
+        .and_then(|options| options.frame_budget);
+    match budget {
+        Some(budget) => apply_within_budget::<S>(world, budget),
+        None => {
+            apply_next_transition::<S>(world);
+        }
+    }
+}
+
+/// Apply the next transition of `S`, if there is one - returning whether a transition ran
+pub(crate) fn apply_next_transition<S: MatchableState>(world: &mut World) -> bool {
+    let Some(staged) = stage_state_transition::<S>(world) else {
+        return false;
     };
+    finish_state_transition(world, staged);
+    true
+}
+
+/// A transition of `S` whose exit schedules already ran, waiting for [`finish_state_transition`]
+pub(crate) struct StagedTransition<S: MatchableState> {
+    exited: S,
+    entered: S,
+    queued: bool,
+}
+
+impl<S: MatchableState> StagedTransition<S> {
+    pub(crate) fn entered(&self) -> &S {
+        &self.entered
+    }
+}
+
+/// Everything [`apply_state_transition`] does up to and including the exit schedules - returning the
+/// transition if its enter schedules should run now
+pub(crate) fn stage_state_transition<S: MatchableState>(
+    world: &mut World,
+) -> Option<StagedTransition<S>> {
+    repair_interrupted_transition::<S>(world);
+    if transitions_deferred::<S>(world) || !world.is_state_machine_enabled::<S>() {
+        return None;
+    }
+    if let Some(mut reentered) = world.get_resource_mut::<Reentered<S>>() {
+        if reentered.0.is_some() {
+            reentered.0 = None;
+        }
+    }
+    if let Some(target) = world
+        .remove_resource::<PendingTransition<S>>()
+        .and_then(|mut pending| {
+            let target = pending.take_if_finished(world);
+            world.insert_resource(pending);
+            target
+        })
+    {
+        if let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() {
+            next_state.set(target);
+        }
+    }
+    let delta_seconds = world
+        .get_resource::<Time>()
+        .map(|time| time.delta_seconds())
+        .unwrap_or_default();
+    match world
+        .get_resource_mut::<TransitionPhase<S>>()
+        .map(|mut phase| phase.advance(delta_seconds))
+    {
+        None | Some(PhaseStatus::Idle) => {}
+        Some(PhaseStatus::Running) => return None,
+        Some(PhaseStatus::Finished(exited, entered)) => {
+            world.insert_resource(TransitionInProgress(exited.clone()));
+            return Some(StagedTransition {
+                exited,
+                entered,
+                queued: false,
+            });
+        }
+    }
+    if !world.contains_resource::<NextMatchableState<S>>() {
+        report_state_error(
+            world,
+            StateMachineError::MissingNextState(std::any::type_name::<S>()),
+        );
+        return None;
+    }
     let Some(current_state) = world.get_resource::<State<S>>().map(|s| s.get().clone()) else {
-        return;
-    };
-    let entered = match next_state_resource {
-        NextMatchableState::Keep => None,
-        NextMatchableState::Value(v) => Some(v.clone()),
-        NextMatchableState::Setter(f) => Some(f(current_state.clone())),
+        report_state_error(
+            world,
+            StateMachineError::MissingState(std::any::type_name::<S>()),
+        );
+        return None;
     };
+    pop_transition_queue::<S>(world);
+    let options = world
+        .get_resource::<StateOptions<S>>()
+        .copied()
+        .unwrap_or_default();
+    let next_state_resource = world.resource::<NextMatchableState<S>>();
+    let queued = !matches!(next_state_resource, NextMatchableState::Keep);
+    let entered =
+        next_state_resource.resolve(&current_state, world.get_resource::<StateSetters<S>>());
+    if entered.as_ref().is_some_and(|entered| {
+        entered != &current_state && transition_held(world, &current_state, entered)
+    }) {
+        return None;
+    }
+    let source = world
+        .get_resource_mut::<QueuedTransitionSource<S>>()
+        .filter(|_| queued)
+        .and_then(|mut source| source.0.take());
     if let Some(entered) = entered {
-        if current_state != entered {
-            world.insert_resource(ActiveTransition::new(
-                Some(current_state.clone()),
-                Some(entered.clone()),
-            ));
-            // Try to run the schedules if they exist.
-            world.try_run_schedule(OnExit(current_state.clone())).ok();
-            world.try_run_schedule(Exiting).ok();
-            world.resource_mut::<ActiveTransition<S>>().swap();
-            world.insert_resource(State::new(entered.clone()));
-            world
-                .try_run_schedule(OnTransition {
-                    from: current_state,
+        let locked = world
+            .get_resource_mut::<StateLock<S>>()
+            .filter(|_| current_state != entered)
+            .and_then(|mut lock| lock.check(&entered).err());
+        let rejection = locked
+            .or_else(|| {
+                world
+                    .get_resource::<TransitionTable<S>>()
+                    .filter(|_| current_state != entered)
+                    .and_then(|table| table.check(world, &current_state, &entered).err())
+            })
+            .or_else(|| {
+                (current_state != entered)
+                    .then(|| check_cooldowns(world, &current_state, &entered).err())
+                    .flatten()
+            });
+        if let Some(reason) = rejection {
+            if let Some(mut events) = world.get_resource_mut::<Events<TransitionRejected<S>>>() {
+                events.send(TransitionRejected {
+                    from: current_state.clone(),
                     to: entered.clone(),
-                })
-                .ok();
-            world.try_run_schedule(OnEnter(entered)).ok();
-            world.try_run_schedule(Entering).ok();
-            world.remove_resource::<ActiveTransition<S>>();
+                    reason,
+                    source,
+                });
+            }
+        } else if current_state != entered {
+            if let Some(source) = source {
+                debug!(
+                    "{} transitioning from {current_state:?} to {entered:?}, queued by {source}",
+                    std::any::type_name::<S>()
+                );
+            }
+            if options.log_transitions {
+                info!(
+                    "{} transitioning from {current_state:?} to {entered:?}",
+                    std::any::type_name::<S>()
+                );
+            }
+            start_cooldowns(world, &current_state, &entered);
+            insert_queued_payload::<S>(world);
+            world.insert_resource(TransitionInProgress(current_state.clone()));
+            exit_state(world, current_state.clone(), entered.clone());
+            if world.contains_resource::<TransitionPhase<S>>() {
+                world
+                    .resource_mut::<TransitionPhase<S>>()
+                    .start(current_state, entered);
+                world.remove_resource::<ActiveTransition<S>>();
+                world.remove_resource::<TransitionInProgress<S>>();
+            } else {
+                world.remove_resource::<ActiveTransition<S>>();
+                return Some(StagedTransition {
+                    exited: current_state,
+                    entered,
+                    queued,
+                });
+            }
+        } else if options.identity_transitions == IdentityTransitions::Reenter {
+            insert_queued_payload::<S>(world);
+            reenter_state(world, entered);
+            clear_payload::<S>(world);
         }
+    }
+    if queued {
+        world.insert_resource(NextMatchableState::<S>::Keep);
+    }
+    None
+}
 
+/// Run the enter schedules of a transition staged by [`stage_state_transition`]
+pub(crate) fn finish_state_transition<S: MatchableState>(
+    world: &mut World,
+    staged: StagedTransition<S>,
+) {
+    enter_state(world, staged.exited, staged.entered);
+    clear_payload::<S>(world);
+    world.remove_resource::<TransitionInProgress<S>>();
+    if staged.queued {
         world.insert_resource(NextMatchableState::<S>::Keep);
     }
 }
+
+fn transition_order<S: MatchableState>(world: &World) -> TransitionOrder {
+    world
+        .get_resource::<StateOptions<S>>()
+        .map(|options| options.transition_order)
+        .unwrap_or_default()
+}
+
+fn exit_state<S: MatchableState>(world: &mut World, exited: S, entered: S) {
+    world.insert_resource(ActiveTransition::new(
+        Some(exited.clone()),
+        Some(entered.clone()),
+    ));
+    world.insert_resource(IncomingState(entered.clone()));
+    // Try to run the schedules if they exist.
+    if transition_order::<S>(world) == TransitionOrder::BeforeExit {
+        run_on_transition(world, &exited, &entered);
+    }
+    run_schedule::<S>(world, OnExit(exited.clone()), "OnExit", &exited);
+    run_schedule::<S>(world, Exiting, "Exiting", &exited);
+    world.remove_resource::<IncomingState<S>>();
+}
+
+fn enter_state<S: MatchableState>(world: &mut World, exited: S, entered: S) {
+    world.insert_resource(ActiveTransition::new(
+        Some(entered.clone()),
+        Some(exited.clone()),
+    ));
+    world.insert_resource(State::new(entered.clone()));
+    world.insert_resource(AppliedState(entered.clone()));
+    world.insert_resource(LastTransition {
+        from: exited.clone(),
+        to: entered.clone(),
+    });
+    send_transition_event(world, Some(exited.clone()), entered.clone());
+    run_transition_hooks(world, &exited, &entered);
+    let order = transition_order::<S>(world);
+    if order == TransitionOrder::AfterExit {
+        run_on_transition(world, &exited, &entered);
+    }
+    run_schedule::<S>(world, OnEnter(entered.clone()), "OnEnter", &entered);
+    run_schedule::<S>(world, Entering, "Entering", &entered);
+    if order == TransitionOrder::AfterEnter {
+        run_on_transition(world, &exited, &entered);
+    }
+    world.remove_resource::<ActiveTransition<S>>();
+}
+
+fn run_on_transition<S: MatchableState>(world: &mut World, exited: &S, entered: &S) {
+    run_schedule::<S>(
+        world,
+        OnTransition {
+            from: exited.clone(),
+            to: entered.clone(),
+        },
+        "OnTransition",
+        &format_args!("{exited:?} -> {entered:?}"),
+    );
+}
+
+fn send_transition_event<S: MatchableState>(world: &mut World, exited: Option<S>, entered: S) {
+    if let Some(mut events) = world.get_resource_mut::<Events<StateTransitionEvent<S>>>() {
+        events.send(StateTransitionEvent { exited, entered });
+    }
+}
+
+/// Runs the [`OnReenter(state)`](OnReenter) schedule for a transition into the current state
+fn reenter_state<S: MatchableState>(world: &mut World, state: S) {
+    world.insert_resource(ActiveTransition::new(
+        Some(state.clone()),
+        Some(state.clone()),
+    ));
+    world.insert_resource(Reentered(Some(state.clone())));
+    run_schedule::<S>(world, OnReenter(state.clone()), "OnReenter", &state);
+    world.remove_resource::<ActiveTransition<S>>();
+}
+
+/// Runs the schedule if it exists, within a span naming the schedule, the state type and its value -
+/// so traces show exactly which transition ran.
+fn run_schedule<S: MatchableState>(
+    world: &mut World,
+    label: impl ScheduleLabel,
+    schedule: &'static str,
+    value: &dyn Debug,
+) {
+    let _span = bevy::log::info_span!(
+        "state_schedule",
+        schedule,
+        state = std::any::type_name::<S>(),
+        value = ?value
+    )
+    .entered();
+    world.try_run_schedule(label).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::time::Duration;
+
+    use super::ActiveTransition;
+    use crate::{
+        apply_state_transition, for_state, on_timer_in, on_transition, state_exists_and_matches,
+        state_missing, state_reentered, AlreadyQueued, EnterSet, Entering, Exiting, IncomingState,
+        NextMatchableState, OnReenter, PendingTransition, StateMatchingApp,
+    };
+    use bevy::{
+        ecs::schedule::ExecutorKind,
+        prelude::{
+            App, IntoSystemConfigs, OnEnter, OnExit, Res, ResMut, Resource, Schedule, State,
+            States, Time, Update, World,
+        },
+        tasks::{AsyncComputeTaskPool, TaskPool},
+    };
+
+    #[derive(Resource, Default)]
+    struct Counts {
+        entered: u32,
+        reentered: u32,
+        condition: u32,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn state_timers_only_tick_in_matching_states() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (|mut counts: ResMut<Counts>| counts.condition += 1)
+                .run_if(on_timer_in(TestState::B, Duration::from_secs(2))),
+        );
+        world.init_resource::<Counts>();
+        world.init_resource::<Time>();
+        world.insert_resource(State::new(TestState::B));
+
+        let mut step = |world: &mut World, state: TestState| {
+            world.insert_resource(State::new(state));
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(1));
+            schedule.run(world);
+        };
+        step(&mut world, TestState::B);
+        step(&mut world, TestState::A);
+        step(&mut world, TestState::B);
+        assert_eq!(world.resource::<Counts>().condition, 0);
+        step(&mut world, TestState::B);
+        assert_eq!(world.resource::<Counts>().condition, 1);
+    }
+
+    #[derive(Resource)]
+    struct AnimationFinished;
+
+    #[test]
+    fn conditional_transitions_wait_for_their_condition() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>();
+        app.update();
+
+        app.world
+            .resource_mut::<PendingTransition<TestState>>()
+            .set_when(TestState::B, |world| {
+                world.contains_resource::<AnimationFinished>()
+            });
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+
+        app.insert_resource(AnimationFinished);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert!(!app
+            .world
+            .resource::<PendingTransition<TestState>>()
+            .is_pending());
+    }
+
+    #[test]
+    fn task_transitions_wait_for_their_task() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>();
+        app.update();
+        let pool = AsyncComputeTaskPool::get_or_init(TaskPool::new);
+
+        app.world
+            .resource_mut::<PendingTransition<TestState>>()
+            .set_when_done(pool.spawn(std::future::pending::<()>()), TestState::B);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+
+        let task = pool.spawn(async {});
+        while !task.is_finished() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        app.world
+            .resource_mut::<PendingTransition<TestState>>()
+            .set_when_done(task, TestState::B);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+    }
+
+    #[test]
+    fn try_set_refuses_to_overwrite_a_different_transition() {
+        let mut next_state = NextMatchableState::<TestState>::Keep;
+        assert_eq!(next_state.try_set(TestState::B), Ok(()));
+        assert_eq!(next_state.try_set(TestState::B), Ok(()));
+        assert_eq!(
+            next_state.try_set(TestState::A),
+            Err(AlreadyQueued(TestState::A))
+        );
+        assert!(matches!(
+            next_state,
+            NextMatchableState::Value(TestState::B)
+        ));
+
+        next_state.setter(|state| state);
+        assert!(next_state.try_set(TestState::B).is_err());
+    }
+
+    #[test]
+    fn named_setters_are_applied() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .register_state_setter("toggle", |state: TestState| match state {
+                TestState::A => TestState::B,
+                TestState::B => TestState::A,
+            });
+        app.update();
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .named_setter("toggle");
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .named_setter("unknown");
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert!(matches!(
+            app.world.resource::<NextMatchableState<TestState>>(),
+            NextMatchableState::Keep
+        ));
+    }
+
+    #[test]
+    fn queueing_the_current_state_reenters_it() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Counts>()
+            .add_systems(OnEnter(TestState::A), |mut counts: ResMut<Counts>| {
+                counts.entered += 1
+            })
+            .add_systems(OnReenter(TestState::A), |mut counts: ResMut<Counts>| {
+                counts.reentered += 1
+            })
+            .add_systems(
+                Update,
+                (|mut counts: ResMut<Counts>| counts.condition += 1)
+                    .run_if(state_reentered(TestState::A)),
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::A);
+        app.update();
+        app.update();
+
+        let counts = app.world.resource::<Counts>();
+        assert_eq!(
+            (counts.entered, counts.reentered, counts.condition),
+            (1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn optional_state_conditions_handle_missing_states() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((
+            (|mut counts: ResMut<Counts>| counts.entered += 1)
+                .run_if(state_exists_and_matches(TestState::A)),
+            (|mut counts: ResMut<Counts>| counts.reentered += 1)
+                .run_if(state_missing::<TestState>()),
+        ));
+        world.init_resource::<Counts>();
+        schedule.run(&mut world);
+        world.init_resource::<State<TestState>>();
+        schedule.run(&mut world);
+
+        let counts = world.resource::<Counts>();
+        assert_eq!((counts.entered, counts.reentered), (1, 1));
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum OtherState {
+        #[default]
+        X,
+        Y,
+    }
+
+    #[test]
+    fn untyped_schedules_can_be_limited_to_a_state_type() {
+        let mut app = App::new();
+        app.init_resource::<Counts>()
+            .add_matchable_state::<TestState>()
+            .add_matchable_state::<OtherState>()
+            .add_systems(
+                Entering,
+                (|mut counts: ResMut<Counts>| counts.entered += 1).run_if(for_state::<TestState>()),
+            )
+            .add_systems(
+                Exiting,
+                (|mut counts: ResMut<Counts>| counts.condition += 1)
+                    .run_if(for_state::<OtherState>()),
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<OtherState>>()
+            .set(OtherState::Y);
+        app.update();
+
+        let counts = app.world.resource::<Counts>();
+        assert_eq!((counts.entered, counts.condition), (1, 1));
+    }
+
+    #[test]
+    fn transition_conditions_fire_once_after_matching_transitions() {
+        let mut app = App::new();
+        app.init_resource::<Counts>()
+            .add_matchable_state::<TestState>()
+            .add_systems(
+                Update,
+                (|mut counts: ResMut<Counts>| counts.condition += 1)
+                    .run_if(on_transition(TestState::A, TestState::B)),
+            );
+        app.update();
+        for state in [TestState::B, TestState::B, TestState::A] {
+            app.world
+                .resource_mut::<NextMatchableState<TestState>>()
+                .set(state);
+            app.update();
+        }
+
+        let counts = app.world.resource::<Counts>();
+        assert_eq!(counts.condition, 1);
+    }
+
+    #[derive(Resource, Default)]
+    struct Order(Vec<&'static str>);
+
+    #[test]
+    fn enter_sets_run_in_order() {
+        let mut app = App::new();
+        app.init_resource::<Order>()
+            .add_matchable_state::<TestState>()
+            .add_systems(
+                Entering,
+                (
+                    (|mut order: ResMut<Order>| order.0.push("post spawn"))
+                        .in_set(EnterSet::PostSpawn),
+                    (|mut order: ResMut<Order>| order.0.push("spawn")).in_set(EnterSet::Spawn),
+                    (|mut order: ResMut<Order>| order.0.push("cleanup")).in_set(EnterSet::Cleanup),
+                ),
+            );
+        app.update();
+        assert_eq!(
+            app.world.resource::<Order>().0,
+            vec!["cleanup", "spawn", "post spawn"]
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct Incoming(Option<TestState>);
+
+    #[test]
+    fn the_incoming_state_is_visible_while_exiting() {
+        let mut app = App::new();
+        app.init_resource::<Incoming>()
+            .add_matchable_state::<TestState>()
+            .add_systems(
+                OnExit(TestState::A),
+                |incoming: Res<IncomingState<TestState>>, mut seen: ResMut<Incoming>| {
+                    seen.0 = Some(incoming.get().clone())
+                },
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Incoming>().0, Some(TestState::B));
+        assert!(!app.world.contains_resource::<IncomingState<TestState>>());
+    }
+
+    #[test]
+    fn transitions_interrupted_by_a_panic_are_rolled_back() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        let mut on_enter = Schedule::new(OnEnter(TestState::B));
+        on_enter
+            .set_executor_kind(ExecutorKind::SingleThreaded)
+            .add_systems(|| panic!("failed to enter"));
+        world.add_schedule(on_enter);
+
+        world.insert_resource(NextMatchableState::Value(TestState::B));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            apply_state_transition::<TestState>(&mut world)
+        }));
+        assert!(result.is_err());
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::B);
+
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        assert!(!world.contains_resource::<ActiveTransition<TestState>>());
+    }
+}
+
+#[cfg(all(test, feature = "bevy_reflect"))]
+mod reflect_tests {
+    use std::any::TypeId;
+
+    use crate::StateMatchingApp;
+    use bevy::{
+        ecs::reflect::ReflectResource,
+        prelude::{App, AppTypeRegistry, OnExit, Reflect, ResMut, Resource, State, States},
+        reflect::TypePath,
+        scene::DynamicSceneBuilder,
+    };
+
+    #[derive(Resource, Default)]
+    struct Exits(u32);
+
+    #[derive(States, Reflect, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn reflected_edits_trigger_transitions() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Exits>();
+        app.add_systems(OnExit(TestState::A), |mut exits: ResMut<Exits>| {
+            exits.0 += 1
+        });
+        app.update();
+
+        let registry = app.world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let reflect_resource = registry
+            .get_type_data::<ReflectResource>(TypeId::of::<State<TestState>>())
+            .unwrap();
+        reflect_resource
+            .reflect_mut(&mut app.world)
+            .unwrap()
+            .apply(&State::new(TestState::B));
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert_eq!(app.world.resource::<Exits>().0, 1);
+    }
+
+    #[test]
+    fn registered_states_are_extracted_into_scenes() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>();
+        app.world.insert_resource(State::new(TestState::B));
+
+        let scene = DynamicSceneBuilder::from_world(&app.world)
+            .extract_resources()
+            .build();
+        assert!(scene
+            .resources
+            .iter()
+            .any(|resource| resource.reflect_type_path() == State::<TestState>::type_path()));
+    }
+}
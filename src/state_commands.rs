@@ -0,0 +1,176 @@
+use bevy::{
+    ecs::schedule::Schedules,
+    log::warn,
+    prelude::{
+        Event, EventReader, NextState, OnEnter, Res, ResMut, Resource, Schedule, State, World,
+    },
+};
+
+use crate::{MatchableState, NextMatchableState, StateSetters};
+
+/// A request to change the state of `S`, sent as an event - an alternative to writing to
+/// [`NextMatchableState<S>`] that composes better with multiple writers, networking and recording.
+///
+/// Enabled with [`StateMatchingApp::add_state_commands`](crate::StateMatchingApp::add_state_commands).
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum StateCommand<S: MatchableState> {
+    /// Change the state to this value
+    Set(S),
+    /// Change the state using the setter registered under this name in [`StateSetters<S>`]
+    Setter(String),
+    /// Change the state back to its default value
+    Reset,
+}
+
+/// Resolves the [`StateCommand<S>`] events sent since the last run into a single queued transition.
+///
+/// The commands are applied in the order they were sent, each one on top of the result of the
+/// previous ones - starting from the current state. So `Set(a)` followed by `Setter("next")` queues
+/// the state after `a`, and the last `Set` or `Reset` overrides anything sent before it.
+/// If any commands were sent, the result replaces whatever was written to [`NextMatchableState<S>`] directly.
+pub fn collect_state_commands<S: MatchableState>(
+    mut commands: EventReader<StateCommand<S>>,
+    state: Option<Res<State<S>>>,
+    setters: Option<Res<StateSetters<S>>>,
+    mut next_state: ResMut<NextMatchableState<S>>,
+) {
+    let Some(state) = state else {
+        commands.clear();
+        return;
+    };
+    let mut target = None;
+    for command in commands.read() {
+        let current = target.clone().unwrap_or_else(|| state.get().clone());
+        target = match command {
+            StateCommand::Set(value) => Some(value.clone()),
+            StateCommand::Reset => Some(S::default()),
+            StateCommand::Setter(name) => {
+                match setters
+                    .as_ref()
+                    .and_then(|s| s.apply(name, current.clone()))
+                {
+                    Some(value) => Some(value),
+                    None => {
+                        warn!(
+                            "No state setter named {name:?} is registered for {}",
+                            std::any::type_name::<S>()
+                        );
+                        Some(current)
+                    }
+                }
+            }
+        };
+    }
+    if let Some(target) = target {
+        next_state.set(target);
+    }
+}
+
+/// Moves a transition queued in bevy's `NextState<S>` into [`NextMatchableState<S>`], clearing it -
+/// so plugins written against bevy's states drive this crate's transitions instead.
+///
+/// Enabled with [`StateMatchingApp::bridge_next_state`](crate::StateMatchingApp::bridge_next_state).
+pub fn bridge_next_state<S: MatchableState>(
+    bevy_next_state: Option<ResMut<NextState<S>>>,
+    mut next_state: ResMut<NextMatchableState<S>>,
+) {
+    if let Some(state) = bevy_next_state.and_then(|mut next| next.0.take()) {
+        next_state.set(state);
+    }
+}
+
+/// The `OnEnter` schedule of the initial state of `S`, held while bevy's own `run_enter_schedule` runs
+#[derive(Resource)]
+pub(crate) struct HiddenInitialEnter<S: MatchableState>(
+    Schedule,
+    std::marker::PhantomData<fn() -> S>,
+);
+
+/// Hides the `OnEnter` schedule of the initial state from bevy's `run_enter_schedule`, so a state
+/// migrated with [`StateMatchingApp::migrate_bevy_state`](crate::StateMatchingApp::migrate_bevy_state)
+/// is only entered once - by this crate - even if it was also added with `add_state`.
+pub(crate) fn hide_initial_enter_from_bevy<S: MatchableState>(world: &mut World) {
+    let Some(state) = world.get_resource::<State<S>>().map(|s| s.get().clone()) else {
+        return;
+    };
+    if let Some(schedule) = world.resource_mut::<Schedules>().remove(&OnEnter(state)) {
+        world.insert_resource(HiddenInitialEnter::<S>(schedule, Default::default()));
+    }
+}
+
+/// Puts the schedule hidden by [`hide_initial_enter_from_bevy`] back
+pub(crate) fn restore_initial_enter<S: MatchableState>(world: &mut World) {
+    if let Some(hidden) = world.remove_resource::<HiddenInitialEnter<S>>() {
+        world.resource_mut::<Schedules>().insert(hidden.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateCommand, StateMatchingApp};
+    use bevy::prelude::{App, Events, NextState, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    fn send(app: &mut App, commands: impl IntoIterator<Item = StateCommand<TestState>>) {
+        app.world
+            .resource_mut::<Events<StateCommand<TestState>>>()
+            .extend(commands);
+        app.update();
+    }
+
+    #[test]
+    fn commands_are_applied_in_order() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_state_commands::<TestState>()
+            .register_state_setter("next", |state: TestState| match state {
+                TestState::A => TestState::B,
+                _ => TestState::C,
+            });
+        app.update();
+
+        send(
+            &mut app,
+            [
+                StateCommand::Set(TestState::C),
+                StateCommand::Reset,
+                StateCommand::Setter("next".to_string()),
+            ],
+        );
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+
+        send(&mut app, [StateCommand::Setter("next".to_string())]);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::C
+        );
+    }
+
+    #[test]
+    fn bevy_next_state_is_bridged() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .bridge_next_state::<TestState>();
+        app.update();
+
+        app.world
+            .resource_mut::<NextState<TestState>>()
+            .set(TestState::C);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::C
+        );
+        assert_eq!(app.world.resource::<NextState<TestState>>().0, None);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use crate::{MatchableState, StateMatcher, TransitionTable};
+
+/// Renders a [`TransitionTable<S>`] as a Graphviz DOT graph, for design reviews and debugging.
+///
+/// The initial state (`S::default()`) is drawn with a double border, guarded transitions are dashed,
+/// and each named matcher is drawn as a note connected to the states it matches.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+///   Paused,
+/// }
+///
+/// let table = StateMachineBuilder::new()
+///     .transition(AppState::Menu, AppState::InGame)
+///     .transition(AppState::InGame, AppState::Paused)
+///     .transition(AppState::Paused, AppState::InGame)
+///     .build();
+/// let dot = StateGraph::new(&table)
+///     .with_matcher("Playing", |state: &AppState| state != &AppState::Menu)
+///     .to_dot();
+/// assert!(dot.starts_with("digraph \"AppState\" {"));
+/// ```
+pub struct StateGraph<'a, S: MatchableState> {
+    table: &'a TransitionTable<S>,
+    matchers: Vec<(String, Box<dyn Fn(&S) -> bool>)>,
+}
+
+impl<'a, S: MatchableState> StateGraph<'a, S> {
+    /// Create a graph of `table`
+    pub fn new(table: &'a TransitionTable<S>) -> Self {
+        Self {
+            table,
+            matchers: vec![],
+        }
+    }
+
+    /// Include a named matcher in the graph
+    pub fn with_matcher<M: 'static>(
+        mut self,
+        name: impl Into<String>,
+        matcher: impl StateMatcher<S, M>,
+    ) -> Self {
+        self.matchers.push((
+            name.into(),
+            Box::new(move |state: &S| matcher.match_state(state)),
+        ));
+        self
+    }
+
+    /// Render the graph in the DOT language
+    pub fn to_dot(&self) -> String {
+        let states = self.table.states();
+        let initial = S::default();
+        let mut dot = String::new();
+
+        let name = std::any::type_name::<S>();
+        let name = name.rsplit("::").next().unwrap_or(name);
+        writeln!(dot, "digraph {} {{", quote(name)).ok();
+        for (i, state) in states.iter().enumerate() {
+            let shape = if state == &initial {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            writeln!(
+                dot,
+                "    s{i} [label={}, shape={shape}];",
+                quote(&format!("{state:?}"))
+            )
+            .ok();
+        }
+
+        let index = |state: &S| states.iter().position(|s| s == state);
+        for edge in self.table.edges() {
+            let Some(to) = index(&edge.to) else {
+                continue;
+            };
+            let style = if edge.is_guarded() {
+                " [style=dashed]"
+            } else {
+                ""
+            };
+            match &edge.from {
+                Some(from) => {
+                    if let Some(from) = index(from) {
+                        writeln!(dot, "    s{from} -> s{to}{style};").ok();
+                    }
+                }
+                None => {
+                    writeln!(dot, "    any{to} [label=\"*\", shape=point];").ok();
+                    writeln!(dot, "    any{to} -> s{to}{style};").ok();
+                }
+            }
+        }
+
+        for (i, (name, matcher)) in self.matchers.iter().enumerate() {
+            writeln!(dot, "    m{i} [label={}, shape=note];", quote(name)).ok();
+            for (s, state) in states.iter().enumerate() {
+                if matcher(state) {
+                    writeln!(dot, "    m{i} -> s{s} [style=dotted, arrowhead=none];").ok();
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateGraph, StateMachineBuilder};
+    use bevy::prelude::States;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn renders_states_transitions_and_matchers() {
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .guarded_transition(TestState::B, TestState::A, |_| true)
+            .build();
+        let dot = StateGraph::new(&table)
+            .with_matcher("Only B", TestState::B)
+            .to_dot();
+        assert_eq!(
+            dot,
+            "digraph \"TestState\" {
+    s0 [label=\"A\", shape=doublecircle];
+    s1 [label=\"B\", shape=circle];
+    s0 -> s1;
+    s1 -> s0 [style=dashed];
+    m0 [label=\"Only B\", shape=note];
+    m0 -> s1 [style=dotted, arrowhead=none];
+}
+"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::prelude::Resource;
+
+use crate::{MatchableState, NextMatchableState, TransitionRejection};
+
+static NEXT_LOCK_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Proof of holding a [`StateLock<S>`], returned by [`StateLock::acquire`].
+///
+/// It can't be cloned, so only the system that acquired the lock can queue transitions
+/// or release it - usually by keeping it in a `Local`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StateLockToken(u64);
+
+/// Grants ownership of transitions of `S` to a single holder.
+///
+/// While the lock is held, any transition not queued through [`StateLock::set`] is rejected
+/// by [`apply_state_transition`](crate::apply_state_transition), sending a
+/// [`TransitionRejected<S>`](crate::TransitionRejected) event with [`TransitionRejection::Locked`].
+/// Useful to keep gameplay systems from leaving a critical sequence, like saving or a server handshake.
+///
+/// Added by [`StateMatchingApp::add_state_lock`](crate::StateMatchingApp::add_state_lock).
+#[derive(Resource, Debug)]
+pub struct StateLock<S: MatchableState> {
+    holder: Option<u64>,
+    authorized: Option<S>,
+}
+
+impl<S: MatchableState> Default for StateLock<S> {
+    fn default() -> Self {
+        Self {
+            holder: None,
+            authorized: None,
+        }
+    }
+}
+
+impl<S: MatchableState> StateLock<S> {
+    /// Acquire the lock, if no one else holds it
+    pub fn acquire(&mut self) -> Option<StateLockToken> {
+        if self.holder.is_some() {
+            return None;
+        }
+        let id = NEXT_LOCK_ID.fetch_add(1, Ordering::Relaxed);
+        self.holder = Some(id);
+        Some(StateLockToken(id))
+    }
+
+    /// Release the lock. Any transition queued through [`StateLock::set`] is still allowed.
+    pub fn release(&mut self, token: StateLockToken) {
+        if self.holder == Some(token.0) {
+            self.holder = None;
+        }
+    }
+
+    /// Whether the lock is currently held
+    pub fn is_locked(&self) -> bool {
+        self.holder.is_some()
+    }
+
+    /// Whether `token` is the one holding the lock
+    pub fn is_held_by(&self, token: &StateLockToken) -> bool {
+        self.holder == Some(token.0)
+    }
+
+    /// Queue a transition into `state` as the holder of the lock, returning whether it was queued
+    pub fn set(
+        &mut self,
+        token: &StateLockToken,
+        next_state: &mut NextMatchableState<S>,
+        state: S,
+    ) -> bool {
+        if !self.is_held_by(token) {
+            return false;
+        }
+        next_state.set(state.clone());
+        self.authorized = Some(state);
+        true
+    }
+
+    /// Check whether a transition into `entered` may be applied, consuming the authorization
+    pub(crate) fn check(&mut self, entered: &S) -> Result<(), TransitionRejection> {
+        let authorized = self.authorized.take();
+        if self.holder.is_none() || authorized.as_ref() == Some(entered) {
+            Ok(())
+        } else {
+            Err(TransitionRejection::Locked)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        NextMatchableState, StateLock, StateMatchingApp, TransitionRejected, TransitionRejection,
+    };
+    use bevy::prelude::{App, Events, Mut, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[test]
+    fn only_the_holder_can_transition() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_state_lock::<TestState>();
+        app.update();
+
+        let token = app
+            .world
+            .resource_mut::<StateLock<TestState>>()
+            .acquire()
+            .unwrap();
+        assert!(app
+            .world
+            .resource_mut::<StateLock<TestState>>()
+            .acquire()
+            .is_none());
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+        let rejected = app
+            .world
+            .resource::<Events<TransitionRejected<TestState>>>()
+            .iter_current_update_events()
+            .map(|event| event.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec![TransitionRejection::Locked]);
+
+        app.world
+            .resource_scope(|world, mut lock: Mut<StateLock<TestState>>| {
+                let mut next_state = world.resource_mut::<NextMatchableState<TestState>>();
+                assert!(lock.set(&token, &mut next_state, TestState::C));
+            });
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::C
+        );
+
+        app.world
+            .resource_mut::<StateLock<TestState>>()
+            .release(token);
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+    }
+}
@@ -1,14 +1,17 @@
-use super::{ActiveTransition, MatchableState};
+use super::{
+    ActiveTransition, DisabledMatching, DisabledStateMachine, MatchableState, TransitionPhase,
+};
 use bevy::{
     ecs::{
         archetype::ArchetypeComponentId,
         component::{self, ComponentId},
         query::Access,
+        system::{FunctionSystem, SystemParamFunction, SystemParamItem},
         world::unsafe_world_cell::UnsafeWorldCell,
     },
     prelude::*,
 };
-pub use bevy_state_matching_prototype_macros::state_matches;
+pub use bevy_state_matching_prototype_macros::{define_matcher, state_matches};
 use std::{borrow::Cow, marker::PhantomData};
 
 /// An enum describing the possible result of a state transition match.
@@ -34,6 +37,62 @@ impl From<bool> for MatchesStateTransition {
     }
 }
 
+/// Converts to `true` only for `TransitionMatches`, the way transition matchers are evaluated.
+///
+/// Use [`MatchesStateTransition::main_matches`] when matching a single state.
+impl From<MatchesStateTransition> for bool {
+    fn from(value: MatchesStateTransition) -> Self {
+        value == MatchesStateTransition::TransitionMatches
+    }
+}
+
+impl MatchesStateTransition {
+    fn rank(self) -> u8 {
+        match self {
+            MatchesStateTransition::NoMatch => 0,
+            MatchesStateTransition::MainMatches => 1,
+            MatchesStateTransition::TransitionMatches => 2,
+        }
+    }
+
+    /// Whether the main state matches - true for both `TransitionMatches` and `MainMatches`
+    pub fn main_matches(self) -> bool {
+        self != MatchesStateTransition::NoMatch
+    }
+
+    /// The weaker of the two results, ordering them `NoMatch` < `MainMatches` < `TransitionMatches`.
+    ///
+    /// This is how [`AndStateMatchers`] combines its matchers.
+    pub fn and(self, other: Self) -> Self {
+        if self.rank() <= other.rank() {
+            self
+        } else {
+            other
+        }
+    }
+
+    /// The stronger of the two results, ordering them `NoMatch` < `MainMatches` < `TransitionMatches`.
+    pub fn or(self, other: Self) -> Self {
+        if self.rank() >= other.rank() {
+            self
+        } else {
+            other
+        }
+    }
+
+    /// Swaps `TransitionMatches` and `NoMatch`.
+    ///
+    /// `MainMatches` is left as is, since it is neither a match nor a mismatch of the transition -
+    /// like "unknown" in three-valued logic.
+    pub fn invert(self) -> Self {
+        match self {
+            MatchesStateTransition::TransitionMatches => MatchesStateTransition::NoMatch,
+            MatchesStateTransition::MainMatches => MatchesStateTransition::MainMatches,
+            MatchesStateTransition::NoMatch => MatchesStateTransition::TransitionMatches,
+        }
+    }
+}
+
 /// A wrapper around a `StateMatcher` that ignores the state matcher's
 /// `match_state_transition`, and instead always returns a
 /// `TransitionMatches` if the main state matches.
@@ -159,26 +218,73 @@ impl<
         main: Option<&S>,
         secondary: Option<&S>,
     ) -> MatchesStateTransition {
-        let result_1 = self.0.match_state_transition(main, secondary);
-        let result_2 = self.1.match_state_transition(main, secondary);
-        match (result_1, result_2) {
-            (
-                MatchesStateTransition::TransitionMatches,
-                MatchesStateTransition::TransitionMatches,
-            ) => MatchesStateTransition::TransitionMatches,
-            (MatchesStateTransition::TransitionMatches, MatchesStateTransition::MainMatches) => {
-                MatchesStateTransition::MainMatches
-            }
-            (MatchesStateTransition::MainMatches, MatchesStateTransition::TransitionMatches) => {
-                MatchesStateTransition::MainMatches
-            }
-            (MatchesStateTransition::MainMatches, MatchesStateTransition::MainMatches) => {
-                MatchesStateTransition::MainMatches
-            }
-            _ => MatchesStateTransition::NoMatch,
+        self.0
+            .match_state_transition(main, secondary)
+            .and(self.1.match_state_transition(main, secondary))
+    }
+}
+
+/// Helpers called by the code `state_matches!` expands into, so each invocation only
+/// generates a list of calls rather than its own matching logic.
+#[doc(hidden)]
+pub mod macro_support {
+    use super::{sealed::InternalStateMatcher, MatchesStateTransition, StateMatcher};
+    use crate::MatchableState;
+
+    /// Implemented by the types `define_matcher!` generates
+    pub trait DefinedMatcher<S: MatchableState>: Send + Sync + 'static {
+        /// Match a transition, like the closure `state_matches!` expands into
+        fn matches(main: Option<&S>, secondary: Option<&S>) -> MatchesStateTransition;
+    }
+
+    /// Match a transition with any state matcher, or only the main state if `every` is set
+    pub fn matcher<S: MatchableState, M: 'static>(
+        main: &S,
+        secondary: Option<&S>,
+        every: bool,
+        matcher: impl StateMatcher<S, M>,
+    ) -> MatchesStateTransition {
+        if every {
+            matcher.match_state(main).into()
+        } else {
+            matcher.match_state_transition(Some(main), secondary)
+        }
+    }
+
+    /// Match a transition with a pattern, or only the main state if `every` is set
+    pub fn pattern<S: MatchableState>(
+        main: &S,
+        secondary: Option<&S>,
+        every: bool,
+        matches: fn(&S) -> bool,
+    ) -> MatchesStateTransition {
+        if !matches(main) {
+            MatchesStateTransition::NoMatch
+        } else if every || !secondary.is_some_and(matches) {
+            MatchesStateTransition::TransitionMatches
+        } else {
+            MatchesStateTransition::MainMatches
+        }
+    }
+
+    /// Match a `from => to` transition, or only the main state against `to` if `every` is set
+    pub fn transition<S: MatchableState>(
+        main: &S,
+        secondary: Option<&S>,
+        every: bool,
+        from: fn(&S) -> bool,
+        to: fn(&S) -> bool,
+    ) -> MatchesStateTransition {
+        if !to(main) {
+            MatchesStateTransition::NoMatch
+        } else if every || secondary.is_some_and(from) {
+            MatchesStateTransition::TransitionMatches
+        } else {
+            MatchesStateTransition::MainMatches
         }
     }
 }
+
 pub(crate) mod sealed {
     use std::marker::PhantomData;
 
@@ -215,6 +321,18 @@ pub(crate) mod sealed {
     pub struct IsFn<In: Marker, Out: Marker>(PhantomData<(In, Out)>);
     impl<In: Marker, Out: Marker> Marker for IsFn<In, Out> {}
 
+    pub struct IsDynamicMatcher;
+    impl Marker for IsDynamicMatcher {}
+
+    pub struct IsTagMatcher;
+    impl Marker for IsTagMatcher {}
+
+    pub struct IsRegionMatcher<M>(PhantomData<M>);
+    impl<M> Marker for IsRegionMatcher<M> {}
+
+    pub struct IsDefinedMatcher;
+    impl Marker for IsDefinedMatcher {}
+
     pub trait InternalStateMatcher<S: States, Marker>: Send + Sync + Sized + 'static {
         /// Check whether to match with the current state
         fn match_state(&self, state: &S) -> bool;
@@ -308,6 +426,14 @@ pub(crate) trait TransitionStateMatcher<S: MatchableState, Marker: sealed::Marke
     fn match_transition(&self, main: Option<&S>, secondary: Option<&S>) -> MatchesStateTransition;
 }
 
+impl<S: MatchableState, T: macro_support::DefinedMatcher<S>>
+    TransitionStateMatcher<S, sealed::IsDefinedMatcher> for T
+{
+    fn match_transition(&self, main: Option<&S>, secondary: Option<&S>) -> MatchesStateTransition {
+        T::matches(main, secondary)
+    }
+}
+
 impl<S: MatchableState, M: sealed::Marker, Matcher: SingleStateMatcher<S, M>>
     InternalStateMatcher<S, sealed::IsSingleStateMatcher<M>> for Matcher
 {
@@ -525,38 +651,118 @@ impl<S: MatchableState, F: 'static + Send + Sync + Fn(Option<&S>, Option<&S>) ->
     }
 }
 
+#[doc(hidden)]
+pub struct IsMatcherFunction;
+
+/// The body of a [`StateMatcherSystem`], as a nameable system function - so the system
+/// is monomorphized for each matcher rather than boxed, and runs without dynamic dispatch.
+#[doc(hidden)]
+pub struct MatcherFunction<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>>(
+    Sm,
+    Option<bool>,
+    PhantomData<fn() -> (S, M)>,
+);
+
+impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> SystemParamFunction<IsMatcherFunction>
+    for MatcherFunction<S, M, Sm>
+{
+    type In = ();
+    type Out = bool;
+    type Param = (
+        Option<Res<'static, State<S>>>,
+        Option<Res<'static, ActiveTransition<S>>>,
+        Option<Res<'static, DisabledStateMachine<S>>>,
+        Option<Res<'static, TransitionPhase<S>>>,
+    );
+
+    #[inline]
+    fn run(
+        &mut self,
+        _: (),
+        (main, transition, disabled, phase): SystemParamItem<Self::Param>,
+    ) -> bool {
+        evaluate_matcher_tracked(
+            &self.0,
+            main.as_deref(),
+            transition.as_deref(),
+            phase.as_deref(),
+            disabled.as_deref(),
+            &mut self.1,
+        )
+    }
+}
+
+/// Like [`evaluate_matcher`], keeping the results from an enabled state machine in `last`
+/// so they can be returned while matching is [`DisabledMatching::Frozen`]
+pub(crate) fn evaluate_matcher_tracked<S: MatchableState, M>(
+    matcher: &impl StateMatcher<S, M>,
+    main: Option<&State<S>>,
+    transition: Option<&ActiveTransition<S>>,
+    phase: Option<&TransitionPhase<S>>,
+    disabled: Option<&DisabledStateMachine<S>>,
+    last: &mut Option<bool>,
+) -> bool {
+    let matches = evaluate_matcher(matcher, main, transition, phase, disabled, *last);
+    if disabled.is_none() {
+        *last = Some(matches);
+    }
+    matches
+}
+
+/// Evaluate `matcher` against the transition of `S` in progress, or the current state if there isn't one -
+/// the way every [`StateMatcherSystem`] does.
+///
+/// While the state machine is disabled with [`DisabledMatching::Frozen`], `last` is returned if it is known.
+/// Otherwise the matcher is evaluated as usual - transitions are held while disabled, so the result is the same.
+pub(crate) fn evaluate_matcher<S: MatchableState, M>(
+    matcher: &impl StateMatcher<S, M>,
+    main: Option<&State<S>>,
+    transition: Option<&ActiveTransition<S>>,
+    phase: Option<&TransitionPhase<S>>,
+    disabled: Option<&DisabledStateMachine<S>>,
+    last: Option<bool>,
+) -> bool {
+    match (disabled.map(|disabled| disabled.matching), last) {
+        (Some(DisabledMatching::NoMatch), _) => return false,
+        (Some(DisabledMatching::Frozen), Some(last)) => return last,
+        _ => {}
+    }
+    if let Some(transition) = transition {
+        let main = transition.get_main();
+        let secondary = transition.get_secondary();
+
+        main != secondary
+            && matcher.match_state_transition(main, secondary)
+                == MatchesStateTransition::TransitionMatches
+    } else if phase.is_some_and(|phase| phase.active().is_some()) {
+        false
+    } else if let Some(main) = main {
+        matcher.match_state(main.get())
+    } else {
+        false
+    }
+}
+
 impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> From<Sm>
     for StateMatcherSystem<S, M, Sm>
 {
     fn from(value: Sm) -> Self {
-        let system = IntoSystem::into_system(
-            move |main: Option<Res<State<S>>>, transition: Option<Res<ActiveTransition<S>>>| {
-                if let Some(transition) = transition.as_ref().map(|v| v.as_ref()) {
-                    let main = transition.get_main();
-                    let secondary = transition.get_secondary();
-
-                    if main == secondary {
-                        false
-                    } else {
-                        let result = value.match_state_transition(main, secondary);
-                        result == MatchesStateTransition::TransitionMatches
-                    }
-                } else if let Some(main) = main {
-                    value.match_state(main.get())
-                } else {
-                    false
-                }
-            },
-        );
-        Self(Box::new(system), PhantomData)
+        let system = IntoSystem::into_system(MatcherFunction(value, None, PhantomData));
+        let name = format!("state_matches::<{}>", std::any::type_name::<S>());
+        Self(system, name.into())
     }
 }
 
 /// A system type for `StateMatcher`s
 /// Allows them to be used as `Condition`s directly
+///
+/// It only reads `State<S>`, the active transition, [`TransitionPhase<S>`] and [`DisabledStateMachine<S>`],
+/// reports that access to the scheduler,
+/// and is named `state_matches::<S>` - so it shows up clearly in ambiguity reports and traces.
+/// The system is monomorphized for each matcher, so evaluating it costs about as much as `in_state`.
 pub struct StateMatcherSystem<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>>(
-    Box<dyn bevy::prelude::ReadOnlySystem<In = (), Out = bool>>,
-    PhantomData<fn() -> (S, M, Sm)>,
+    FunctionSystem<IsMatcherFunction, MatcherFunction<S, M, Sm>>,
+    Cow<'static, str>,
 );
 
 impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> System
@@ -567,11 +773,11 @@ impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> System
     type Out = bool;
 
     fn name(&self) -> Cow<'static, str> {
-        self.0.name()
+        self.1.clone()
     }
 
     fn type_id(&self) -> std::any::TypeId {
-        self.0.type_id()
+        System::type_id(&self.0)
     }
 
     fn component_access(&self) -> &Access<ComponentId> {
@@ -591,6 +797,7 @@ impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> System
     }
 
     /// # SAFETY: Passing through to `FunctionSystem`'s implementation
+    #[inline]
     unsafe fn run_unsafe(&mut self, input: Self::In, world: UnsafeWorldCell) -> Self::Out {
         self.0.run_unsafe(input, world)
     }
@@ -620,7 +827,7 @@ impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> System
     }
 }
 
-/// # SAFETY: The boxed system is must be a read only system
+/// # SAFETY: The inner system only has read only params
 unsafe impl<S: MatchableState, M: 'static, Sm: StateMatcher<S, M>> ReadOnlySystem
     for StateMatcherSystem<S, M, Sm>
 {
@@ -892,6 +1099,55 @@ mod tests {
         assert!(!match_state_value.match_state(&state_b));
     }
 
+    #[test]
+    fn macro_can_pass_pattern_bindings_to_a_closure() {
+        let match_state_value = state_matches!(TestState, C(flag) => |flag: &bool| *flag);
+        assert!(match_state_value.match_state(&TestState::C(true)));
+        assert!(!match_state_value.match_state(&TestState::C(false)));
+        assert!(!match_state_value.match_state(&TestState::A));
+    }
+
+    define_matcher!(OnlyTrueC, TestState, C(true));
+
+    const ONLY_TRUE_C: OnlyTrueC = OnlyTrueC;
+
+    #[test]
+    fn defined_matchers_are_reusable_types() {
+        assert!(ONLY_TRUE_C.match_state(&TestState::C(true)));
+        assert!(!ONLY_TRUE_C.match_state(&TestState::C(false)));
+        assert_eq!(
+            ONLY_TRUE_C.match_state_transition(Some(&TestState::C(true)), Some(&TestState::A)),
+            MatchesStateTransition::TransitionMatches
+        );
+
+        let mut world = World::new();
+        world.insert_resource(State::new(TestState::C(true)));
+        let mut system: StateMatcherSystem<_, _, _> = ONLY_TRUE_C.into();
+        system.initialize(&mut world);
+        assert!(system.run((), &mut world));
+    }
+
+    #[test]
+    fn macro_can_generate_matcher_for_a_transition_between_patterns() {
+        let match_state_value = state_matches!(TestState, A | B => C(_));
+        assert!(match_state_value.match_state(&TestState::C(true)));
+        assert!(!match_state_value.match_state(&TestState::A));
+        assert_eq!(
+            match_state_value
+                .match_state_transition(Some(&TestState::C(true)), Some(&TestState::B)),
+            MatchesStateTransition::TransitionMatches
+        );
+        assert_eq!(
+            match_state_value
+                .match_state_transition(Some(&TestState::C(true)), Some(&TestState::C(false))),
+            MatchesStateTransition::MainMatches
+        );
+        assert_eq!(
+            match_state_value.match_state_transition(Some(&TestState::A), Some(&TestState::B)),
+            MatchesStateTransition::NoMatch
+        );
+    }
+
     #[test]
     fn macro_can_generate_matcher_for_a_closure() {
         let state_a = State::new(TestState::A);
@@ -909,6 +1165,39 @@ mod tests {
         assert!(!match_state_value.match_state(&state_b));
     }
 
+    #[test]
+    fn transition_results_can_be_combined() {
+        use MatchesStateTransition::*;
+
+        assert_eq!(TransitionMatches.and(MainMatches), MainMatches);
+        assert_eq!(MainMatches.and(NoMatch), NoMatch);
+        assert_eq!(MainMatches.or(TransitionMatches), TransitionMatches);
+        assert_eq!(NoMatch.or(MainMatches), MainMatches);
+        assert_eq!(NoMatch.invert(), TransitionMatches);
+        assert_eq!(MainMatches.invert(), MainMatches);
+        assert!(bool::from(TransitionMatches));
+        assert!(!bool::from(MainMatches));
+        assert!(MainMatches.main_matches());
+    }
+
+    #[test]
+    fn matcher_systems_are_named_and_read_only() {
+        let mut world = World::new();
+        world.insert_resource(State::new(TestState::A));
+        let mut system: StateMatcherSystem<_, _, _> = TestState::A.into();
+        system.initialize(&mut world);
+
+        assert!(system.name().starts_with("state_matches::<"));
+        let state_id = world
+            .components()
+            .resource_id::<State<TestState>>()
+            .unwrap();
+        assert!(system.component_access().has_read(state_id));
+        assert!(!system.component_access().has_write(state_id));
+        fn assert_read_only(_: &impl bevy::ecs::system::ReadOnlySystem) {}
+        assert_read_only(&system);
+    }
+
     #[test]
     fn macro_can_generate_matcher_for_a_simple_transition() {
         let mut world = World::new();
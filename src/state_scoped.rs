@@ -0,0 +1,376 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::{Bundle, Commands, Component, DespawnRecursiveExt, Entity, Query, Res, With},
+};
+
+use crate::{state::ActiveTransition, MatchableState, StateMatcher};
+
+/// Marks an entity as belonging to the states matched by a [`StateMatcher<S>`].
+///
+/// Once [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state)
+/// has been called for `S`, entities with this component are despawned (recursively) during
+/// the [`Exiting`](crate::Exiting) schedule whenever the incoming state no longer matches their scope.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum GameState {
+///   #[default]
+///   Menu,
+///   Playing { paused: bool },
+/// }
+///
+/// fn spawn_level(mut commands: Commands) {
+///     // Survives pausing and unpausing, but is despawned when returning to the menu
+///     commands.spawn((
+///         SpatialBundle::default(),
+///         StateScoped::new(state_matches!(GameState, Playing { .. })),
+///     ));
+///     // Only lives while in the menu
+///     commands.spawn((NodeBundle::default(), StateScoped::new(GameState::Menu)));
+/// }
+/// ```
+#[derive(Component)]
+pub struct StateScoped<S: MatchableState>(Box<dyn Fn(&S) -> bool + Send + Sync>, ScopeMode);
+
+/// What happens to a [`StateScoped<S>`] entity once the state leaves its scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScopeMode {
+    /// Despawn the entity, recursively
+    #[default]
+    Despawn,
+    /// Hide the entity and mark it as [`OutOfScope`], restoring it once the scope is re-entered
+    #[cfg(feature = "render")]
+    Hide,
+}
+
+impl<S: MatchableState> StateScoped<S> {
+    /// Scope an entity to the states matched by `matcher`
+    pub fn new<M: 'static>(matcher: impl StateMatcher<S, M>) -> Self {
+        Self(
+            Box::new(move |state: &S| matcher.match_state(state)),
+            ScopeMode::Despawn,
+        )
+    }
+
+    /// Scope an entity to the states matched by `matcher`, hiding it rather than despawning it
+    /// while out of scope.
+    ///
+    /// Useful for heavy UI or levels in quickly toggled states, like a pause menu.
+    /// Hidden UI nodes aren't interactable, since bevy's focus system skips nodes that aren't visible -
+    /// and with the `ui` feature, their `Interaction` is reset to `None` so they don't stay hovered or pressed.
+    /// Anything else, like gameplay systems or picking backends, has to skip hidden entities itself
+    /// by filtering on `Without<OutOfScope>`.
+    #[cfg(feature = "render")]
+    pub fn hidden<M: 'static>(matcher: impl StateMatcher<S, M>) -> Self {
+        Self(
+            Box::new(move |state: &S| matcher.match_state(state)),
+            ScopeMode::Hide,
+        )
+    }
+
+    /// What happens to the entity once the state leaves its scope
+    pub fn mode(&self) -> ScopeMode {
+        self.1
+    }
+
+    /// Check whether `state` is within this scope
+    pub fn in_scope(&self, state: &S) -> bool {
+        (self.0)(state)
+    }
+}
+
+impl<S: MatchableState> From<S> for StateScoped<S> {
+    fn from(value: S) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Despawns all [`StateScoped<S>`] entities whose scope doesn't match the incoming state.
+///
+/// Runs in the [`Exiting`](crate::Exiting) schedule, so it does nothing unless a transition of `S` is in progress.
+pub fn despawn_out_of_scope<S: MatchableState>(
+    mut commands: Commands,
+    transition: Option<Res<ActiveTransition<S>>>,
+    scoped: Query<(Entity, &StateScoped<S>)>,
+) {
+    let Some(incoming) = transition.as_ref().and_then(|t| t.get_secondary()) else {
+        return;
+    };
+    for (entity, scope) in scoped.iter() {
+        if scope.mode() == ScopeMode::Despawn && !scope.in_scope(incoming) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Marks a [`StateScoped::hidden`] entity that is currently out of scope, holding the visibility
+/// it will be restored to. Only its visibility and UI interaction are affected - filter on
+/// `Without<OutOfScope>` to skip it anywhere else.
+#[cfg(feature = "render")]
+#[derive(Component, Debug)]
+pub struct OutOfScope(pub bevy::render::view::Visibility);
+
+/// Hides all [`StateScoped::hidden`] entities whose scope doesn't match the incoming state,
+/// and restores the ones whose scope matches it again.
+///
+/// Runs in the [`Exiting`](crate::Exiting) schedule, so it does nothing unless a transition of `S` is in progress.
+#[cfg(feature = "render")]
+pub fn hide_out_of_scope<S: MatchableState>(
+    mut commands: Commands,
+    transition: Option<Res<ActiveTransition<S>>>,
+    mut scoped: Query<(
+        Entity,
+        &StateScoped<S>,
+        &mut bevy::render::view::Visibility,
+        Option<&OutOfScope>,
+    )>,
+) {
+    use bevy::render::view::Visibility;
+
+    let Some(incoming) = transition.as_ref().and_then(|t| t.get_secondary()) else {
+        return;
+    };
+    for (entity, scope, mut visibility, out_of_scope) in scoped.iter_mut() {
+        if scope.mode() != ScopeMode::Hide {
+            continue;
+        }
+        match (scope.in_scope(incoming), out_of_scope) {
+            (false, None) => {
+                commands.entity(entity).insert(OutOfScope(*visibility));
+                *visibility = Visibility::Hidden;
+                #[cfg(feature = "ui")]
+                commands.add(move |world: &mut bevy::prelude::World| {
+                    if let Some(mut interaction) = world.get_mut::<bevy::ui::Interaction>(entity) {
+                        *interaction = bevy::ui::Interaction::None;
+                    }
+                });
+            }
+            (true, Some(OutOfScope(previous))) => {
+                *visibility = *previous;
+                commands.entity(entity).remove::<OutOfScope>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Despawns all entities with the component `C` while a transition of `S` is in progress.
+///
+/// Used by [`StateMatchingApp::despawn_on_exit`](crate::StateMatchingApp::despawn_on_exit).
+pub fn despawn_with<S: MatchableState, C: Component>(
+    mut commands: Commands,
+    transition: Option<Res<ActiveTransition<S>>>,
+    entities: Query<Entity, With<C>>,
+) {
+    if transition.is_none() {
+        return;
+    }
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// A trait adding state-scoped spawning to `Commands`
+pub trait StateScopedCommands<'w, 's> {
+    /// Spawn `bundle` along with a [`StateScoped<S>`] built from `matcher`,
+    /// so the entity is despawned once the state leaves its scope.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_state_matching_prototype::*;
+    /// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+    /// # enum GameState {
+    /// #   #[default]
+    /// #   Menu,
+    /// #   Playing { paused: bool },
+    /// # }
+    /// fn spawn_pause_menu(mut commands: Commands) {
+    ///     commands.spawn_in_state(GameState::Playing { paused: true }, NodeBundle::default());
+    /// }
+    /// ```
+    fn spawn_in_state<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        bundle: impl Bundle,
+    ) -> EntityCommands<'w, 's, '_>;
+}
+
+impl<'w, 's> StateScopedCommands<'w, 's> for Commands<'w, 's> {
+    fn spawn_in_state<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        bundle: impl Bundle,
+    ) -> EntityCommands<'w, 's, '_> {
+        self.spawn((bundle, StateScoped::new(matcher)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as bevy_state_matching_prototype;
+    use crate::{
+        despawn_out_of_scope, state::ActiveTransition, state_matches, NextMatchableState,
+        StateMatchingApp, StateScoped, StateScopedCommands,
+    };
+    use bevy::prelude::{App, Commands, Component, OnEnter, Schedule, States, With, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[test]
+    fn entities_are_despawned_when_leaving_their_scope() {
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(despawn_out_of_scope::<TestState>);
+
+        let a = world.spawn(StateScoped::new(TestState::A)).id();
+        let b_or_c = world
+            .spawn(StateScoped::new(state_matches!(TestState, B | C)))
+            .id();
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::A),
+            Some(TestState::B),
+        ));
+        schedule.run(&mut world);
+        assert!(world.get_entity(a).is_none());
+        assert!(world.get_entity(b_or_c).is_some());
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::B),
+            Some(TestState::C),
+        ));
+        schedule.run(&mut world);
+        assert!(world.get_entity(b_or_c).is_some());
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::C),
+            Some(TestState::A),
+        ));
+        schedule.run(&mut world);
+        assert!(world.get_entity(b_or_c).is_none());
+    }
+
+    #[derive(Component)]
+    struct Marker;
+
+    fn set(app: &mut App, state: TestState) {
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(state);
+        app.update();
+    }
+
+    fn markers(app: &mut App) -> usize {
+        app.world
+            .query_filtered::<(), With<Marker>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn entities_spawned_in_a_state_are_despawned_when_leaving_it() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>().add_systems(
+            OnEnter(TestState::B),
+            |mut commands: Commands| {
+                commands.spawn_in_state(state_matches!(TestState, B | C), Marker);
+            },
+        );
+        app.update();
+
+        set(&mut app, TestState::B);
+        assert_eq!(markers(&mut app), 1);
+        set(&mut app, TestState::C);
+        assert_eq!(markers(&mut app), 1);
+        set(&mut app, TestState::A);
+        assert_eq!(markers(&mut app), 0);
+    }
+
+    #[derive(Component)]
+    struct Level;
+
+    #[test]
+    fn marked_entities_are_despawned_when_exiting() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .despawn_on_exit::<TestState, Marker, _>(TestState::A)
+            .despawn_on_exit::<TestState, Level, _>(TestState::B);
+        app.update();
+        app.world.spawn(Marker);
+        let level = app.world.spawn(Level).id();
+
+        set(&mut app, TestState::B);
+        assert_eq!(markers(&mut app), 0);
+        assert!(app.world.get_entity(level).is_some());
+
+        set(&mut app, TestState::C);
+        assert!(app.world.get_entity(level).is_none());
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn hidden_entities_are_restored_when_back_in_scope() {
+        use crate::{hide_out_of_scope, OutOfScope};
+        use bevy::prelude::Visibility;
+
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems((
+            despawn_out_of_scope::<TestState>,
+            hide_out_of_scope::<TestState>,
+        ));
+
+        let a = world
+            .spawn((StateScoped::hidden(TestState::A), Visibility::Visible))
+            .id();
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::A),
+            Some(TestState::B),
+        ));
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(a), Some(&Visibility::Hidden));
+        assert!(world.get::<OutOfScope>(a).is_some());
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::B),
+            Some(TestState::A),
+        ));
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Visibility>(a), Some(&Visibility::Visible));
+        assert!(world.get::<OutOfScope>(a).is_none());
+    }
+
+    #[cfg(all(feature = "render", feature = "ui"))]
+    #[test]
+    fn hidden_ui_nodes_stop_interacting() {
+        use crate::hide_out_of_scope;
+        use bevy::{prelude::Visibility, ui::Interaction};
+
+        let mut world = World::new();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(hide_out_of_scope::<TestState>);
+        let button = world
+            .spawn((
+                StateScoped::hidden(TestState::A),
+                Visibility::Visible,
+                Interaction::Hovered,
+            ))
+            .id();
+
+        world.insert_resource(ActiveTransition::new(
+            Some(TestState::A),
+            Some(TestState::B),
+        ));
+        schedule.run(&mut world);
+        assert_eq!(world.get::<Interaction>(button), Some(&Interaction::None));
+    }
+}
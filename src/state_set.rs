@@ -0,0 +1,112 @@
+use bevy::prelude::{App, IntoSystemConfigs, State, StateTransition, World};
+
+use crate::{
+    apply_state_transition,
+    state::{finish_state_transition, run_enter_schedule, stage_state_transition},
+    MatchableState, RegisterableState, StateMatchingApp,
+};
+
+/// A tuple of state types whose transitions are applied together, in the order they are listed.
+///
+/// Added with [`StateMatchingApp::add_matchable_states`]. When several members have transitions
+/// queued in the same frame, all of their exit schedules run first, then every `State<S>` is updated,
+/// and only then do their enter schedules run - so no `OnEnter` of the batch sees a half-updated set.
+pub trait StateSet: Send + Sync + 'static {
+    /// Register every state in the set, along with [`apply_state_set_transition`]
+    fn add_states(app: &mut App);
+
+    /// Apply the queued transitions of every state in the set - see [`apply_state_set_transition`]
+    fn apply_transitions(world: &mut World);
+}
+
+/// Apply the queued transitions of all the states in `T` as one batch - see [`StateSet`].
+///
+/// Runs right before the [`apply_state_transition`] systems of the members, which then have nothing left to do.
+pub fn apply_state_set_transition<T: StateSet>(world: &mut World) {
+    T::apply_transitions(world);
+}
+
+macro_rules! impl_state_set {
+    ($($s:ident),*) => {
+        impl<$($s: MatchableState + RegisterableState),*> StateSet for ($($s,)*) {
+            fn add_states(app: &mut App) {
+                $(app.add_matchable_state::<$s>();)*
+                app.add_systems(
+                    StateTransition,
+                    apply_state_set_transition::<Self>
+                        $(.after(run_enter_schedule::<$s>).before(apply_state_transition::<$s>))*,
+                );
+            }
+
+            #[allow(non_snake_case)]
+            fn apply_transitions(world: &mut World) {
+                $(let $s = stage_state_transition::<$s>(world);)*
+                $(if let Some(staged) = &$s {
+                    world.insert_resource(State::new(staged.entered().clone()));
+                })*
+                $(if let Some(staged) = $s {
+                    finish_state_transition(world, staged);
+                })*
+            }
+        }
+    };
+}
+
+impl_state_set!(S1, S2);
+impl_state_set!(S1, S2, S3);
+impl_state_set!(S1, S2, S3, S4);
+impl_state_set!(S1, S2, S3, S4, S5);
+impl_state_set!(S1, S2, S3, S4, S5, S6);
+impl_state_set!(S1, S2, S3, S4, S5, S6, S7);
+impl_state_set!(S1, S2, S3, S4, S5, S6, S7, S8);
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{App, OnEnter, Res, ResMut, Resource, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum Mode {
+        #[default]
+        Menu,
+        Game,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum Level {
+        #[default]
+        None,
+        First,
+    }
+
+    #[derive(Resource, Default)]
+    struct SeenLevel(Option<Level>);
+
+    #[test]
+    fn members_are_all_updated_before_entering() {
+        let mut app = App::new();
+        app.init_resource::<SeenLevel>()
+            .add_matchable_states::<(Mode, Level)>()
+            .add_systems(
+                OnEnter(Mode::Game),
+                |level: Res<State<Level>>, mut seen: ResMut<SeenLevel>| {
+                    seen.0 = Some(level.get().clone())
+                },
+            );
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<Mode>>()
+            .set(Mode::Game);
+        app.world
+            .resource_mut::<NextMatchableState<Level>>()
+            .set(Level::First);
+        app.update();
+
+        assert_eq!(app.world.resource::<SeenLevel>().0, Some(Level::First));
+        assert_eq!(app.world.resource::<State<Mode>>().get(), &Mode::Game);
+        assert!(matches!(
+            app.world.resource::<NextMatchableState<Level>>(),
+            NextMatchableState::Keep
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::{Resource, World};
+
+use crate::MatchableState;
+
+/// How the [`StateMatcherSystem`](crate::StateMatcherSystem)s of a disabled state machine behave -
+/// see [`StateMatchingSwitchWorld::disable_state_machine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisabledMatching {
+    /// Nothing matches, so every system gated by a matcher of the state stops running
+    #[default]
+    NoMatch,
+    /// Every matcher keeps the result it had when the state machine was disabled
+    Frozen,
+}
+
+/// Present while the state machine of `S` is disabled with [`StateMatchingSwitchWorld::disable_state_machine`]
+#[derive(Resource, Debug)]
+pub struct DisabledStateMachine<S: MatchableState> {
+    /// How matchers of `S` behave while disabled
+    pub matching: DisabledMatching,
+    marker: PhantomData<fn() -> S>,
+}
+
+/// A trait for suspending state machines on a `World` at runtime - for editor modes, or tests
+/// that drive a subsystem manually
+pub trait StateMatchingSwitchWorld {
+    /// Enable or disable the state machine of `S`. Disabling it behaves like
+    /// [`disable_state_machine`](StateMatchingSwitchWorld::disable_state_machine) with [`DisabledMatching::NoMatch`].
+    fn set_state_machine_enabled<S: MatchableState>(&mut self, enabled: bool);
+
+    /// Suspend the state machine of `S`: [`apply_state_transition`](crate::apply_state_transition)
+    /// leaves queued transitions waiting, and its matchers behave according to `matching`
+    fn disable_state_machine<S: MatchableState>(&mut self, matching: DisabledMatching);
+
+    /// Whether the state machine of `S` is enabled
+    fn is_state_machine_enabled<S: MatchableState>(&self) -> bool;
+}
+
+impl StateMatchingSwitchWorld for World {
+    fn set_state_machine_enabled<S: MatchableState>(&mut self, enabled: bool) {
+        if enabled {
+            self.remove_resource::<DisabledStateMachine<S>>();
+        } else if self.is_state_machine_enabled::<S>() {
+            self.disable_state_machine::<S>(DisabledMatching::default());
+        }
+    }
+
+    fn disable_state_machine<S: MatchableState>(&mut self, matching: DisabledMatching) {
+        self.insert_resource(DisabledStateMachine::<S> {
+            matching,
+            marker: PhantomData,
+        });
+    }
+
+    fn is_state_machine_enabled<S: MatchableState>(&self) -> bool {
+        !self.contains_resource::<DisabledStateMachine<S>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        state_exists_and_matches, DisabledMatching, NextMatchableState, StateMatchingApp,
+        StateMatchingSwitchWorld, StateMatchingSystems,
+    };
+    use bevy::prelude::{App, IntoSystemConfigs, ResMut, Resource, State, States, Update};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    #[test]
+    fn disabled_state_machines_stop_transitioning_and_matching() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .add_systems(
+                Update,
+                (|mut runs: ResMut<Runs>| runs.0 += 1).run_in(TestState::A),
+            );
+        app.update();
+
+        app.world.set_state_machine_enabled::<TestState>(false);
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 1);
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+
+        app.world
+            .disable_state_machine::<TestState>(DisabledMatching::Frozen);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+
+        app.world.set_state_machine_enabled::<TestState>(true);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert_eq!(app.world.resource::<Runs>().0, 2);
+    }
+
+    #[test]
+    fn run_conditions_respect_disabled_state_machines() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .add_systems(
+                Update,
+                (
+                    (|mut runs: ResMut<Runs>| runs.0 += 1)
+                        .run_if(state_exists_and_matches(TestState::A)),
+                    (|mut runs: ResMut<Runs>| runs.0 += 10).run_once_in(TestState::A),
+                ),
+            );
+        app.world
+            .disable_state_machine::<TestState>(DisabledMatching::NoMatch);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 0);
+
+        app.world
+            .disable_state_machine::<TestState>(DisabledMatching::Frozen);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 11);
+
+        app.world.set_state_machine_enabled::<TestState>(true);
+        app.update();
+        assert_eq!(app.world.resource::<Runs>().0, 12);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    state_matching::{sealed, SingleStateMatcher},
+    MatchableState,
+};
+pub use bevy_state_matching_prototype_macros::StateTags;
+
+/// States with semantic tags attached, so many variants can be matched as a group with [`matches_tag`].
+///
+/// Usually derived with [`StateTags`], listing the tags in `#[state_tags(...)]` attributes:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, StateTags, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   #[state_tags(menu, ui_visible)]
+///   MainMenu,
+///   #[state_tags(menu, ui_visible)]
+///   Settings,
+///   #[state_tags(ui_visible)]
+///   InGame { paused: bool },
+/// }
+///
+/// assert!(AppState::Settings.matches(matches_tag("menu")));
+/// assert!(!AppState::InGame { paused: false }.matches(matches_tag("menu")));
+/// ```
+pub trait TaggedState: MatchableState {
+    /// The tags attached to this state
+    fn tags(&self) -> &'static [&'static str];
+
+    /// Whether `tag` is attached to this state
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags().contains(&tag)
+    }
+}
+
+/// A state matcher for states with a given tag attached, created with [`matches_tag`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagMatcher(String);
+
+impl<S: TaggedState> SingleStateMatcher<S, sealed::IsTagMatcher> for TagMatcher {
+    fn match_single_state(&self, state: &S) -> bool {
+        state.has_tag(&self.0)
+    }
+}
+
+/// Match any state with `tag` attached - see [`TaggedState`]
+pub fn matches_tag(tag: impl Into<String>) -> TagMatcher {
+    TagMatcher(tag.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as bevy_state_matching_prototype;
+    use crate::{matches_tag, MatchableState, MatchesStateTransition, StateTags, TaggedState};
+    use bevy::prelude::States;
+
+    #[derive(States, StateTags, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    #[state_tags(any)]
+    enum TestState {
+        #[default]
+        #[state_tags(menu, ui_visible)]
+        MainMenu,
+        #[state_tags(menu)]
+        Settings(u8),
+        InGame {
+            paused: bool,
+        },
+    }
+
+    #[test]
+    fn tags_are_derived_per_variant() {
+        assert_eq!(TestState::MainMenu.tags(), &["any", "menu", "ui_visible"]);
+        assert_eq!(TestState::Settings(1).tags(), &["any", "menu"]);
+        assert_eq!(TestState::InGame { paused: true }.tags(), &["any"]);
+    }
+
+    #[test]
+    fn tag_matchers_match_groups_of_variants() {
+        assert!(TestState::Settings(0).matches(matches_tag("menu")));
+        assert!(!TestState::InGame { paused: false }.matches(matches_tag("menu")));
+        assert_eq!(
+            TestState::matches_transition(
+                matches_tag("menu"),
+                Some(&TestState::Settings(0)),
+                Some(&TestState::MainMenu)
+            ),
+            MatchesStateTransition::MainMatches
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::{Resource, World};
+
+use crate::MatchableState;
+
+/// Single-steps transitions of `S` for debugging.
+///
+/// While enabled, [`apply_state_transition`](crate::apply_state_transition) holds every queued
+/// transition in [`NextMatchableState<S>`](crate::NextMatchableState) instead of applying it, exposing
+/// it through [`TransitionStepping::pending`] until [`TransitionStepping::step`] lets it through.
+///
+/// Added by [`StateMatchingApp::add_transition_stepping`](crate::StateMatchingApp::add_transition_stepping).
+#[derive(Resource, Debug)]
+pub struct TransitionStepping<S: MatchableState> {
+    /// Whether transitions are held - when disabled, they are applied right away
+    pub enabled: bool,
+    pending: Option<(S, S)>,
+    step: bool,
+}
+
+impl<S: MatchableState> Default for TransitionStepping<S> {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pending: None,
+            step: false,
+        }
+    }
+}
+
+impl<S: MatchableState> TransitionStepping<S> {
+    /// The `(from, to)` pair of the transition being held, if any
+    pub fn pending(&self) -> Option<(&S, &S)> {
+        self.pending.as_ref().map(|(from, to)| (from, to))
+    }
+
+    /// Whether the next queued transition would be held
+    pub(crate) fn holds(&self) -> bool {
+        self.enabled && !self.step
+    }
+
+    /// Let the held transition through on the next run of [`apply_state_transition`](crate::apply_state_transition).
+    /// Does nothing if no transition is held.
+    pub fn step(&mut self) {
+        if self.pending.is_some() {
+            self.step = true;
+        }
+    }
+}
+
+/// Whether the transition from `from` to `to` should be held back by the [`TransitionStepping<S>`]
+pub(crate) fn transition_held<S: MatchableState>(world: &mut World, from: &S, to: &S) -> bool {
+    let Some(mut stepping) = world.get_resource_mut::<TransitionStepping<S>>() else {
+        return false;
+    };
+    if !stepping.enabled || std::mem::take(&mut stepping.step) {
+        stepping.pending = None;
+        return false;
+    }
+    stepping.pending = Some((from.clone(), to.clone()));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp, TransitionStepping};
+    use bevy::prelude::{App, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn transitions_are_held_until_stepped() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_transition_stepping::<TestState>();
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+        assert_eq!(
+            app.world
+                .resource::<TransitionStepping<TestState>>()
+                .pending(),
+            Some((&TestState::A, &TestState::B))
+        );
+
+        app.world
+            .resource_mut::<TransitionStepping<TestState>>()
+            .step();
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+        assert_eq!(
+            app.world
+                .resource::<TransitionStepping<TestState>>()
+                .pending(),
+            None
+        );
+    }
+}
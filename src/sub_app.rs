@@ -0,0 +1,93 @@
+use bevy::prelude::{State, World};
+
+use crate::{MatchableState, NextMatchableState};
+
+/// Mirror `State<S>` from the main world into a sub-app's world - call it from the extract function
+/// passed to `SubApp::new`.
+///
+/// If `S` was added to the sub-app with [`StateMatchingApp::add_matchable_state`](crate::StateMatchingApp::add_matchable_state),
+/// a changed state is queued in its [`NextMatchableState<S>`], so the sub-app runs its own exit and enter
+/// schedules once its `StateTransition` schedule runs. Otherwise `State<S>` is overwritten directly,
+/// which is enough for `run_in` conditions.
+///
+/// ```
+/// # use bevy::{app::{AppLabel, SubApp}, prelude::*};
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum GameState {
+///   #[default]
+///   Menu,
+///   Playing,
+/// }
+///
+/// #[derive(AppLabel, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+/// struct SimulationApp;
+///
+/// let mut app = App::new();
+/// app.add_matchable_state::<GameState>();
+///
+/// let mut simulation = App::empty();
+/// simulation.add_matchable_state::<GameState>();
+/// app.insert_sub_app(
+///     SimulationApp,
+///     SubApp::new(simulation, |main_world, simulation| {
+///         sync_state::<GameState>(main_world, &mut simulation.world);
+///     }),
+/// );
+/// ```
+pub fn sync_state<S: MatchableState>(main_world: &World, sub_world: &mut World) {
+    let Some(state) = main_world.get_resource::<State<S>>().map(|s| s.get()) else {
+        sub_world.remove_resource::<State<S>>();
+        return;
+    };
+    if sub_world.get_resource::<State<S>>().map(|s| s.get()) == Some(state) {
+        return;
+    }
+    if sub_world.contains_resource::<State<S>>() {
+        if let Some(mut next_state) = sub_world.get_resource_mut::<NextMatchableState<S>>() {
+            next_state.set(state.clone());
+            return;
+        }
+    }
+    sub_world.insert_resource(State::new(state.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sync_state, NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{App, State, States};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn states_are_mirrored_into_sub_apps() {
+        let mut main = App::new();
+        main.add_matchable_state::<TestState>();
+        main.world.insert_resource(State::new(TestState::B));
+
+        let mut mirror = App::empty();
+        sync_state::<TestState>(&main.world, &mut mirror.world);
+        assert_eq!(
+            mirror.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+
+        let mut registered = App::new();
+        registered.add_matchable_state::<TestState>();
+        sync_state::<TestState>(&main.world, &mut registered.world);
+        assert!(matches!(
+            registered.world.resource::<NextMatchableState<TestState>>(),
+            NextMatchableState::Value(TestState::B)
+        ));
+        registered.update();
+        assert_eq!(
+            registered.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use bevy::prelude::{App, Res, ResMut, Resource, State, World};
+
+use crate::{
+    apply_state_transition, state::ActiveTransition, Entering, Exiting, MatchableState,
+    NextMatchableState, RegisterableState, StateMatcher, StateMatchingApp,
+};
+
+/// Every transition of `S` entered since it was added with [`StateTestApp::add_test_state`],
+/// as `(exited, entered)` pairs - the initial state has no exited state.
+#[derive(Resource, Debug)]
+pub struct TransitionLog<S: MatchableState>(Vec<(Option<S>, S)>);
+
+impl<S: MatchableState> Default for TransitionLog<S> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<S: MatchableState> TransitionLog<S> {
+    /// The transitions entered so far, in order
+    pub fn transitions(&self) -> &[(Option<S>, S)] {
+        &self.0
+    }
+
+    /// Whether a transition from `from` into `to` was entered
+    pub fn contains(&self, from: &S, to: &S) -> bool {
+        self.0
+            .iter()
+            .any(|(exited, entered)| exited.as_ref() == Some(from) && entered == to)
+    }
+}
+
+fn log_transition<S: MatchableState>(
+    transition: Option<Res<ActiveTransition<S>>>,
+    mut log: ResMut<TransitionLog<S>>,
+) {
+    let Some(entered) = transition.as_ref().and_then(|t| t.get_main()) else {
+        return;
+    };
+    let exited = transition.as_ref().and_then(|t| t.get_secondary()).cloned();
+    log.0.push((exited, entered.clone()));
+}
+
+/// Run `f` as if a transition of `S` was in progress, with `main` being the state the matchers
+/// focus on and `secondary` the other side of the transition - the way `apply_state_transition` does.
+///
+/// Neither `State<S>` nor [`NextMatchableState<S>`] are touched, so systems relying on state matchers
+/// can be tested in isolation.
+pub fn with_simulated_transition<S: MatchableState>(
+    world: &mut World,
+    main: Option<S>,
+    secondary: Option<S>,
+    f: impl FnOnce(&mut World),
+) {
+    world.insert_resource(ActiveTransition::new(main, secondary));
+    f(world);
+    world.remove_resource::<ActiveTransition<S>>();
+}
+
+/// Run only the [`Entering`] schedule, as if `to` was entered from `from`. See [`with_simulated_transition`].
+pub fn simulate_entering<S: MatchableState>(world: &mut World, from: Option<S>, to: S) {
+    with_simulated_transition(world, Some(to), from, |world| {
+        world.try_run_schedule(Entering).ok();
+    });
+}
+
+/// Run only the [`Exiting`] schedule, as if `from` was exited into `to`. See [`with_simulated_transition`].
+pub fn simulate_exiting<S: MatchableState>(world: &mut World, from: S, to: Option<S>) {
+    with_simulated_transition(world, Some(from), to, |world| {
+        world.try_run_schedule(Exiting).ok();
+    });
+}
+
+/// A trait with helpers for unit testing state machines on a bevy `App`, without
+/// hand-assembling worlds, resources and schedules.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+/// }
+///
+/// let mut app = App::new();
+/// app.add_test_state::<AppState>();
+/// app.update();
+///
+/// app.queue(AppState::InGame)
+///     .flush::<AppState>()
+///     .assert_in(AppState::InGame)
+///     .assert_transition_ran(AppState::Menu, AppState::InGame);
+/// ```
+pub trait StateTestApp {
+    /// Add `S` as a matchable state, and record its transitions in a [`TransitionLog<S>`]
+    fn add_test_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self;
+
+    /// Queue a transition into `state`
+    fn queue<S: MatchableState>(&mut self, state: S) -> &mut Self;
+
+    /// Apply the transition queued for `S` right away, without running a full update.
+    ///
+    /// Note the initial state is only entered on the first `update`.
+    fn flush<S: MatchableState>(&mut self) -> &mut Self;
+
+    /// Panic unless the current state matches `matcher`
+    fn assert_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+    ) -> &mut Self;
+
+    /// Panic unless a transition from `from` into `to` was entered since the state was added
+    fn assert_transition_ran<S: MatchableState>(&mut self, from: S, to: S) -> &mut Self;
+}
+
+impl StateTestApp for App {
+    fn add_test_state<S: MatchableState + RegisterableState>(&mut self) -> &mut Self {
+        self.add_matchable_state::<S>()
+            .init_resource::<TransitionLog<S>>()
+            .add_systems(Entering, log_transition::<S>)
+    }
+
+    fn queue<S: MatchableState>(&mut self, state: S) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(NextMatchableState::<S>::default)
+            .set(state);
+        self
+    }
+
+    fn flush<S: MatchableState>(&mut self) -> &mut Self {
+        apply_state_transition::<S>(&mut self.world);
+        self
+    }
+
+    fn assert_in<S: MatchableState, M: 'static>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+    ) -> &mut Self {
+        let Some(state) = self.world.get_resource::<State<S>>() else {
+            panic!("{} was never added", std::any::type_name::<S>());
+        };
+        assert!(
+            matcher.match_state(state.get()),
+            "the current state {:?} doesn't match",
+            state.get()
+        );
+        self
+    }
+
+    fn assert_transition_ran<S: MatchableState>(&mut self, from: S, to: S) -> &mut Self {
+        let Some(log) = self.world.get_resource::<TransitionLog<S>>() else {
+            panic!(
+                "transitions of {} aren't recorded - add it with `add_test_state`",
+                std::any::type_name::<S>()
+            );
+        };
+        assert!(
+            log.contains(&from, &to),
+            "no transition from {from:?} to {to:?} ran - the transitions were {:?}",
+            log.transitions()
+        );
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{simulate_entering, Entering, StateMatchingSystems, StateTestApp, TransitionLog};
+    use bevy::prelude::{App, ResMut, Resource, Schedule, State, States, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Entered(u32);
+
+    #[test]
+    fn logs_the_initial_state_and_transitions() {
+        let mut app = App::new();
+        app.add_test_state::<TestState>();
+        app.update();
+        app.queue(TestState::B)
+            .flush::<TestState>()
+            .assert_in(TestState::B)
+            .assert_transition_ran(TestState::A, TestState::B);
+
+        assert_eq!(
+            app.world
+                .resource::<TransitionLog<TestState>>()
+                .transitions(),
+            &[(None, TestState::A), (Some(TestState::A), TestState::B)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn assert_in_panics_on_mismatch() {
+        let mut app = App::new();
+        app.add_test_state::<TestState>();
+        app.update();
+        app.assert_in(TestState::B);
+    }
+
+    #[test]
+    fn simulated_transitions_only_run_the_requested_schedule() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.init_resource::<Entered>();
+        let mut entering = Schedule::new(Entering);
+        entering.add_systems((|mut entered: ResMut<Entered>| entered.0 += 1).run_in(TestState::B));
+        world.add_schedule(entering);
+
+        simulate_entering(&mut world, Some(TestState::A), TestState::B);
+        simulate_entering(&mut world, None, TestState::A);
+        assert_eq!(world.resource::<Entered>().0, 1);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+    }
+}
@@ -0,0 +1,332 @@
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::{schedule::ScheduleLabel, system::Command},
+    log::warn,
+    prelude::{App, Event, Events, PreUpdate, Resource, State, World},
+};
+
+use crate::{
+    cooldowns::check_cooldowns, fixed_timestep::transitions_would_defer, MatchableState,
+    NextMatchableState, PendingTransition, StateLock, StateMatchingSwitchWorld, TransitionPhase,
+    TransitionRejection, TransitionStepping, TransitionTable,
+};
+
+/// A single state change within a [`StateTransaction`]
+trait TransactionEntry: Send + Sync + 'static {
+    /// Check whether the state's transitions are held, and its state lock, transition table and cooldowns,
+    /// without changing anything
+    fn check(&self, world: &World) -> Result<(), StateTransactionRejected>;
+
+    /// Queue the state change in [`NextMatchableState`]
+    fn queue(self: Box<Self>, world: &mut World);
+}
+
+struct SetState<S: MatchableState>(S);
+
+impl<S: MatchableState> TransactionEntry for SetState<S> {
+    fn check(&self, world: &World) -> Result<(), StateTransactionRejected> {
+        let Some(current) = world.get_resource::<State<S>>().map(|state| state.get()) else {
+            return Ok(());
+        };
+        if current == &self.0 {
+            return Ok(());
+        }
+        let held = (!world.is_state_machine_enabled::<S>()
+            || world
+                .get_resource::<TransitionStepping<S>>()
+                .is_some_and(|stepping| stepping.holds())
+            || world
+                .get_resource::<TransitionPhase<S>>()
+                .is_some_and(|phase| phase.active().is_some())
+            || world
+                .get_resource::<PendingTransition<S>>()
+                .is_some_and(|pending| pending.is_pending())
+            || transitions_would_defer::<S>(world))
+        .then_some(TransitionRejection::Held);
+        let rejection = held
+            .or_else(|| {
+                world
+                    .get_resource::<StateLock<S>>()
+                    .is_some_and(|lock| lock.is_locked())
+                    .then_some(TransitionRejection::Locked)
+            })
+            .or_else(|| {
+                world
+                    .get_resource::<TransitionTable<S>>()
+                    .and_then(|table| table.check(world, current, &self.0).err())
+            })
+            .or_else(|| check_cooldowns(world, current, &self.0).err());
+        match rejection {
+            Some(reason) => Err(StateTransactionRejected {
+                state: std::any::type_name::<S>(),
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn queue(self: Box<Self>, world: &mut World) {
+        if let Some(mut next_state) = world.get_resource_mut::<NextMatchableState<S>>() {
+            next_state.set(self.0);
+        }
+    }
+}
+
+/// Transitions of several state types that are applied together, or not at all.
+///
+/// Before anything is queued, each transition is checked for anything holding transitions of its state
+/// back (see [`TransitionRejection::Held`]), and against the [`StateLock`], [`TransitionTable`] and [`TransitionCooldowns`](crate::TransitionCooldowns) of its state. If all of them pass, they are all queued in their
+/// [`NextMatchableState`] and applied in the same `StateTransition` run. Otherwise none are, and a
+/// [`StateTransactionRejected`] event is sent.
+///
+/// Queue it by adding it to `Commands`, once [`StateMatchingTransactionsApp::add_state_transactions`] was called:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum AppState { #[default] Menu, InGame }
+/// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// # enum NetState { #[default] Offline, Connected }
+/// fn join_game(mut commands: Commands) {
+///     commands.add(
+///         StateTransaction::new()
+///             .set(AppState::InGame)
+///             .set(NetState::Connected),
+///     );
+/// }
+/// ```
+#[derive(Default)]
+pub struct StateTransaction {
+    entries: Vec<Box<dyn TransactionEntry>>,
+}
+
+impl StateTransaction {
+    /// An empty transaction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transition `S` into `state` as part of the transaction
+    pub fn set<S: MatchableState>(mut self, state: S) -> Self {
+        self.entries.push(Box::new(SetState(state)));
+        self
+    }
+
+    /// Check every transition, and queue them all if they pass
+    fn apply_to(self, world: &mut World) -> Result<(), StateTransactionRejected> {
+        for entry in self.entries.iter() {
+            entry.check(world)?;
+        }
+        for entry in self.entries {
+            entry.queue(world);
+        }
+        Ok(())
+    }
+}
+
+impl Command for StateTransaction {
+    fn apply(self, world: &mut World) {
+        match world.get_resource_mut::<StateTransactions>() {
+            Some(mut transactions) => transactions.0.push(self),
+            None => warn!(
+                "Can't queue a state transaction - was `add_state_transactions` called on the app?"
+            ),
+        }
+    }
+}
+
+/// Sent when a [`StateTransaction`] is dropped because one of its transitions was rejected
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct StateTransactionRejected {
+    /// The type name of the state whose transition was rejected
+    pub state: &'static str,
+    /// Why it was rejected
+    pub reason: TransitionRejection,
+}
+
+/// The [`StateTransaction`]s waiting to be applied, in the order they were queued
+#[derive(Resource, Default)]
+struct StateTransactions(Vec<StateTransaction>);
+
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+struct ApplyStateTransactions;
+
+fn apply_state_transactions(world: &mut World) {
+    let transactions = std::mem::take(&mut world.resource_mut::<StateTransactions>().0);
+    for transaction in transactions {
+        if let Err(rejected) = transaction.apply_to(world) {
+            world
+                .resource_mut::<Events<StateTransactionRejected>>()
+                .send(rejected);
+        }
+    }
+}
+
+/// A trait adding atomic multi-state transitions to a bevy `App`
+pub trait StateMatchingTransactionsApp {
+    /// Apply the [`StateTransaction`]s added to `Commands` right before the `StateTransition` schedule
+    fn add_state_transactions(&mut self) -> &mut Self;
+}
+
+impl StateMatchingTransactionsApp for App {
+    fn add_state_transactions(&mut self) -> &mut Self {
+        if self.world.contains_resource::<StateTransactions>() {
+            return self;
+        }
+        self.init_resource::<StateTransactions>()
+            .add_event::<StateTransactionRejected>()
+            .add_systems(ApplyStateTransactions, apply_state_transactions);
+        if let Some(mut order) = self.world.get_resource_mut::<MainScheduleOrder>() {
+            order.insert_after(PreUpdate, ApplyStateTransactions);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        DisabledMatching, NextMatchableState, StateMachineBuilder, StateMatchingApp,
+        StateMatchingSwitchWorld, StateMatchingTransactionsApp, StateTransaction,
+        StateTransactionRejected, TransitionRejection,
+    };
+    use bevy::{
+        ecs::system::Command,
+        prelude::{App, Events, Resource, State, States, Time, World},
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum AppState {
+        #[default]
+        Menu,
+        InGame,
+    }
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum NetState {
+        #[default]
+        Offline,
+        Connected,
+    }
+
+    #[derive(Resource)]
+    struct Reachable;
+
+    fn queue(app: &mut App) {
+        StateTransaction::new()
+            .set(AppState::InGame)
+            .set(NetState::Connected)
+            .apply(&mut app.world);
+        app.update();
+    }
+
+    fn states(world: &World) -> (AppState, NetState) {
+        (
+            world.resource::<State<AppState>>().get().clone(),
+            world.resource::<State<NetState>>().get().clone(),
+        )
+    }
+
+    #[test]
+    fn transactions_apply_all_or_nothing() {
+        let mut app = App::new();
+        app.add_matchable_state::<AppState>()
+            .add_matchable_state::<NetState>()
+            .add_state_machine(
+                StateMachineBuilder::new()
+                    .guarded_transition(NetState::Offline, NetState::Connected, |world| {
+                        world.contains_resource::<Reachable>()
+                    })
+                    .build(),
+            )
+            .add_state_transactions();
+        app.update();
+
+        queue(&mut app);
+        assert_eq!(states(&app.world), (AppState::Menu, NetState::Offline));
+        let rejected = app
+            .world
+            .resource_mut::<Events<StateTransactionRejected>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rejected,
+            vec![StateTransactionRejected {
+                state: std::any::type_name::<NetState>(),
+                reason: TransitionRejection::GuardFailed,
+            }]
+        );
+
+        app.world.insert_resource(Reachable);
+        queue(&mut app);
+        assert_eq!(states(&app.world), (AppState::InGame, NetState::Connected));
+    }
+
+    #[test]
+    fn transactions_with_held_states_are_rejected() {
+        let mut app = App::new();
+        app.add_matchable_state::<AppState>()
+            .add_matchable_state::<NetState>()
+            .add_state_transactions();
+        app.update();
+        app.world
+            .disable_state_machine::<NetState>(DisabledMatching::NoMatch);
+
+        queue(&mut app);
+        assert_eq!(states(&app.world), (AppState::Menu, NetState::Offline));
+        let rejected = app
+            .world
+            .resource_mut::<Events<StateTransactionRejected>>()
+            .drain()
+            .map(|rejected| rejected.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec![TransitionRejection::Held]);
+        assert!(matches!(
+            app.world.resource::<NextMatchableState<AppState>>(),
+            NextMatchableState::Keep
+        ));
+    }
+
+    #[test]
+    fn transactions_respect_cooldowns() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_matchable_state::<AppState>()
+            .add_matchable_state::<NetState>()
+            .transition_cooldown(
+                NetState::Offline,
+                NetState::Connected,
+                Duration::from_secs(5),
+            )
+            .add_state_transactions();
+        app.update();
+
+        app.world
+            .resource_mut::<NextMatchableState<NetState>>()
+            .set(NetState::Connected);
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<NetState>>()
+            .set(NetState::Offline);
+        app.update();
+
+        queue(&mut app);
+        assert_eq!(states(&app.world), (AppState::Menu, NetState::Offline));
+        let rejected = app
+            .world
+            .resource_mut::<Events<StateTransactionRejected>>()
+            .drain()
+            .map(|rejected| rejected.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(rejected, vec![TransitionRejection::Cooldown]);
+
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(5));
+        queue(&mut app);
+        assert_eq!(states(&app.world), (AppState::InGame, NetState::Connected));
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::{
+    ecs::system::CommandQueue,
+    prelude::{Commands, Resource, World},
+};
+
+use crate::MatchableState;
+
+type TransitionHook<S> = Box<dyn Fn(&S, &S, &mut Commands) + Send + Sync>;
+
+/// Lightweight callbacks run on every transition of `S`, registered with
+/// [`StateMatchingApp::on_transition`](crate::StateMatchingApp::on_transition)
+#[derive(Resource)]
+pub struct TransitionHooks<S: MatchableState>(Vec<TransitionHook<S>>);
+
+impl<S: MatchableState> Default for TransitionHooks<S> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<S: MatchableState> TransitionHooks<S> {
+    /// Add a hook, to run after the ones already added
+    pub fn add(&mut self, hook: impl Fn(&S, &S, &mut Commands) + Send + Sync + 'static) {
+        self.0.push(Box::new(hook));
+    }
+}
+
+/// Runs the [`TransitionHooks<S>`] in the order they were added, then applies their commands
+pub(crate) fn run_transition_hooks<S: MatchableState>(world: &mut World, exited: &S, entered: &S) {
+    let Some(hooks) = world.remove_resource::<TransitionHooks<S>>() else {
+        return;
+    };
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    for hook in hooks.0.iter() {
+        hook(exited, entered, &mut commands);
+    }
+    queue.apply(world);
+    world.insert_resource(hooks);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateMatchingApp};
+    use bevy::prelude::{App, OnEnter, ResMut, Resource, States, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[derive(Resource, Default)]
+    struct Log(Vec<String>);
+
+    #[test]
+    fn hooks_run_before_the_enter_schedules() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .init_resource::<Log>()
+            .on_transition::<TestState>(|from, to, commands| {
+                let entry = format!("{from:?} -> {to:?}");
+                commands.add(move |world: &mut World| world.resource_mut::<Log>().0.push(entry));
+            })
+            .add_systems(OnEnter(TestState::B), |mut log: ResMut<Log>| {
+                log.0.push("entered B".to_string())
+            });
+        app.update();
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        assert_eq!(app.world.resource::<Log>().0, vec!["A -> B", "entered B"]);
+    }
+}
@@ -0,0 +1,224 @@
+use bevy::prelude::{Res, Resource};
+
+use crate::{MatchableState, StateMatcher};
+
+/// How long a [`TransitionPhase<S>`] lasts before the destination state is entered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransitionDuration {
+    /// The phase lasts for a number of `apply_state_transition` runs - usually frames
+    Frames(u32),
+    /// The phase lasts for a number of seconds, based on the `Time` resource
+    Seconds(f32),
+}
+
+/// The in-progress portion of a [`TransitionPhase<S>`]
+#[derive(Clone, Debug)]
+pub struct ActivePhase<S: MatchableState> {
+    /// The state that was exited
+    pub from: S,
+    /// The state that will be entered once the phase completes
+    pub to: S,
+    elapsed: f32,
+}
+
+/// Enables multi-frame transitions for states of type `S`.
+///
+/// When this resource exists, queued transitions first run the exit schedules,
+/// then wait for the configured [`TransitionDuration`] before updating [`State<S>`](bevy::prelude::State)
+/// and running the transition & enter schedules. Any transitions queued during
+/// the phase are held until it completes.
+///
+/// While the phase runs, no state matcher of `S` matches - the old state was already exited,
+/// even though [`State<S>`](bevy::prelude::State) still holds it. Use [`transitioning`],
+/// [`transitioning_from`] and [`transitioning_into`] to run systems during the phase instead.
+///
+/// Use [`progress`](TransitionPhase::progress) to drive fades, door animations and the like.
+#[derive(Resource, Debug)]
+pub struct TransitionPhase<S: MatchableState> {
+    duration: TransitionDuration,
+    active: Option<ActivePhase<S>>,
+}
+
+pub(crate) enum PhaseStatus<S: MatchableState> {
+    Idle,
+    Running,
+    Finished(S, S),
+}
+
+impl<S: MatchableState> TransitionPhase<S> {
+    /// Create a transition phase lasting for `duration`
+    pub fn new(duration: TransitionDuration) -> Self {
+        Self {
+            duration,
+            active: None,
+        }
+    }
+
+    /// The configured duration of each phase
+    pub fn duration(&self) -> TransitionDuration {
+        self.duration
+    }
+
+    /// The currently running phase, if there is one
+    pub fn active(&self) -> Option<&ActivePhase<S>> {
+        self.active.as_ref()
+    }
+
+    /// The progress of the current phase, between `0.` and `1.`, or `None` if no transition is in progress
+    pub fn progress(&self) -> Option<f32> {
+        let active = self.active.as_ref()?;
+        let total = match self.duration {
+            TransitionDuration::Frames(frames) => frames as f32,
+            TransitionDuration::Seconds(seconds) => seconds,
+        };
+        if total <= 0. {
+            Some(1.)
+        } else {
+            Some((active.elapsed / total).min(1.))
+        }
+    }
+
+    pub(crate) fn start(&mut self, from: S, to: S) {
+        self.active = Some(ActivePhase {
+            from,
+            to,
+            elapsed: 0.,
+        });
+    }
+
+    pub(crate) fn advance(&mut self, delta_seconds: f32) -> PhaseStatus<S> {
+        let Some(active) = self.active.as_mut() else {
+            return PhaseStatus::Idle;
+        };
+        active.elapsed += match self.duration {
+            TransitionDuration::Frames(_) => 1.,
+            TransitionDuration::Seconds(_) => delta_seconds,
+        };
+        if self.progress().unwrap_or(1.) < 1. {
+            return PhaseStatus::Running;
+        }
+        match self.active.take() {
+            Some(ActivePhase { from, to, .. }) => PhaseStatus::Finished(from, to),
+            None => PhaseStatus::Idle,
+        }
+    }
+}
+
+/// A run condition that is true while a [`TransitionPhase<S>`] is in progress
+pub fn transitioning<S: MatchableState>() -> impl FnMut(Option<Res<TransitionPhase<S>>>) -> bool {
+    |phase: Option<Res<TransitionPhase<S>>>| phase.is_some_and(|phase| phase.active.is_some())
+}
+
+/// A run condition that is true while a [`TransitionPhase<S>`] is in progress,
+/// and the destination state matches `matcher`
+pub fn transitioning_into<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+) -> impl FnMut(Option<Res<TransitionPhase<S>>>) -> bool {
+    move |phase: Option<Res<TransitionPhase<S>>>| {
+        phase
+            .as_ref()
+            .and_then(|phase| phase.active.as_ref())
+            .is_some_and(|active| matcher.match_state(&active.to))
+    }
+}
+
+/// A run condition that is true while a [`TransitionPhase<S>`] is in progress,
+/// and the state being left matches `matcher`
+pub fn transitioning_from<S: MatchableState, M: 'static>(
+    matcher: impl StateMatcher<S, M>,
+) -> impl FnMut(Option<Res<TransitionPhase<S>>>) -> bool {
+    move |phase: Option<Res<TransitionPhase<S>>>| {
+        phase
+            .as_ref()
+            .and_then(|phase| phase.active.as_ref())
+            .is_some_and(|active| matcher.match_state(&active.from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        apply_state_transition, transitioning_from, NextMatchableState, StateMatchingApp,
+        StateMatchingSystems, TransitionDuration, TransitionPhase,
+    };
+    use bevy::prelude::{App, IntoSystemConfigs, ResMut, Resource, State, States, Update, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    #[test]
+    fn a_transition_phase_delays_entering_the_new_state() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.insert_resource(NextMatchableState::Value(TestState::B));
+        world.insert_resource(TransitionPhase::<TestState>::new(
+            TransitionDuration::Frames(2),
+        ));
+
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        assert_eq!(
+            world.resource::<TransitionPhase<TestState>>().progress(),
+            Some(0.)
+        );
+
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        assert_eq!(
+            world.resource::<TransitionPhase<TestState>>().progress(),
+            Some(0.5)
+        );
+
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::B);
+        assert_eq!(
+            world.resource::<TransitionPhase<TestState>>().progress(),
+            None
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct Runs {
+        in_a: u32,
+        leaving_a: u32,
+    }
+
+    #[test]
+    fn the_exited_state_no_longer_matches_during_a_phase() {
+        let mut app = App::new();
+        app.init_resource::<Runs>()
+            .add_matchable_state::<TestState>()
+            .add_transition_phase::<TestState>(TransitionDuration::Frames(2))
+            .add_systems(
+                Update,
+                (
+                    (|mut runs: ResMut<Runs>| runs.in_a += 1).run_in(TestState::A),
+                    (|mut runs: ResMut<Runs>| runs.leaving_a += 1)
+                        .run_if(transitioning_from(TestState::A)),
+                ),
+            );
+        app.update();
+
+        app.world
+            .resource_mut::<NextMatchableState<TestState>>()
+            .set(TestState::B);
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::A
+        );
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<TestState>>().get(),
+            &TestState::B
+        );
+
+        let runs = app.world.resource::<Runs>();
+        assert_eq!((runs.in_a, runs.leaving_a), (1, 2));
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fmt::Display, panic::Location};
+
+use bevy::{
+    ecs::system::{SystemName, SystemParam},
+    prelude::{ResMut, Resource},
+};
+
+use crate::{MatchableState, NextMatchableState};
+
+/// Where a transition was queued from, recorded by [`TrackedNextState`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionSource {
+    /// The name of the system that queued the transition
+    pub system: String,
+    /// The source location the transition was queued at
+    pub location: &'static Location<'static>,
+    /// An optional tag, provided with [`TrackedNextState::set_tagged`]
+    pub tag: Option<String>,
+}
+
+impl Display for TransitionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.system, self.location)?;
+        if let Some(tag) = &self.tag {
+            write!(f, " ({tag})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The source of the transition currently queued in [`NextMatchableState<S>`], if it
+/// was queued through a [`TrackedNextState<S>`].
+///
+/// Cleared by [`apply_state_transition`](crate::apply_state_transition) once the transition
+/// is applied or rejected.
+#[derive(Resource, Debug)]
+pub struct QueuedTransitionSource<S: MatchableState>(
+    pub(crate) Option<TransitionSource>,
+    std::marker::PhantomData<fn() -> S>,
+);
+
+impl<S: MatchableState> Default for QueuedTransitionSource<S> {
+    fn default() -> Self {
+        Self(None, std::marker::PhantomData)
+    }
+}
+
+impl<S: MatchableState> QueuedTransitionSource<S> {
+    /// The source of the queued transition
+    pub fn get(&self) -> Option<&TransitionSource> {
+        self.0.as_ref()
+    }
+}
+
+/// A system param for queueing transitions of `S` while recording which system queued them.
+///
+/// The source is logged when the transition is applied, and included in
+/// [`TransitionRejected<S>`](crate::TransitionRejected) events. Transitions queued by writing
+/// to [`NextMatchableState<S>`] directly have no source.
+#[derive(SystemParam)]
+pub struct TrackedNextState<'w, 's, S: MatchableState> {
+    next_state: ResMut<'w, NextMatchableState<S>>,
+    source: ResMut<'w, QueuedTransitionSource<S>>,
+    system: SystemName<'s>,
+}
+
+impl<'w, 's, S: MatchableState> TrackedNextState<'w, 's, S> {
+    #[track_caller]
+    fn record(&mut self, tag: Option<String>) {
+        self.source.0 = Some(TransitionSource {
+            system: self.system.name().to_string(),
+            location: Location::caller(),
+            tag,
+        });
+    }
+
+    /// Queue a transition into `state`
+    #[track_caller]
+    pub fn set(&mut self, state: S) {
+        self.record(None);
+        self.next_state.set(state);
+    }
+
+    /// Queue a transition into `state`, tagging it with `tag` - like `"pause_menu"`
+    #[track_caller]
+    pub fn set_tagged(&mut self, state: S, tag: impl Into<String>) {
+        self.record(Some(tag.into()));
+        self.next_state.set(state);
+    }
+
+    /// Queue a transition into the value returned by `setter`
+    #[track_caller]
+    pub fn setter(&mut self, setter: impl Fn(S) -> S + 'static + Sync + Send) {
+        self.record(None);
+        self.next_state.setter(setter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        StateLock, StateMatchingApp, TrackedNextState, TransitionRejected, TransitionSource,
+    };
+    use bevy::prelude::{App, Events, States, Update};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+    }
+
+    fn open_menu(mut next_state: TrackedNextState<TestState>) {
+        next_state.set_tagged(TestState::B, "menu");
+    }
+
+    #[test]
+    fn rejections_include_the_source() {
+        let mut app = App::new();
+        app.add_matchable_state::<TestState>()
+            .add_state_lock::<TestState>()
+            .add_systems(Update, open_menu);
+        app.update();
+        let _token = app.world.resource_mut::<StateLock<TestState>>().acquire();
+        app.update();
+
+        let sources = app
+            .world
+            .resource::<Events<TransitionRejected<TestState>>>()
+            .iter_current_update_events()
+            .filter_map(|event| event.source.clone())
+            .collect::<Vec<TransitionSource>>();
+        assert_eq!(sources.len(), 1);
+        assert!(sources[0].system.ends_with("open_menu"));
+        assert_eq!(sources[0].tag.as_deref(), Some("menu"));
+    }
+}
@@ -0,0 +1,496 @@
+use bevy::{
+    log::warn,
+    prelude::{Event, Local, Resource, World},
+};
+
+use crate::{state::AppliedState, MatchableState, TransitionSource};
+pub use bevy_state_matching_prototype_macros::StateTransitions;
+
+type Guard = Box<dyn Fn(&World) -> bool + Send + Sync>;
+
+/// States with their allowed transitions declared next to the type, so they can be turned into a
+/// [`TransitionTable<S>`] with [`StateMachineBuilder::declared`].
+///
+/// Usually derived with [`StateTransitions`], listing the edges in `#[states(transitions(...))]` attributes:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, StateTransitions, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// #[states(transitions(Menu -> InGame, InGame -> Menu, * -> Error))]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+///   Error,
+/// }
+///
+/// App::new()
+///     .add_matchable_state::<AppState>()
+///     .add_state_machine(StateMachineBuilder::<AppState>::declared());
+/// ```
+pub trait DeclaredTransitions: MatchableState {
+    /// The allowed transitions, as `(from, to)` pairs - where `from` is `None` if the transition
+    /// can start from any state
+    fn declared_transitions() -> Vec<(Option<Self>, Self)>;
+}
+
+/// A structural problem found by [`TransitionTable::validate`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransitionGraphIssue<S: MatchableState> {
+    /// The state can't be reached from the initial state
+    Unreachable(S),
+    /// The state has no transitions leading out of it
+    DeadEnd(S),
+    /// Transitions lead into the state, but it isn't one of the states declared with
+    /// [`StateMachineBuilder::queued`] - so no system ever queues them
+    NeverQueued(S),
+}
+
+/// An allowed transition within a [`TransitionTable<S>`]
+pub struct TransitionEdge<S: MatchableState> {
+    /// The state the transition starts from, or `None` if it can start from any state
+    pub from: Option<S>,
+    /// The state the transition leads to
+    pub to: S,
+    guard: Option<Guard>,
+}
+
+impl<S: MatchableState> TransitionEdge<S> {
+    /// Whether this edge has a guard
+    pub fn is_guarded(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    fn connects(&self, from: &S, to: &S) -> bool {
+        self.from.as_ref().map_or(true, |f| f == from) && &self.to == to
+    }
+}
+
+/// Why a transition was rejected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransitionRejection {
+    /// The transition is not in the [`TransitionTable<S>`]
+    NotAllowed,
+    /// The transition is in the [`TransitionTable<S>`], but all of its guards failed
+    GuardFailed,
+    /// A [`StateLock<S>`](crate::StateLock) is held, and the transition wasn't queued by its holder
+    Locked,
+    /// A [`TransitionCooldowns<S>`](crate::TransitionCooldowns) covering the transition is still running
+    Cooldown,
+    /// The transition can't be applied right away - the state machine is disabled or being stepped through,
+    /// a transition phase or [`PendingTransition<S>`](crate::PendingTransition) is in progress, or the
+    /// fixed timestep loop is deferring it. Only reported for [`StateTransaction`](crate::StateTransaction)s.
+    Held,
+}
+
+/// Sent whenever a queued transition is rejected rather than applied
+#[derive(Event, Clone, Debug)]
+pub struct TransitionRejected<S: MatchableState> {
+    /// The current state, which remains unchanged
+    pub from: S,
+    /// The state that was queued
+    pub to: S,
+    /// Why the transition was rejected
+    pub reason: TransitionRejection,
+    /// Where the transition was queued from, if it was queued through a [`TrackedNextState<S>`](crate::TrackedNextState)
+    pub source: Option<TransitionSource>,
+}
+
+/// The declared states and allowed transitions of a state machine of type `S`.
+///
+/// When this resource exists, [`apply_state_transition`](crate::apply_state_transition)
+/// only applies transitions that are part of the table (and whose guards pass),
+/// rejecting any others with a [`TransitionRejected<S>`] event.
+///
+/// Built using a [`StateMachineBuilder<S>`], and registered with
+/// [`StateMatchingApp::add_state_machine`](crate::StateMatchingApp::add_state_machine).
+#[derive(Resource)]
+pub struct TransitionTable<S: MatchableState> {
+    states: Vec<S>,
+    edges: Vec<TransitionEdge<S>>,
+    queued: Option<Vec<S>>,
+}
+
+impl<S: MatchableState> TransitionTable<S> {
+    /// The declared states
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// The allowed transitions
+    pub fn edges(&self) -> &[TransitionEdge<S>] {
+        &self.edges
+    }
+
+    /// Check whether the transition from `from` to `to` is allowed, returning the reason if it isn't
+    pub fn check(&self, world: &World, from: &S, to: &S) -> Result<(), TransitionRejection> {
+        let edges = self.edges.iter().filter(|edge| edge.connects(from, to));
+        let mut result = Err(TransitionRejection::NotAllowed);
+        for edge in edges {
+            match &edge.guard {
+                Some(guard) if !guard(world) => result = Err(TransitionRejection::GuardFailed),
+                _ => return Ok(()),
+            }
+        }
+        result
+    }
+
+    /// Check the graph for states that can't be reached from `initial`, and states with no way out.
+    /// If the states systems queue were declared with [`StateMachineBuilder::queued`], transitions
+    /// into any other state are reported too.
+    ///
+    /// Guards are assumed to pass, since they can only be evaluated at runtime.
+    pub fn validate(&self, initial: &S) -> Vec<TransitionGraphIssue<S>> {
+        let mut reachable = vec![initial.clone()];
+        let mut index = 0;
+        while let Some(from) = reachable.get(index).cloned() {
+            for edge in self.edges.iter() {
+                if edge.from.as_ref().map_or(true, |f| f == &from) && !reachable.contains(&edge.to)
+                {
+                    reachable.push(edge.to.clone());
+                }
+            }
+            index += 1;
+        }
+
+        let mut issues = vec![];
+        for state in self.states.iter() {
+            if !reachable.contains(state) {
+                issues.push(TransitionGraphIssue::Unreachable(state.clone()));
+            }
+            let has_exit = self
+                .edges
+                .iter()
+                .any(|edge| edge.from.as_ref().map_or(true, |f| f == state) && &edge.to != state);
+            if !has_exit {
+                issues.push(TransitionGraphIssue::DeadEnd(state.clone()));
+            }
+            let never_queued = self.queued.as_ref().is_some_and(|queued| {
+                !queued.contains(state) && self.edges.iter().any(|edge| &edge.to == state)
+            });
+            if never_queued {
+                issues.push(TransitionGraphIssue::NeverQueued(state.clone()));
+            }
+        }
+        issues
+    }
+}
+
+/// Logs a warning for every issue [`TransitionTable::validate`] finds, starting from the state that was
+/// actually entered first - so computed, restored and overridden initial states are taken into account.
+///
+/// Added to `StateTransition` by [`StateMatchingApp::add_state_machine`](crate::StateMatchingApp::add_state_machine),
+/// and only validates once, as soon as the initial state has been entered.
+pub fn validate_transition_table<S: MatchableState>(world: &World, mut validated: Local<bool>) {
+    let (Some(table), Some(initial)) = (
+        world.get_resource::<TransitionTable<S>>(),
+        world.get_resource::<AppliedState<S>>(),
+    ) else {
+        return;
+    };
+    if std::mem::replace(&mut *validated, true) {
+        return;
+    }
+    let initial = &initial.0;
+    for issue in table.validate(initial) {
+        match issue {
+            TransitionGraphIssue::Unreachable(state) => warn!(
+                "{state:?} can never be reached from {initial:?} in the transition table for {}",
+                std::any::type_name::<S>()
+            ),
+            TransitionGraphIssue::DeadEnd(state) => warn!(
+                "{state:?} has no transitions out of it in the transition table for {}",
+                std::any::type_name::<S>()
+            ),
+            TransitionGraphIssue::NeverQueued(state) => warn!(
+                "{state:?} has transitions into it in the transition table for {}, but no system queues it",
+                std::any::type_name::<S>()
+            ),
+        }
+    }
+}
+
+/// A builder for a [`TransitionTable<S>`]
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_state_matching_prototype::*;
+/// #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+/// enum AppState {
+///   #[default]
+///   Menu,
+///   InGame,
+///   GameOver,
+///   Error,
+/// }
+///
+/// #[derive(Resource)]
+/// struct Lives(u32);
+///
+/// App::new().add_matchable_state::<AppState>().add_state_machine(
+///     StateMachineBuilder::new()
+///         .transition(AppState::Menu, AppState::InGame)
+///         .guarded_transition(AppState::InGame, AppState::GameOver, |world| {
+///             world.get_resource::<Lives>().is_some_and(|lives| lives.0 == 0)
+///         })
+///         .transition(AppState::GameOver, AppState::Menu)
+///         .transition_from_any(AppState::Error),
+/// );
+/// ```
+pub struct StateMachineBuilder<S: MatchableState> {
+    table: TransitionTable<S>,
+}
+
+impl<S: MatchableState> Default for StateMachineBuilder<S> {
+    fn default() -> Self {
+        Self {
+            table: TransitionTable {
+                states: vec![],
+                edges: vec![],
+                queued: None,
+            },
+        }
+    }
+}
+
+impl<S: MatchableState> StateMachineBuilder<S> {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder with the transitions declared by `S`, usually with the [`StateTransitions`] derive
+    pub fn declared() -> Self
+    where
+        S: DeclaredTransitions,
+    {
+        S::declared_transitions()
+            .into_iter()
+            .fold(Self::new(), |builder, (from, to)| {
+                builder.add_edge(from, to, None)
+            })
+    }
+
+    /// Declare a state. States used in transitions are declared automatically.
+    pub fn state(mut self, state: S) -> Self {
+        if !self.table.states.contains(&state) {
+            self.table.states.push(state);
+        }
+        self
+    }
+
+    /// Allow transitioning from `from` to `to`
+    pub fn transition(self, from: S, to: S) -> Self {
+        self.add_edge(Some(from), to, None)
+    }
+
+    /// Allow transitioning from `from` to `to`, but only if `guard` returns true
+    pub fn guarded_transition(
+        self,
+        from: S,
+        to: S,
+        guard: impl Fn(&World) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.add_edge(Some(from), to, Some(Box::new(guard)))
+    }
+
+    /// Allow transitioning from any state to `to`
+    pub fn transition_from_any(self, to: S) -> Self {
+        self.add_edge(None, to, None)
+    }
+
+    /// Allow transitioning from any state to `to`, but only if `guard` returns true
+    pub fn guarded_transition_from_any(
+        self,
+        to: S,
+        guard: impl Fn(&World) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.add_edge(None, to, Some(Box::new(guard)))
+    }
+
+    /// Only allow the transition from `from` to `to` if `guard` returns true - applied to the
+    /// edges already added between them, like the ones from [`declared`](StateMachineBuilder::declared).
+    /// Edges from any state aren't affected.
+    pub fn guard(
+        mut self,
+        from: S,
+        to: S,
+        guard: impl Fn(&World) -> bool + Send + Sync + Clone + 'static,
+    ) -> Self {
+        for edge in self.table.edges.iter_mut() {
+            if edge.from.as_ref() == Some(&from) && edge.to == to {
+                edge.guard = Some(Box::new(guard.clone()));
+            }
+        }
+        self
+    }
+
+    /// Declare that systems queue transitions into `states`, so validation can warn about
+    /// transitions into states nothing queues. Without any declared states, that check is skipped.
+    pub fn queued(mut self, states: impl IntoIterator<Item = S>) -> Self {
+        self.table
+            .queued
+            .get_or_insert_with(Vec::new)
+            .extend(states);
+        self
+    }
+
+    fn add_edge(mut self, from: Option<S>, to: S, guard: Option<Guard>) -> Self {
+        if let Some(from) = &from {
+            self = self.state(from.clone());
+        }
+        self = self.state(to.clone());
+        self.table.edges.push(TransitionEdge { from, to, guard });
+        self
+    }
+
+    /// Build the transition table
+    pub fn build(self) -> TransitionTable<S> {
+        self.table
+    }
+}
+
+impl<S: MatchableState> From<StateMachineBuilder<S>> for TransitionTable<S> {
+    fn from(value: StateMachineBuilder<S>) -> Self {
+        value.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate as bevy_state_matching_prototype;
+    use crate::{
+        apply_state_transition, NextMatchableState, StateMachineBuilder, StateTransitions,
+        TransitionGraphIssue, TransitionRejected, TransitionRejection,
+    };
+    use bevy::prelude::{Events, State, States, World};
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[test]
+    fn transitions_outside_the_table_are_rejected() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.init_resource::<Events<TransitionRejected<TestState>>>();
+        world.insert_resource(
+            StateMachineBuilder::new()
+                .transition(TestState::A, TestState::B)
+                .transition(TestState::B, TestState::C)
+                .build(),
+        );
+
+        world.insert_resource(NextMatchableState::Value(TestState::C));
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        let rejected = world
+            .resource_mut::<Events<TransitionRejected<TestState>>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].reason, TransitionRejection::NotAllowed);
+
+        world.insert_resource(NextMatchableState::Value(TestState::B));
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::B);
+    }
+
+    #[test]
+    fn guarded_transitions_are_rejected_when_the_guard_fails() {
+        let mut world = World::new();
+        world.init_resource::<State<TestState>>();
+        world.init_resource::<Events<TransitionRejected<TestState>>>();
+        world.insert_resource(
+            StateMachineBuilder::new()
+                .guarded_transition(TestState::A, TestState::B, |_| false)
+                .build(),
+        );
+
+        world.insert_resource(NextMatchableState::Value(TestState::B));
+        apply_state_transition::<TestState>(&mut world);
+        assert_eq!(world.resource::<State<TestState>>().get(), &TestState::A);
+        let rejected = world
+            .resource_mut::<Events<TransitionRejected<TestState>>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(rejected[0].reason, TransitionRejection::GuardFailed);
+    }
+
+    #[derive(States, StateTransitions, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    #[states(transitions(Menu -> InGame { paused: false }, InGame { paused: false } -> Menu, * -> Error))]
+    enum DeclaredState {
+        #[default]
+        Menu,
+        InGame {
+            paused: bool,
+        },
+        Error,
+    }
+
+    #[test]
+    fn declared_transitions_build_a_table() {
+        let table = StateMachineBuilder::<DeclaredState>::declared()
+            .guard(
+                DeclaredState::InGame { paused: false },
+                DeclaredState::Menu,
+                |_| false,
+            )
+            .build();
+        let world = World::new();
+        let in_game = DeclaredState::InGame { paused: false };
+        assert_eq!(table.check(&world, &DeclaredState::Menu, &in_game), Ok(()));
+        assert_eq!(
+            table.check(&world, &in_game, &DeclaredState::Menu),
+            Err(TransitionRejection::GuardFailed)
+        );
+        assert_eq!(table.check(&world, &in_game, &DeclaredState::Error), Ok(()));
+        assert_eq!(
+            table.check(&world, &DeclaredState::Error, &DeclaredState::Menu),
+            Err(TransitionRejection::NotAllowed)
+        );
+        assert_eq!(
+            table.validate(&DeclaredState::Menu),
+            vec![TransitionGraphIssue::DeadEnd(DeclaredState::Error)]
+        );
+    }
+
+    #[test]
+    fn validation_finds_unreachable_and_dead_end_states() {
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .transition(TestState::B, TestState::A)
+            .transition(TestState::C, TestState::A)
+            .build();
+        assert_eq!(
+            table.validate(&TestState::A),
+            vec![TransitionGraphIssue::Unreachable(TestState::C)]
+        );
+
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .build();
+        assert_eq!(
+            table.validate(&TestState::A),
+            vec![TransitionGraphIssue::DeadEnd(TestState::B)]
+        );
+    }
+    #[test]
+    fn validation_finds_states_no_system_queues() {
+        let table = StateMachineBuilder::new()
+            .transition(TestState::A, TestState::B)
+            .transition(TestState::B, TestState::C)
+            .transition(TestState::C, TestState::A)
+            .queued([TestState::B, TestState::A])
+            .build();
+        assert_eq!(
+            table.validate(&TestState::A),
+            vec![TransitionGraphIssue::NeverQueued(TestState::C)]
+        );
+    }
+}
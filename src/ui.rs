@@ -0,0 +1,134 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::{App, Changed, Component, IntoSystemConfigs, PreUpdate, Query, ResMut},
+    ui::{Interaction, UiSystem},
+};
+
+use crate::{MatchableState, NextMatchableState, StateMatcher, StateMatchingSystems};
+
+/// Queues a transition to its state when the `Interaction` of the entity becomes `Pressed` -
+/// usually inserted with [`StateMatchingEntityCommands::on_click_set_state`]
+#[derive(Component, Debug, Clone)]
+pub struct SetStateOnClick<S: MatchableState>(pub S);
+
+/// Queue the [`SetStateOnClick<S>`] of every entity that was just pressed
+pub(crate) fn set_state_on_click<S: MatchableState>(
+    buttons: Query<(&Interaction, &SetStateOnClick<S>), Changed<Interaction>>,
+    mut next_state: ResMut<NextMatchableState<S>>,
+) {
+    for (interaction, SetStateOnClick(state)) in buttons.iter() {
+        if interaction == &Interaction::Pressed {
+            next_state.set(state.clone());
+        }
+    }
+}
+
+/// A trait for wiring UI entities to state transitions
+pub trait StateMatchingEntityCommands {
+    /// Queue a transition into `state` whenever the entity is clicked.
+    ///
+    /// The transition is queued in `PreUpdate`, right after UI interactions are processed,
+    /// so it gets applied during the same frame's `StateTransition` schedule.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_state_matching_prototype::*;
+    /// # #[derive(States, Clone, Copy, Default, Eq, PartialEq, Hash, Debug)]
+    /// # enum AppState {
+    /// #   #[default]
+    /// #   Menu,
+    /// #   InGame,
+    /// # }
+    /// fn spawn_play_button(mut commands: Commands) {
+    ///     commands
+    ///         .spawn(ButtonBundle::default())
+    ///         .on_click_set_state(AppState::InGame);
+    /// }
+    /// ```
+    fn on_click_set_state<S: MatchableState>(&mut self, state: S) -> &mut Self;
+}
+
+impl StateMatchingEntityCommands for EntityCommands<'_, '_, '_> {
+    fn on_click_set_state<S: MatchableState>(&mut self, state: S) -> &mut Self {
+        self.insert(SetStateOnClick(state))
+    }
+}
+
+/// A trait for adding UI systems gated by state matchers to a bevy `App`
+pub trait StateMatchingUiApp {
+    /// Add `systems` reacting to UI `Interaction`s, running only while the state matches `matcher`.
+    ///
+    /// They run in `PreUpdate`, right after UI interactions are processed, so any transitions
+    /// they queue get applied during the same frame's `StateTransition` schedule.
+    fn add_ui_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self;
+}
+
+impl StateMatchingUiApp for App {
+    fn add_ui_systems_in<S: MatchableState, M: 'static, Marker>(
+        &mut self,
+        matcher: impl StateMatcher<S, M>,
+        systems: impl IntoSystemConfigs<Marker>,
+    ) -> &mut Self {
+        self.add_systems(PreUpdate, systems.after(UiSystem::Focus).run_in(matcher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StateMatchingApp, StateMatchingEntityCommands, StateMatchingUiApp};
+    use bevy::{
+        prelude::{App, Commands, Entity, Query, ResMut, Resource, Startup, State, States, With},
+        ui::Interaction,
+    };
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum AppState {
+        #[default]
+        Menu,
+        InGame,
+    }
+
+    #[derive(Resource, Default)]
+    struct Clicks(u32);
+
+    #[test]
+    fn clicked_buttons_queue_their_state() {
+        let mut app = App::new();
+        app.init_resource::<Clicks>()
+            .add_matchable_state::<AppState>()
+            .add_systems(Startup, |mut commands: Commands| {
+                commands
+                    .spawn(Interaction::None)
+                    .on_click_set_state(AppState::InGame);
+            })
+            .add_ui_systems_in(
+                AppState::Menu,
+                |buttons: Query<&Interaction>, mut clicks: ResMut<Clicks>| {
+                    clicks.0 += buttons
+                        .iter()
+                        .filter(|interaction| *interaction == &Interaction::Pressed)
+                        .count() as u32;
+                },
+            );
+        app.update();
+
+        let button = app
+            .world
+            .query_filtered::<Entity, With<Interaction>>()
+            .single(&app.world);
+        app.world.entity_mut(button).insert(Interaction::Pressed);
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<AppState>>().get(),
+            &AppState::InGame
+        );
+        assert_eq!(app.world.resource::<Clicks>().0, 1);
+
+        app.update();
+        assert_eq!(app.world.resource::<Clicks>().0, 1);
+    }
+}
@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, DetectChanges, IntoSystemConfigs, PreUpdate, Res, ResMut, Resource, State, StateTransition,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{apply_state_transition, MatchableState, NextMatchableState};
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_local_storage(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_local_storage(_: &str) -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_local_storage(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        storage.set_item(key, value).ok();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_local_storage(_: &str, _: &str) {}
+
+#[cfg(target_arch = "wasm32")]
+fn read_url_hash() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    Some(hash.trim_start_matches('#').to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_url_hash() -> Option<String> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_url_hash(hash: &str) {
+    if let Some(window) = web_sys::window() {
+        window.location().set_hash(hash).ok();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_url_hash(_: &str) {}
+
+/// The URL fragment most recently synced with `S`, by [`StateMatchingWebApp::sync_state_with_url_hash`]
+#[derive(Resource, Debug)]
+pub struct UrlHash<S: MatchableState>(String, PhantomData<fn() -> S>);
+
+impl<S: MatchableState> UrlHash<S> {
+    /// The fragment, without the leading `#`
+    pub fn get(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A trait adding browser integration for states to a bevy `App`.
+///
+/// Everything here only has an effect on `wasm32` targets, so it can be set up unconditionally.
+pub trait StateMatchingWebApp {
+    /// Persist `S` in the browser's local storage under `key`.
+    ///
+    /// The state is written whenever it changes, and restored when this is called - so
+    /// the app starts directly in the stored state, running its enter schedules as usual.
+    /// Stored values that fail to deserialize are ignored.
+    fn persist_state_to_local_storage<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+    ) -> &mut Self;
+
+    /// Keep `S` in sync with the URL fragment, in both directions.
+    ///
+    /// Whenever `S` changes, the fragment is set to `to_hash(state)` - adding a browser history entry.
+    /// Whenever the fragment changes (through a deep link, or back/forward navigation),
+    /// `from_hash(fragment)` is queued as the next state - if it returns `None`, the fragment is ignored.
+    /// The fragment present when this is called is used as the initial state.
+    fn sync_state_with_url_hash<S: MatchableState>(
+        &mut self,
+        to_hash: impl Fn(&S) -> String + Send + Sync + 'static,
+        from_hash: impl Fn(&str) -> Option<S> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl StateMatchingWebApp for App {
+    fn persist_state_to_local_storage<S: MatchableState + Serialize + DeserializeOwned>(
+        &mut self,
+        key: impl Into<String>,
+    ) -> &mut Self {
+        let key = key.into();
+        if let Some(state) =
+            read_local_storage(&key).and_then(|value| ron::from_str::<S>(&value).ok())
+        {
+            self.insert_resource(State::new(state));
+        }
+        self.add_systems(
+            StateTransition,
+            (move |state: Option<Res<State<S>>>| {
+                let Some(state) = state.filter(|state| state.is_changed()) else {
+                    return;
+                };
+                if let Ok(value) = ron::to_string(state.get()) {
+                    write_local_storage(&key, &value);
+                }
+            })
+            .after(apply_state_transition::<S>),
+        )
+    }
+
+    fn sync_state_with_url_hash<S: MatchableState>(
+        &mut self,
+        to_hash: impl Fn(&S) -> String + Send + Sync + 'static,
+        from_hash: impl Fn(&str) -> Option<S> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let initial = read_url_hash();
+        if let Some(state) = initial.as_deref().and_then(&from_hash) {
+            self.insert_resource(State::new(state));
+        }
+        self.insert_resource(UrlHash::<S>(initial.unwrap_or_default(), PhantomData))
+            .add_systems(
+                PreUpdate,
+                move |mut last: ResMut<UrlHash<S>>,
+                      next_state: Option<ResMut<NextMatchableState<S>>>| {
+                    let Some(hash) = read_url_hash().filter(|hash| hash != &last.0) else {
+                        return;
+                    };
+                    if let (Some(state), Some(mut next_state)) = (from_hash(&hash), next_state) {
+                        next_state.set(state);
+                    }
+                    last.0 = hash;
+                },
+            )
+            .add_systems(
+                StateTransition,
+                (move |state: Option<Res<State<S>>>, mut last: ResMut<UrlHash<S>>| {
+                    let Some(state) = state.filter(|state| state.is_changed()) else {
+                        return;
+                    };
+                    let hash = to_hash(state.get());
+                    if hash != last.0 {
+                        write_url_hash(&hash);
+                        last.0 = hash;
+                    }
+                })
+                .after(apply_state_transition::<S>),
+            )
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+use bevy::prelude::Resource;
+
+use crate::{MatchableState, NextMatchableState};
+
+/// A small random number generator for stochastic state machines, used by
+/// [`NextMatchableState::set_weighted`].
+///
+/// Insert one seeded with [`StateRng::seeded`] to make the choices deterministic - for tests and replays.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct StateRng(u64);
+
+impl Default for StateRng {
+    /// A generator seeded from the OS's randomness
+    fn default() -> Self {
+        Self::seeded(RandomState::new().build_hasher().finish())
+    }
+}
+
+impl StateRng {
+    /// A generator that always produces the same sequence for the same seed
+    pub fn seeded(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// The next value in `0.0..1.0`
+    pub fn next_f32(&mut self) -> f32 {
+        // splitmix64
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Pick one of the `choices`, with a probability proportional to its weight.
+    ///
+    /// Choices with a weight of zero or less are never picked. Returns `None` if no choice can be picked.
+    pub fn choose_weighted<T>(&mut self, choices: impl IntoIterator<Item = (T, f32)>) -> Option<T> {
+        let choices = choices
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0.)
+            .collect::<Vec<_>>();
+        let total = choices.iter().map(|(_, weight)| weight).sum::<f32>();
+        let mut roll = self.next_f32() * total;
+        let mut picked = None;
+        for (choice, weight) in choices {
+            picked = Some(choice);
+            if roll < weight {
+                break;
+            }
+            roll -= weight;
+        }
+        picked
+    }
+}
+
+impl<S: MatchableState> NextMatchableState<S> {
+    /// Queue a transition into one of the `choices`, picked at random with a probability
+    /// proportional to its weight - like `[(Wander, 0.7), (Idle, 0.3)]`.
+    ///
+    /// Nothing is queued if there is no choice with a positive weight. Since [`NextEntityState<S>`](crate::NextEntityState)
+    /// dereferences to a [`NextMatchableState<S>`], this works for per-entity states too.
+    pub fn set_weighted(
+        &mut self,
+        choices: impl IntoIterator<Item = (S, f32)>,
+        rng: &mut StateRng,
+    ) {
+        if let Some(state) = rng.choose_weighted(choices) {
+            self.set(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextMatchableState, StateRng};
+    use bevy::prelude::States;
+
+    #[derive(States, PartialEq, Eq, Debug, Default, Hash, Clone)]
+    enum TestState {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    #[test]
+    fn seeded_choices_are_deterministic_and_respect_weights() {
+        let choices = [(TestState::A, 0.5), (TestState::B, 0.5), (TestState::C, 0.)];
+        let picks = |seed| {
+            let mut rng = StateRng::seeded(seed);
+            (0..100)
+                .map(|_| rng.choose_weighted(choices.clone()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let first = picks(7);
+        assert_eq!(first, picks(7));
+        assert!(first.contains(&TestState::A));
+        assert!(first.contains(&TestState::B));
+        assert!(!first.contains(&TestState::C));
+
+        let mut next_state = NextMatchableState::Keep;
+        next_state.set_weighted([(TestState::C, 0.)], &mut StateRng::seeded(7));
+        assert!(matches!(next_state, NextMatchableState::Keep));
+        next_state.set_weighted([(TestState::C, 1.)], &mut StateRng::seeded(7));
+        assert!(matches!(
+            next_state,
+            NextMatchableState::Value(TestState::C)
+        ));
+    }
+}
@@ -14,6 +14,11 @@ fn main() {
         .run()
         .expect("Please fix failing tests in output above.");
 
+    // Run the tests behind feature flags, like `input`
+    cmd!("cargo test --all-features")
+        .run()
+        .expect("Please fix failing tests in output above.");
+
     // Run doc tests: these are ignored by `cargo test`
     cmd!("cargo test --doc --workspace")
         .run()